@@ -0,0 +1,95 @@
+// Throughput of ParticleMap lookups and updates on a full LEVEL_W x LEVEL_H map.
+// The same lookups are also run against a HashMap keyed by grid position (what ParticleMap used to be)
+// so the two can be compared.
+//
+// Run with: cargo bench --bench particle_map
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bevy::utils::HashMap;
+use pixelstellar::entities::particle::{components::*, resources::*};
+
+const LOOKUP_PASSES: usize = 10;
+const UPDATE_PASSES: usize = 3;
+
+fn report(name: &str, ops: usize, elapsed: Duration) {
+    let per_sec = ops as f64 / elapsed.as_secs_f64();
+    println!("{name:<28} {ops:>10} ops in {:>9.2?}  ({:>7.2} Mops/s)", elapsed, per_sec / 1_000_000.);
+}
+
+fn all_positions() -> Vec<(i32, i32)> {
+    (MIN_Y..MAX_Y).flat_map(|y| (MIN_X..MAX_X).map(move |x| (x, y))).collect()
+}
+
+// Looks at a cell and the 5 neighbours update_water checks, like the automata do every frame
+fn neighbour_lookups(get: impl Fn((i32, i32)) -> ParticleElement, positions: &[(i32, i32)]) -> usize {
+    let mut water = 0;
+    for &(x, y) in positions {
+        for pos in [(x, y), (x, y-1), (x-1, y-1), (x+1, y-1), (x-1, y), (x+1, y)] {
+            if get(pos) == ParticleElement::Water {
+                water += 1;
+            }
+        }
+    }
+    water
+}
+
+fn main() {
+    let mut map = ParticleMap::new();
+    let positions = all_positions();
+    println!("map: {} x {} cells ({} total)\n", MAX_X - MIN_X, MAX_Y - MIN_Y, positions.len());
+
+    // Fill the whole level, alternating stone and water rows
+    let start = Instant::now();
+    for &(x, y) in &positions {
+        if y % 2 == 0 {
+            map.insert_at(ParticleElement::Stone, (x, y), ListType::All);
+        } else {
+            map.insert_at(ParticleElement::Water, (x, y), ListType::All);
+        }
+    }
+    report("insert_at (fill)", positions.len(), start.elapsed());
+
+    // Lookups
+    let start = Instant::now();
+    for _ in 0..LOOKUP_PASSES {
+        black_box(neighbour_lookups(|pos| map.get_element_at(pos), &positions));
+    }
+    report("get_element_at", positions.len() * 6 * LOOKUP_PASSES, start.elapsed());
+
+    let hashed: HashMap<(i32, i32), ParticleElement> = positions.iter()
+        .map(|&pos| (pos, map.get_element_at(pos)))
+        .collect();
+    let start = Instant::now();
+    for _ in 0..LOOKUP_PASSES {
+        black_box(neighbour_lookups(|pos| hashed.get(&pos).copied().unwrap_or(ParticleElement::Air), &positions));
+    }
+    report("HashMap lookup (reference)", positions.len() * 6 * LOOKUP_PASSES, start.elapsed());
+
+    // Updates: every water cell is deleted and put back, which is what a moving particle used to cost
+    let water: Vec<(i32, i32)> = positions.iter().copied().filter(|&(_, y)| y % 2 != 0).collect();
+    let start = Instant::now();
+    for _ in 0..UPDATE_PASSES {
+        for &pos in &water {
+            map.delete_at(pos);
+            map.insert_at(ParticleElement::Water, pos, ListType::OnlyAir);
+        }
+    }
+    report("delete_at + insert_at", water.len() * UPDATE_PASSES, start.elapsed());
+
+    // ...and swapped with the stone under it, which is what it costs now
+    let start = Instant::now();
+    for _ in 0..UPDATE_PASSES {
+        for &(x, y) in &water {
+            map.swap((x, y), (x, y - 1));
+        }
+    }
+    report("swap", water.len() * UPDATE_PASSES, start.elapsed());
+
+    // Rays across the whole level, stopping at the first solid cell
+    let start = Instant::now();
+    for y in MIN_Y..MAX_Y {
+        black_box(map.ray((MIN_X, y), (MAX_X, y), ListType::Whitelist(vec![ParticleElement::Air, ParticleElement::Water])));
+    }
+    report("ray (full width)", (MAX_Y - MIN_Y) as usize, start.elapsed());
+}
@@ -0,0 +1,137 @@
+// The particle automata on Planet 1's terrain, run the old whole map way, split into chunks on one thread
+// and split into chunks on the compute task pool (ParticleUpdateMode). Water and sand are poured over the
+// whole level first so there is something to update, the terrain on its own falls asleep after one step.
+// The chunked runs have to match each other, the tests in entities/particle/systems.rs check that too.
+// Then a lake as wide as the level (like on planet 2 or 7) with a heap of water at one end, to see what
+// levelling big bodies of liquid costs (level_liquids in the same file).
+//
+// Run with: cargo bench --bench particle_update
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use pixelstellar::common::{gravity::GravityResource, seed::{SimulationRng, SimulationSeed}, state::GamePhase, timestep::SimulationStep};
+use pixelstellar::entities::particle::{components::*, resources::*, systems::ParticlePlugin};
+use pixelstellar::planets::definition::Planets;
+use pixelstellar::worldgen::WorldGen;
+
+const SEED: u64 = 1;
+const STEPS: usize = 300;
+const POUR_HEIGHT: i32 = 70; // rows of water and sand at the top of the level
+const LAKE_DEPTH: i32 = 60;  // rows of water in the lake
+const HEAP: (i32, i32) = (200, 40); // columns and rows of extra water at the left end of the lake
+
+fn planet_1_app(mode: ParticleUpdateMode) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, ParticlePlugin));
+    app.insert_resource(mode);
+    app.insert_resource(SimulationRng::new(SimulationSeed(SEED)));
+    app.insert_resource(GravityResource::new(3600., 750.));
+
+    let mut map = app.world_mut().resource_mut::<ParticleMap>();
+    map.reseed(SEED);
+    let worldgen = WorldGen::new(Planets::builtin().get(GamePhase(0)).terrain.clone(), &SimulationSeed(SEED), map.registry());
+    worldgen.generate_level(&mut map, &SimulationSeed(SEED));
+    for x in MIN_X..MAX_X {
+        for y in MAX_Y - POUR_HEIGHT..MAX_Y {
+            // blocks of 16 columns, so sand sinks through water and water fills in around it
+            let element = if (x / 16) % 2 == 0 { ParticleElement::Water } else { ParticleElement::Sand };
+            map.insert_at(element, (x, y), ListType::OnlyAir);
+        }
+    }
+    app
+}
+
+// position, element, colour, velocity and temperature as bits, sediment
+type Cell = ((i32, i32), u16, [u8; 4], [u32; 3], u16);
+
+// everything in every cell, not just the elements
+fn checksum(map: &ParticleMap) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut cells: Vec<Cell> = map.positions_where(|_| true).into_iter()
+        .map(|pos| {
+            let particle = map.get_particle_at(pos).unwrap();
+            let floats = [particle.velocity.x.to_bits(), particle.velocity.y.to_bits(), particle.temperature.to_bits()];
+            (pos, particle.element.0, particle.color, floats, particle.sediment.0)
+        })
+        .collect();
+    cells.sort();
+    cells.hash(&mut hasher);
+    hasher.finish()
+}
+
+// a stone basin over the whole level, filled with water and more of it heaped up at the left
+fn lake_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, ParticlePlugin));
+    app.insert_resource(SimulationRng::new(SimulationSeed(SEED)));
+    app.insert_resource(GravityResource::new(3600., 750.));
+
+    let mut map = app.world_mut().resource_mut::<ParticleMap>();
+    map.reseed(SEED);
+    let bottom = MIN_Y + 10;
+    for x in MIN_X..MAX_X {
+        for y in MIN_Y..bottom {
+            map.insert_at(ParticleElement::Stone, (x, y), ListType::All);
+        }
+        let wall = !(MIN_X + 4..MAX_X - 4).contains(&x);
+        let top = if x < MIN_X + HEAP.0 { bottom + LAKE_DEPTH + HEAP.1 } else { bottom + LAKE_DEPTH };
+        for y in bottom..bottom + LAKE_DEPTH + HEAP.1 + 10 {
+            if wall {
+                map.insert_at(ParticleElement::Stone, (x, y), ListType::All);
+            } else if y < top {
+                map.insert_at(ParticleElement::Water, (x, y), ListType::All);
+            }
+        }
+    }
+    app
+}
+
+fn run_app(mut app: App) -> (Duration, u64) {
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        app.world_mut().run_schedule(SimulationStep);
+    }
+    let elapsed = start.elapsed();
+    (elapsed, checksum(app.world().resource::<ParticleMap>()))
+}
+
+fn run(mode: ParticleUpdateMode) -> (Duration, u64) {
+    run_app(planet_1_app(mode))
+}
+
+fn report(name: &str, elapsed: Duration) {
+    println!("{name:<10} {STEPS} steps in {:>9.2?}  ({:>7.2?} per step)", elapsed, elapsed / STEPS as u32);
+}
+
+fn main() {
+    let map = planet_1_app(ParticleUpdateMode::WholeMap);
+    let map = map.world().resource::<ParticleMap>();
+    println!("planet 1: {} particles in {} chunks, {} of them poured\n",
+        map.positions_where(|_| true).len(),
+        map.loaded_chunks().len(),
+        map.positions_where(|particle| particle.element == ParticleElement::Water || particle.element == ParticleElement::Sand).len(),
+    );
+
+    let (whole_map, _) = run(ParticleUpdateMode::WholeMap);
+    report("whole map", whole_map);
+    let (serial, serial_sum) = run(ParticleUpdateMode::Serial);
+    report("serial", serial);
+    let (parallel, first) = run(ParticleUpdateMode::Parallel);
+    report("parallel", parallel);
+    println!("speedup    {:.2}x over the whole map, {:.2}x over serial",
+        whole_map.as_secs_f64() / parallel.as_secs_f64(),
+        serial.as_secs_f64() / parallel.as_secs_f64(),
+    );
+
+    let (_, second) = run(ParticleUpdateMode::Parallel);
+    assert_eq!(first, second, "two parallel runs came out different");
+    assert_eq!(first, serial_sum, "the serial and parallel runs came out different");
+    println!("serial and parallel runs match");
+
+    let lake = lake_app();
+    let water = lake.world().resource::<ParticleMap>().positions_where(|particle| particle.element == ParticleElement::Water).len();
+    println!("\nlake: {water} water cells, {} wide", MAX_X - MIN_X - 8);
+    let (lake, _) = run_app(lake);
+    report("lake", lake);
+}
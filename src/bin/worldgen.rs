@@ -0,0 +1,239 @@
+// Makes a planet's terrain without the game (no window, no GPU) and saves it as a PNG, so generators can be
+// looked at and checked for changes quickly. Every cell is drawn in its element's colour over the sky colour,
+// one pixel per cell, the top of the image is the top of the level.
+// Chunked planets go on forever, only the level area (what a normal planet is) is made.
+//
+// Run with: cargo run --bin pixelstellar-worldgen -- <planet> [options]
+//   <planet>          the planet's name (i.e. planet_4) or where it comes in the game (1 is the first planet)
+//   --seed <n>        seed to make it with (PIXELSTELLAR_SEED, or 0)
+//   --out <file>      where the PNG goes (<planet>_<seed>.png)
+//   --stats <file>    also write element counts, cave coverage and the surface height range as JSON
+//   --assets <dir>    where the planet and element files are read from (assets)
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+use serde::Serialize;
+
+use pixelstellar::common::{seed::{SimulationSeed, SEED_ENV_VAR}, state::GamePhase};
+use pixelstellar::entities::particle::{components::ParticleElement, elements::*, resources::*};
+use pixelstellar::planets::definition::*;
+use pixelstellar::worldgen::WorldGen;
+
+const SKY: [u8; 3] = [135, 206, 235]; // the ClearColor in main.rs
+
+struct Options {
+    planet: String,
+    seed: u64,
+    out: Option<PathBuf>,
+    stats: Option<PathBuf>,
+    assets: PathBuf,
+}
+
+#[derive(Serialize)]
+struct SurfaceRange {
+    min: i32,
+    max: i32,
+}
+
+#[derive(Serialize)]
+struct Stats {
+    planet: String,
+    seed: u64,
+    width: u32,
+    height: u32,
+    elements: BTreeMap<String, usize>,  // cells of every element in the level, air left out
+    cave_coverage: f32,                 // share of the cells under the surface that are empty
+    surface: SurfaceRange,              // lowest and highest surface in the level, in cells
+}
+
+fn usage() -> String {
+    "usage: pixelstellar-worldgen <planet> [--seed <n>] [--out <file.png>] [--stats <file.json>] [--assets <dir>]".into()
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut planet = None;
+    let mut options = Options {
+        planet: String::new(),
+        seed: std::env::var(SEED_ENV_VAR).ok().and_then(|seed| seed.parse().ok()).unwrap_or(0),
+        out: None,
+        stats: None,
+        assets: PathBuf::from("assets"),
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value\n{}", usage()));
+        match arg.as_str() {
+            "--seed" => options.seed = value()?.parse().map_err(|_| format!("the seed has to be a number\n{}", usage()))?,
+            "--out" => options.out = Some(value()?.into()),
+            "--stats" => options.stats = Some(value()?.into()),
+            "--assets" => options.assets = value()?.into(),
+            "-h" | "--help" => return Err(usage()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}\n{}", usage())),
+            _ => planet = Some(arg),
+        }
+    }
+    options.planet = planet.ok_or_else(usage)?;
+    Ok(options)
+}
+
+// the same files the game loads, or the ones compiled into the game if there's no assets folder
+fn load_planets(assets: &Path) -> Result<Planets, String> {
+    let folder = assets.join(PLANETS_FOLDER);
+    let Ok(entries) = std::fs::read_dir(&folder) else {
+        return Ok(Planets::builtin())
+    };
+    let mut list = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if !path.to_string_lossy().ends_with(".planet.ron") {
+            continue
+        }
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        list.push(PlanetDefinition::from_ron(&text).map_err(|e| format!("{}: {e}", path.display()))?);
+    }
+    Planets::new(list).map_err(|e| format!("{}: {e}", folder.display()))
+}
+
+fn load_elements(assets: &Path) -> Result<ElementRegistry, String> {
+    let path = assets.join(ELEMENTS_ASSET);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => ElementRegistry::from_ron(&text).map_err(|e| format!("{}: {e}", path.display())),
+        Err(_) => Ok(ElementRegistry::builtin()),
+    }
+}
+
+fn find_planet<'a>(planets: &'a Planets, planet: &str) -> Result<&'a PlanetDefinition, String> {
+    let phase = match planet.parse::<usize>() {
+        Ok(number) if (1..=planets.iter().count()).contains(&number) => Some(GamePhase(number - 1)),
+        _ => planets.by_name(planet),
+    };
+    let names: Vec<&str> = planets.iter().map(|planet| planet.name.as_str()).collect();
+    phase.map(|phase| planets.get(phase)).ok_or_else(|| format!("there's no planet {planet}, the planets are {}", names.join(", ")))
+}
+
+fn render(map: &ParticleMap) -> RgbaImage {
+    let (width, height) = ((MAX_X - MIN_X + 1) as u32, (MAX_Y - MIN_Y + 1) as u32);
+    RgbaImage::from_fn(width, height, |px, py| {
+        let pos = (MIN_X + px as i32, MAX_Y - py as i32);
+        let Some(particle) = map.get_particle_at(pos) else {
+            return image::Rgba([SKY[0], SKY[1], SKY[2], 255])
+        };
+        // see-through elements (gases, water) are drawn over the sky like in the game
+        let alpha = particle.color[3] as f32 / 255.;
+        let blend = |c: usize| (particle.color[c] as f32 * alpha + SKY[c] as f32 * (1. - alpha)) as u8;
+        image::Rgba([blend(0), blend(1), blend(2), 255])
+    })
+}
+
+fn stats(map: &ParticleMap, worldgen: &WorldGen, planet: &str, seed: u64) -> Stats {
+    let mut elements = BTreeMap::new();
+    let (mut under, mut open) = (0, 0);
+    let (mut lowest, mut highest) = (i32::MAX, i32::MIN);
+    for x in MIN_X..=MAX_X {
+        let surface = worldgen.column(x).surface;
+        lowest = lowest.min(surface);
+        highest = highest.max(surface);
+        for y in MIN_Y..=MAX_Y {
+            let element = map.get_element_at((x, y));
+            if y <= surface {
+                under += 1;
+                if element == ParticleElement::Air {
+                    open += 1;
+                }
+            }
+            if element != ParticleElement::Air {
+                *elements.entry(map.registry().get(element).name.clone()).or_insert(0) += 1;
+            }
+        }
+    }
+    Stats {
+        planet: planet.into(),
+        seed,
+        width: (MAX_X - MIN_X + 1) as u32,
+        height: (MAX_Y - MIN_Y + 1) as u32,
+        elements,
+        cave_coverage: if under == 0 { 0. } else { open as f32 / under as f32 },
+        surface: SurfaceRange { min: lowest, max: highest },
+    }
+}
+
+fn run() -> Result<(), String> {
+    let options = parse_args()?;
+    let planets = load_planets(&options.assets)?;
+    let planet = find_planet(&planets, &options.planet)?;
+    let seed = SimulationSeed(options.seed);
+
+    let mut map = ParticleMap::new();
+    map.set_registry(load_elements(&options.assets)?);
+    map.reseed(seed.0);
+    let worldgen = WorldGen::new(planet.terrain.clone(), &seed, map.registry());
+    worldgen.generate_level(&mut map, &seed);
+
+    let out = options.out.unwrap_or_else(|| format!("{}_{}.png", planet.name, seed.0).into());
+    render(&map).save(&out).map_err(|e| format!("{}: {e}", out.display()))?;
+    println!("{} (seed {}) -> {}", planet.name, seed.0, out.display());
+
+    if let Some(path) = options.stats {
+        let stats = stats(&map, &worldgen, &planet.name, seed.0);
+        let json = serde_json::to_string_pretty(&stats).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("{}: {e}", path.display()))?;
+        println!("stats -> {}", path.display());
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // same as run() without the files
+    fn generate(planet: &str, seed: u64) -> (ParticleMap, WorldGen) {
+        let assets = Path::new("assets");
+        let planets = load_planets(assets).unwrap();
+        let planet = find_planet(&planets, planet).unwrap();
+        let seed = SimulationSeed(seed);
+        let mut map = ParticleMap::new();
+        map.set_registry(load_elements(assets).unwrap());
+        map.reseed(seed.0);
+        let worldgen = WorldGen::new(planet.terrain.clone(), &seed, map.registry());
+        worldgen.generate_level(&mut map, &seed);
+        (map, worldgen)
+    }
+
+    // FNV-1a, so the number doesn't change between rust versions like DefaultHasher could
+    fn checksum(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+    }
+
+    #[test]
+    fn planets_are_found_by_number_or_name() {
+        let planets = load_planets(Path::new("assets")).unwrap();
+        assert_eq!(find_planet(&planets, "1").unwrap().name, "planet_1");
+        assert_eq!(find_planet(&planets, "planet_4").unwrap().name, "planet_4");
+        assert!(find_planet(&planets, "0").is_err());
+        assert!(find_planet(&planets, "planet_99").unwrap_err().contains("planet_1"));
+    }
+
+    // if one of these changes a generator changed what planet 1 looks like, check the PNG before updating them
+    #[test]
+    fn planet_1_seed_7_comes_out_the_same() {
+        let (map, worldgen) = generate("planet_1", 7);
+        let stats = stats(&map, &worldgen, "planet_1", 7);
+        let elements: Vec<(&str, usize)> = stats.elements.iter().map(|(name, count)| (name.as_str(), *count)).collect();
+        assert_eq!(elements, [("BedRock", 95452), ("Dirt", 37427), ("Grass", 1795), ("Stone", 24104)]);
+        assert_eq!((stats.surface.min, stats.surface.max), (-28, 18));
+        assert!((stats.cave_coverage - 0.08206483).abs() < 1e-6, "cave coverage is {}", stats.cave_coverage);
+
+        let image = render(&map);
+        assert_eq!((image.width(), image.height()), (stats.width, stats.height));
+        assert_eq!(checksum(image.as_raw()), 10791421743002060140);
+    }
+}
@@ -1,5 +1,26 @@
 use rand::Rng;
 
+/* Cave automaton. Every cell starts out as wall with a chance of wall_prob, then each step a wall cell stays
+   a wall if it has at least survival_limit wall neighbours and an open cell fills in if it has at least
+   birth_limit, which smooths the noise out into caves. Cells outside the grid count as walls, so the caves
+   close up towards the edges.
+   The rng is passed in, world generation hands it a stream from the seed (see worldgen/caves.rs).
+*/
+pub struct CaveGrid {
+    pub width: usize,
+    pub height: usize,
+    walls: Vec<bool>,
+}
+
+impl CaveGrid {
+    pub fn is_wall(&self, x: isize, y: isize) -> bool {
+        if x < 0 || y < 0 || x >= self.width as isize || y >= self.height as isize {
+            return true
+        }
+        self.walls[y as usize * self.width + x as usize]
+    }
+}
+
 pub fn generate_cave(
     width: usize,
     height: usize,
@@ -7,8 +28,9 @@ pub fn generate_cave(
     steps: usize,
     birth_limit: usize,
     survival_limit: usize,
-) -> Vec<Vec<u8>> {
-    let mut grid = initialize_grid(width, height, wall_prob);
+    rng: &mut impl Rng,
+) -> CaveGrid {
+    let mut grid = initialize_grid(width, height, wall_prob, rng);
 
     for _ in 0..steps {
         grid = simulate_step(&grid, birth_limit, survival_limit);
@@ -17,40 +39,33 @@ pub fn generate_cave(
     grid
 }
 
-pub fn initialize_grid(width: usize, height: usize, wall_prob: f32) -> Vec<Vec<u8>> {
-    let mut rng = rand::thread_rng();
-    (0..height)
-        .map(|_| {
-            (0..width)
-                .map(|_| if rng.gen::<f32>() < wall_prob { 1 } else { 0 })
-                .collect()
-        })
-        .collect()
+pub fn initialize_grid(width: usize, height: usize, wall_prob: f32, rng: &mut impl Rng) -> CaveGrid {
+    CaveGrid {
+        width,
+        height,
+        walls: (0..width * height).map(|_| rng.gen::<f32>() < wall_prob).collect(),
+    }
 }
 
-pub fn simulate_step(grid: &Vec<Vec<u8>>, birth_limit: usize, survival_limit: usize) -> Vec<Vec<u8>> {
-    let height = grid.len();
-    let width = grid[0].len();
-    let mut new_grid = vec![vec![0; width]; height];
+pub fn simulate_step(grid: &CaveGrid, birth_limit: usize, survival_limit: usize) -> CaveGrid {
+    let mut walls = Vec::with_capacity(grid.walls.len());
 
-    for y in 0..height {
-        for x in 0..width {
+    for y in 0..grid.height as isize {
+        for x in 0..grid.width as isize {
             let wall_neighbors = count_wall_neighbors(grid, x, y);
 
-            if grid[y][x] == 1 {
-                new_grid[y][x] = if wall_neighbors >= survival_limit { 1 } else { 0 };
+            if grid.is_wall(x, y) {
+                walls.push(wall_neighbors >= survival_limit);
             } else {
-                new_grid[y][x] = if wall_neighbors >= birth_limit { 1 } else { 0 };
+                walls.push(wall_neighbors >= birth_limit);
             }
         }
     }
 
-    new_grid
+    CaveGrid { width: grid.width, height: grid.height, walls }
 }
 
-pub fn count_wall_neighbors(grid: &Vec<Vec<u8>>, x: usize, y: usize) -> usize {
-    let height = grid.len();
-    let width = grid[0].len();
+pub fn count_wall_neighbors(grid: &CaveGrid, x: isize, y: isize) -> usize {
     let mut count = 0;
 
     for dy in -1..=1 {
@@ -58,15 +73,11 @@ pub fn count_wall_neighbors(grid: &Vec<Vec<u8>>, x: usize, y: usize) -> usize {
             if dx == 0 && dy == 0 {
                 continue;
             }
-            let nx = x as isize + dx;
-            let ny = y as isize + dy;
-            if nx >= 0 && ny >= 0 && nx < width as isize && ny < height as isize {
-                count += grid[ny as usize][nx as usize];
-            } else {
+            if grid.is_wall(x + dx, y + dy) {
                 count += 1;
             }
         }
     }
 
-    count.into()
-}
\ No newline at end of file
+    count
+}
@@ -1,10 +1,12 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
 // Gravitational acceleration: 1px/frame^2 @60Hz == 3600px/s^2
 // const GRAVITY_FORCE: f32 = 3600.;
 // const TERMINAL_VELOCITY: f32 = 750.;
 
-#[derive(Resource)]
+// planets set their own in their file (see assets/planets)
+#[derive(Resource, Deserialize, Clone, Debug)]
 pub struct GravityResource {
     pub gravity_force: f32,
     pub terminal_velocity: f32,
@@ -19,6 +21,12 @@ impl GravityResource {
     }
 }
 
+impl Default for GravityResource {
+    fn default() -> Self {
+        Self::new(3600., 750.)
+    }
+}
+
 #[derive(Component)]
 pub struct Gravity {
     current_g: f32, // I think this is current y velocity
@@ -44,7 +52,7 @@ impl Gravity {
     pub fn update_gravity(
         curr_velocity: &f32,
         deltat: &f32,
-        grav_res: &ResMut<GravityResource>,
+        grav_res: &GravityResource,
     ) -> f32{
         let (gravity_force, terminal_velocity) = (grav_res.gravity_force, grav_res.terminal_velocity);
         f32::max(-terminal_velocity, curr_velocity - (gravity_force/70.) * deltat)
@@ -61,7 +69,7 @@ impl Gravity {
 pub fn initialize( //initializes resource
    commands: &mut Commands,
 ) {
-    (*commands).insert_resource(GravityResource::new(3600., 750.));
+    (*commands).insert_resource(GravityResource::default());
 }
 
 pub fn change_gravity( //changes gravity
@@ -1,12 +1,10 @@
 use bevy::prelude::*;
 
 use crate::entities::particle::components::ParticleElement;
-use crate::entities::particle::resources::convert_to_grid_position;
-use crate::entities::particle::systems::*;
+use crate::entities::particle::resources::{convert_to_grid_position, ChargeField, PARTICLE_SIZE};
 
 use crate::{
     entities::particle::resources::ParticleMap,
-    entities::particle::components::ParticleTagQuickSand,
     entities::player::components::Player,
     entities::enemy::components::Enemy,
     entities::spaceship::components::Spaceship,
@@ -40,7 +38,24 @@ impl Hitbox {
         let other_tr = other.offset + Vec2::new(other.width,other.height)/2.0;
         self_tr.x > other_bl.x && self_bl.x < other_tr.x && self_tr.y > other_bl.y && self_bl.y < other_tr.y
     }
-    pub fn all_player_collisions(&self, hitboxes: &Query<&Hitbox, Without<Player>>)  -> bool {
+    // particles dont have a Hitbox, so check the cells of the map that overlap this hitbox and block the player instead.
+    // particle (x, y) covers x*PARTICLE_SIZE..(x+1)*PARTICLE_SIZE, same as where render_particles draws it
+    pub fn collides_with_particles(&self, map: &ParticleMap) -> bool {
+        let bl = (self.offset - Vec2::new(self.width, self.height) / 2.0) / PARTICLE_SIZE;
+        let tr = (self.offset + Vec2::new(self.width, self.height) / 2.0) / PARTICLE_SIZE;
+        for x in (bl.x.floor() as i32)..(tr.x.ceil() as i32) {
+            for y in (bl.y.floor() as i32)..(tr.y.ceil() as i32) {
+                if map.registry().get(map.get_element_at((x, y))).blocks_player {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+    pub fn all_player_collisions(&self, hitboxes: &Query<&Hitbox, Without<Player>>, map: &ParticleMap)  -> bool {
+        if self.collides_with_particles(map) {
+            return true;
+        }
         for hitbox in hitboxes.iter() {
             if self.collides_with(hitbox) {
                 //info!("Collision detected between {:?} and {:?}", self, hitbox);
@@ -57,7 +72,10 @@ impl Hitbox {
         }
         false
     }
-    pub fn all_enemy_collisions(&self, hitboxes: &Query<&Hitbox, Without<Enemy>>)  -> bool {
+    pub fn all_enemy_collisions(&self, hitboxes: &Query<&Hitbox, Without<Enemy>>, map: &ParticleMap)  -> bool {
+        if self.collides_with_particles(map) {
+            return true;
+        }
         for hitbox in hitboxes.iter() {
             if self.collides_with(hitbox) {
                 //info!("Enemy Collision detected between {:?} and {:?}", self, hitbox);
@@ -66,7 +84,10 @@ impl Hitbox {
         }
         false
     }
-    pub fn all_ship_collisions(&self, hitboxes: &Query<&Hitbox, Without<Spaceship>>)  -> bool {
+    pub fn all_ship_collisions(&self, hitboxes: &Query<&Hitbox, Without<Spaceship>>, map: &ParticleMap)  -> bool {
+        if self.collides_with_particles(map) {
+            return true;
+        }
         for hitbox in hitboxes.iter() {
             if self.collides_with(hitbox) {
                 return true;
@@ -109,99 +130,43 @@ impl Hitbox {
     }
 
     pub fn ratio_of_water_grid_tiles(&self, map: &ResMut<ParticleMap>) -> f32 {
-        let (top_left_x, top_left_y, bottom_right_x, bottom_right_y) = self.get_grid_tiles_to_check();
-        let mut count = 0;
-        let mut no_count = 0;
-        for x in top_left_x..=bottom_right_x {
-            for y in bottom_right_y..=top_left_y {
-                if map.get_element_at((x, y)) == ParticleElement::Water {
-                    count+=1;
-                }
-                else {
-                    no_count+=1;
-                }
-            }   
-        }
-        count as f32 / (count + no_count) as f32
+        self.ratio_of_element_grid_tiles(map, ParticleElement::Water)
     }
 
     pub fn ratio_of_lava_grid_tiles(&self, map: &ResMut<ParticleMap>) -> f32 {
-        let (top_left_x, top_left_y, bottom_right_x, bottom_right_y) = self.get_grid_tiles_to_check();
-        let mut count = 0;
-        let mut no_count = 0;
-        for x in top_left_x..=bottom_right_x {
-            for y in bottom_right_y..=top_left_y {
-                if map.get_element_at((x, y)) == ParticleElement::Lava {
-                    count+=1;
-                }
-                else {
-                    no_count+=1;
-                }
-            }   
-        }
-        count as f32 / (count + no_count) as f32
+        self.ratio_of_element_grid_tiles(map, ParticleElement::Lava)
     }
   
     pub fn ratio_of_quicksand_grid_tiles(&self, map: &ResMut<ParticleMap>) -> f32 {
-        let (top_left_x, top_left_y, bottom_right_x, bottom_right_y) = self.get_grid_tiles_to_check();
-        let mut count = 0;
-        let mut no_count = 0;
-        for x in top_left_x..=bottom_right_x {
-            for y in bottom_right_y..=top_left_y {
-                if map.get_element_at((x, y)) == ParticleElement::QuickSand {
-                    count+=1;
-                }
-                else {
-                    no_count+=1;
-                }
-            }   
-        }
-        count as f32 / (count + no_count) as f32
+        self.ratio_of_element_grid_tiles(map, ParticleElement::QuickSand)
     }
     pub fn ratio_of_slime_grid_tiles(&self, map: &ResMut<ParticleMap>) -> f32 {
-        let (top_left_x, top_left_y, bottom_right_x, bottom_right_y) = self.get_grid_tiles_to_check();
-        let mut count = 0;
-        let mut no_count = 0;
-        for x in top_left_x..=bottom_right_x {
-            for y in bottom_right_y..=top_left_y {
-                if map.get_element_at((x, y)) == ParticleElement::Slime {
-                    count+=1;
-                }
-                else {
-                    no_count+=1;
-                }
-            }   
-        }
-        count as f32 / (count + no_count) as f32
+        self.ratio_of_element_grid_tiles(map, ParticleElement::Slime)
     }
 
     pub fn ratio_of_healing_spring_grid_tiles(&self, map: &ResMut<ParticleMap>) -> f32 {
-        let (top_left_x, top_left_y, bottom_right_x, bottom_right_y) = self.get_grid_tiles_to_check();
-        let mut count = 0;
-        let mut no_count = 0;
-        for x in top_left_x..=bottom_right_x {
-            for y in bottom_right_y..=top_left_y {
-                if map.get_element_at((x, y)) == ParticleElement::Healing_Spring {
-                    count+=1;
-                }
-                else {
-                    no_count+=1;
-                }
-            }   
-        }
-        count as f32 / (count + no_count) as f32
+        self.ratio_of_element_grid_tiles(map, ParticleElement::Healing_Spring)
     }
       
       
       
       
     pub fn ratio_of_toxic_gas_tiles(&self, map: &ResMut<ParticleMap>) -> f32 {
+        self.ratio_of_element_grid_tiles(map, ParticleElement::ToxicGas)
+    }
+
+    pub fn ratio_of_fire_grid_tiles(&self, map: &ResMut<ParticleMap>) -> f32 {
+        self.ratio_of_element_grid_tiles(map, ParticleElement::Fire)
+    }
+
+    // how much of the hitbox is covered by one element, 0 to 1. works for any element in the ElementRegistry
+    pub fn ratio_of_element_grid_tiles(&self, map: &ParticleMap, element: ParticleElement) -> f32 {
         let (top_left_x, top_left_y, bottom_right_x, bottom_right_y) = self.get_grid_tiles_to_check();
         let mut count = 0;
         let mut no_count = 0;
         for x in top_left_x..=bottom_right_x {
             for y in bottom_right_y..=top_left_y {
-                if map.get_element_at((x, y)) == ParticleElement::ToxicGas {
+                if map.get_element_at((x, y)) == element {
                     count+=1;
                 }
                 else {
@@ -233,6 +198,12 @@ impl Hitbox {
         count
     }
 
+    // charged cells hurt when they're inside the hitbox or right next to it (standing on a copper floor)
+    pub fn is_touching_charge(&self, charge: &ChargeField) -> bool {
+        let (top_left_x, top_left_y, bottom_right_x, bottom_right_y) = self.get_grid_tiles_to_check();
+        (top_left_x - 1..=bottom_right_x + 1).any(|x| (bottom_right_y - 1..=top_left_y + 1).any(|y| charge.is_charged((x, y))))
+    }
+
     pub fn is_particle_in_hitbox(&self, (x,y): (i32, i32)) -> bool {
         let (top_left_x, top_left_y, bottom_right_x, bottom_right_y) = self.get_grid_tiles_to_check();
         x >= top_left_x && x <= bottom_right_x && y <= top_left_y && y >= bottom_right_y
@@ -1,7 +1,11 @@
 pub mod gravity;
 pub mod hitbox;
 pub mod perlin_noise;
+pub mod save;
+pub mod seed;
 pub mod cellular_automata;
 pub mod ui;
 pub mod state;
-pub mod death;
\ No newline at end of file
+pub mod timestep;
+pub mod death;
+pub mod wind;
\ No newline at end of file
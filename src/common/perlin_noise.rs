@@ -1,6 +1,6 @@
 use rand::{
     seq::SliceRandom,
-    thread_rng,
+    Rng,
 };
 
 pub fn get_1d_octaves(x: f32, 
@@ -72,13 +72,13 @@ pub fn get_2d_pn_value(x: f32, y: f32, amplitude: f32, frequency: f32, permutati
     noise_value
 }
 
-pub fn generate_permutation_array() -> [usize; 512] {
+// the rng should come from the SimulationSeed (see common/seed.rs) so the same seed gives the same terrain
+pub fn generate_permutation_array(rng: &mut impl Rng) -> [usize; 512] {
     let mut perm: [usize; 256] = [0; 256];
     for i in 0..256 {
         perm[i] = i;
     }
-    let mut rng = thread_rng();
-    perm.shuffle(&mut rng);
+    perm.shuffle(rng);
 
     let mut perm_extended: [usize; 512] = [0; 512];
     for i in 0..512 {
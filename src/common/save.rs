@@ -0,0 +1,626 @@
+use std::{fmt, fs, path::{Path, PathBuf}};
+
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::common::{
+    gravity::GravityResource,
+    hitbox::Hitbox,
+    seed::SimulationSeed,
+    state::{AppState, GamePhase},
+    timestep::Interpolated,
+};
+use crate::entities::{
+    enemy::{
+        components::{Enemy, EnemyHealth, EnemyKind, Jump, Velocity as EnemyVelocity},
+        systems::{enemy_sprite, spawn_enemy},
+    },
+    particle::{components::*, resources::*},
+    player::{
+        blaster::components::{BlasterSelection, BlasterType, ChangeBlasterEvent},
+        components::{Health, Player, Velocity},
+    },
+};
+use crate::planets::definition::{CurrentPlanet, Planets};
+
+/* Save games. F5 saves while playing, and the game saves on its own when it is closed mid-planet.
+   The main menu gets a Load button when there is a save, which puts you back on the same planet
+   exactly where you left off.
+
+   The file is "PXSV", the format version (u32, little endian) and then the SaveGame struct in bincode.
+   Bump SAVE_VERSION whenever SaveGame changes, old saves are refused instead of loaded as garbage.
+   Set PIXELSTELLAR_SAVE_RON=1 to also write a readable savegame.ron next to the binary. Either file can be loaded,
+   so a save can be edited by hand for debugging by pointing SaveSettings::path at the .ron.
+
+   Elements are stored with the element names of the registry they were saved with, so a save still
+   loads after elements were added to or reordered in default.elements.ron.
+   The rng streams are not saved, they restart from the seed when the save is loaded.
+*/
+pub const SAVE_VERSION: u32 = 1;
+pub const SAVE_PATH: &str = "saves/savegame.bin";
+pub const SAVE_RON_ENV_VAR: &str = "PIXELSTELLAR_SAVE_RON";
+const SAVE_MAGIC: &[u8; 4] = b"PXSV";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveGame {
+    pub version: u32,
+    pub seed: u64,
+    pub planet: String,  // the planet's name, see assets/planets
+    pub gravity: SavedGravity,
+    pub player: SavedPlayer,
+    pub blaster: BlasterType,
+    pub enemies: Vec<SavedEnemy>,
+    // element names by index, at the time of saving
+    pub elements: Vec<String>,
+    pub chunks: Vec<SavedChunk>,
+    // chunks the planet has generated so far (ChunkList), only on chunked planets. chunks that were dug out
+    // to nothing aren't in `chunks` but mustn't be generated again
+    pub generated: Vec<(i32, i32)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedGravity {
+    pub gravity_force: f32,
+    pub terminal_velocity: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedPlayer {
+    pub position: [f32; 3],
+    pub velocity: [f32; 2],
+    pub health: f32,
+    pub max_health: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedEnemy {
+    pub kind: String,  // the EnemyKind's name
+    pub position: [f32; 3],
+    pub velocity: [f32; 2],
+    pub hp: f32,
+    pub is_jumping: bool,
+    pub needs_jump: bool,
+    pub jumped: bool,
+}
+
+// only the cells that aren't air, index is the cell's index in the chunk (see chunk_of)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedChunk {
+    pub chunk: (i32, i32),
+    pub cells: Vec<SavedCell>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedCell {
+    pub index: u16,
+    pub element: u16,
+    pub color: [u8; 4],
+    pub velocity: [f32; 2],
+    pub temperature: f32,
+    pub sediment: u16,
+}
+
+impl SaveGame {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SaveError> {
+        let mut bytes = SAVE_MAGIC.to_vec();
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend(bincode::serialize(self)?);
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+        if bytes.len() < 8 || &bytes[..4] != SAVE_MAGIC {
+            return Err(SaveError::NotASave)
+        }
+        let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        if version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(version))
+        }
+        Ok(bincode::deserialize(&bytes[8..])?)
+    }
+
+    pub fn to_ron(&self) -> Result<String, SaveError> {
+        Ok(ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, SaveError> {
+        let save: SaveGame = ron::from_str(text)?;
+        if save.version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(save.version))
+        }
+        Ok(save)
+    }
+
+    // reads either form, the binary one starts with the magic bytes
+    pub fn read(path: &Path) -> Result<Self, SaveError> {
+        let bytes = fs::read(path)?;
+        if bytes.starts_with(SAVE_MAGIC) {
+            return Self::from_bytes(&bytes)
+        }
+        match std::str::from_utf8(&bytes) {
+            Ok(text) => Self::from_ron(text),
+            Err(_) => Err(SaveError::NotASave),
+        }
+    }
+
+    pub fn write(&self, path: &Path, ron_debug: bool) -> Result<(), SaveError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_bytes()?)?;
+        if ron_debug {
+            fs::write(path.with_extension("ron"), self.to_ron()?)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Bincode(bincode::Error),
+    Ron(ron::Error),
+    RonParse(ron::error::SpannedError),
+    NotASave,
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "could not read or write save: {e}"),
+            SaveError::Bincode(e) => write!(f, "could not encode or decode save: {e}"),
+            SaveError::Ron(e) => write!(f, "could not write save as ron: {e}"),
+            SaveError::RonParse(e) => write!(f, "could not parse ron save: {e}"),
+            SaveError::NotASave => write!(f, "not a pixelstellar save file"),
+            SaveError::UnsupportedVersion(v) => write!(f, "save format version {v} is not supported (expected {SAVE_VERSION})"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<bincode::Error> for SaveError {
+    fn from(e: bincode::Error) -> Self {
+        SaveError::Bincode(e)
+    }
+}
+
+impl From<ron::Error> for SaveError {
+    fn from(e: ron::Error) -> Self {
+        SaveError::Ron(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        SaveError::RonParse(e)
+    }
+}
+
+
+#[derive(Resource)]
+pub struct SaveSettings {
+    pub path: PathBuf,
+    pub ron_debug: bool,
+}
+
+impl Default for SaveSettings {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from(SAVE_PATH),
+            ron_debug: std::env::var(SAVE_RON_ENV_VAR).is_ok_and(|v| v == "1"),
+        }
+    }
+}
+
+impl SaveSettings {
+    pub fn has_save(&self) -> bool {
+        self.path.exists()
+    }
+}
+
+// The save is read in the menu and applied once the planet it was made on has been set up
+#[derive(Resource)]
+pub struct PendingLoad(pub SaveGame);
+
+// sent by the menu's Load button
+#[derive(Event)]
+pub struct LoadGame;
+
+#[derive(SystemParam)]
+pub struct GameSnapshot<'w, 's> {
+    seed: Res<'w, SimulationSeed>,
+    planet: CurrentPlanet<'w>,
+    gravity: Res<'w, GravityResource>,
+    map: Res<'w, ParticleMap>,
+    blaster: Option<Res<'w, BlasterSelection>>,
+    player: Query<'w, 's, (&'static Transform, Option<&'static Interpolated>, &'static Velocity, &'static Health), With<Player>>,
+    chunk_list: Option<Res<'w, ChunkList>>,
+    enemies: Query<'w, 's, EnemySnapshot, With<Enemy>>,
+}
+
+type EnemySnapshot = (&'static EnemyKind, &'static Transform, Option<&'static Interpolated>, &'static EnemyVelocity, &'static EnemyHealth, &'static Jump);
+
+fn tick_translation(transform: &Transform, interpolated: Option<&Interpolated>) -> [f32; 3] {
+    interpolated.map_or(transform.translation, |i| i.tick_translation(transform)).to_array()
+}
+
+impl GameSnapshot<'_, '_> {
+    pub fn capture(&self) -> Option<SaveGame> {
+        let (transform, interpolated, velocity, health) = self.player.get_single().ok()?;
+        let registry = self.map.registry();
+
+        let mut chunks = Vec::new();
+        for chunk in self.map.loaded_chunks() {
+            let Some(cells) = self.map.chunk_cells(chunk) else {
+                continue
+            };
+            let cells: Vec<SavedCell> = cells.iter().enumerate()
+                .filter_map(|(index, cell)| cell.map(|particle| SavedCell {
+                    index: index as u16,
+                    element: particle.element.0,
+                    color: particle.color,
+                    velocity: particle.velocity.to_array(),
+                    temperature: particle.temperature,
+                    sediment: particle.sediment.0,
+                }))
+                .collect();
+            if !cells.is_empty() {
+                chunks.push(SavedChunk { chunk, cells });
+            }
+        }
+        // same order every time, so the same world gives the same file
+        chunks.sort_by_key(|chunk| chunk.chunk);
+        let mut generated: Vec<(i32, i32)> = match &self.chunk_list {
+            Some(chunk_list) if self.planet.get().terrain.chunked => chunk_list.chunk_list.iter().copied().collect(),
+            _ => Vec::new(),
+        };
+        generated.sort();
+
+        Some(SaveGame {
+            version: SAVE_VERSION,
+            seed: self.seed.0,
+            planet: self.planet.get().name.clone(),
+            gravity: SavedGravity {
+                gravity_force: self.gravity.gravity_force,
+                terminal_velocity: self.gravity.terminal_velocity,
+            },
+            player: SavedPlayer {
+                position: tick_translation(transform, interpolated),
+                velocity: velocity.velocity.to_array(),
+                health: health.current,
+                max_health: health.max,
+            },
+            blaster: self.blaster.as_ref().map(|b| b.selected).unwrap_or(BlasterType::Water),
+            enemies: self.enemies.iter().map(|(kind, transform, interpolated, velocity, health, jump)| SavedEnemy {
+                kind: kind.name.clone(),
+                position: tick_translation(transform, interpolated),
+                velocity: velocity.velocity.to_array(),
+                hp: health.hp,
+                is_jumping: jump.is_jumping,
+                needs_jump: jump.needs_jump,
+                jumped: jump.jumped,
+            }).collect(),
+            elements: registry.elements().map(|(_, definition)| definition.name.clone()).collect(),
+            chunks,
+            generated,
+        })
+    }
+}
+
+fn write_save(snapshot: &GameSnapshot, settings: &SaveSettings) {
+    let Some(save) = snapshot.capture() else {
+        return
+    };
+    match save.write(&settings.path, settings.ron_debug) {
+        Ok(()) => info!("saved game to {}", settings.path.display()),
+        Err(e) => error!("{e}"),
+    }
+}
+
+fn save_on_key(
+    keys: Res<ButtonInput<KeyCode>>,
+    snapshot: GameSnapshot,
+    settings: Res<SaveSettings>,
+) {
+    if keys.just_pressed(KeyCode::F5) {
+        write_save(&snapshot, &settings);
+    }
+}
+
+// closing the window mid-planet keeps your progress
+fn save_on_exit(
+    mut exit: EventReader<AppExit>,
+    snapshot: GameSnapshot,
+    settings: Res<SaveSettings>,
+) {
+    if exit.read().last().is_some() {
+        write_save(&snapshot, &settings);
+    }
+}
+
+// Load button: read the save and go straight to the planet it was made on. The planet is set up
+// like normal (same seed, so the same world) and apply_pending_load puts everything back on top
+fn start_load(
+    mut commands: Commands,
+    mut events: EventReader<LoadGame>,
+    settings: Res<SaveSettings>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+    planets: Res<Planets>,
+) {
+    if events.read().last().is_none() {
+        return
+    }
+    match SaveGame::read(&settings.path) {
+        Ok(save) => {
+            let Some(phase) = planets.by_name(&save.planet) else {
+                error!("could not load {}: there is no planet called {}", settings.path.display(), save.planet);
+                return
+            };
+            commands.insert_resource(SimulationSeed(save.seed));
+            next_phase.set(phase);
+            next_app_state.set(AppState::InGame);
+            commands.insert_resource(PendingLoad(save));
+        }
+        Err(e) => error!("could not load {}: {e}", settings.path.display()),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_pending_load(
+    mut commands: Commands,
+    pending: Res<PendingLoad>,
+    mut map: ResMut<ParticleMap>,
+    mut liquid_bodies: ResMut<LiquidBodies>,
+    chunk_list: Option<ResMut<ChunkList>>,
+    planet: CurrentPlanet,
+    mut gravity: ResMut<GravityResource>,
+    mut blaster_events: EventWriter<ChangeBlasterEvent>,
+    mut player: Query<(&mut Transform, &mut Velocity, &mut Health, &mut Hitbox), With<Player>>,
+    enemies: Query<Entity, With<Enemy>>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    // wait until the planet has spawned the player
+    let Ok((mut transform, mut velocity, mut health, mut hitbox)) = player.get_single_mut() else {
+        return
+    };
+    let save = &pending.0;
+
+    // elements are matched up by name, in case the element list changed since the save was made
+    let elements: Vec<Option<ParticleElement>> = save.elements.iter()
+        .map(|name| {
+            let element = map.registry().by_name(name);
+            if element.is_none() {
+                warn!("save has an unknown element {name}, those cells are left empty");
+            }
+            element
+        })
+        .collect();
+
+    for chunk in map.loaded_chunks() {
+        map.despawn_chunk(chunk);
+    }
+    // the bodies found in the old cells are no good now
+    liquid_bodies.clear();
+    for saved in save.chunks.iter() {
+        for cell in saved.cells.iter() {
+            let Some(Some(element)) = elements.get(cell.element as usize) else {
+                continue
+            };
+            let index = cell.index as i32;
+            let pos = (saved.chunk.0 * CHUNK_SIZE + index % CHUNK_SIZE, saved.chunk.1 * CHUNK_SIZE + index / CHUNK_SIZE);
+            map.put_particle_at(pos, Particle {
+                element: *element,
+                color: cell.color,
+                velocity: Vec2::from_array(cell.velocity),
+                temperature: cell.temperature,
+                // sediment from an element this game doesn't have is just dropped
+                sediment: elements.get(cell.sediment as usize).copied().flatten().unwrap_or(ParticleElement::Air),
+            });
+        }
+    }
+    // chunked planets generate chunks around the player as they move, the saved ones are already there
+    if let Some(mut chunk_list) = chunk_list.filter(|_| planet.get().terrain.chunked) {
+        chunk_list.chunk_list = save.generated.iter().copied().collect();
+    }
+
+    gravity.gravity_force = save.gravity.gravity_force;
+    gravity.terminal_velocity = save.gravity.terminal_velocity;
+
+    transform.translation = Vec3::from_array(save.player.position);
+    hitbox.offset = transform.translation.xy();
+    velocity.velocity = Vec2::from_array(save.player.velocity);
+    health.max = save.player.max_health;
+    health.current = save.player.health;
+
+    blaster_events.send(ChangeBlasterEvent { new_blaster_type: save.blaster });
+
+    // the enemies the planet spawned are thrown away and the saved ones spawned again by kind,
+    // so ones that died or split since the planet started come back as they were
+    for entity in enemies.iter() {
+        commands.entity(entity).despawn();
+    }
+    let kinds = &planet.get().enemies;
+    let sprites: Vec<_> = kinds.iter().map(|kind| enemy_sprite(&asset_server, &mut texture_atlases, kind)).collect();
+    for saved in save.enemies.iter() {
+        let Some(i) = kinds.iter().position(|kind| kind.name == saved.kind) else {
+            warn!("save has a {} enemy but {} has no enemy called that, it is left out", saved.kind, save.planet);
+            continue
+        };
+        let (kind, (texture, layout)) = (&kinds[i], &sprites[i]);
+        let translation = Vec3::from_array(saved.position);
+        let entity = spawn_enemy(&mut commands, kind, texture.clone(), layout.clone(), translation);
+        commands.entity(entity).insert((
+            Hitbox::new(kind.size.0, kind.size.1, translation.xy()),
+            EnemyVelocity { velocity: Vec2::from_array(saved.velocity) },
+            EnemyHealth { hp: saved.hp },
+            Jump { is_jumping: saved.is_jumping, needs_jump: saved.needs_jump, jumped: saved.jumped },
+        ));
+    }
+
+    info!("loaded save on {}", save.planet);
+    commands.remove_resource::<PendingLoad>();
+}
+
+pub struct SavePlugin;
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().contains_resource::<SaveSettings>() {
+            app.init_resource::<SaveSettings>();
+        }
+        app.add_event::<LoadGame>();
+        app.add_systems(Update, start_load.run_if(in_state(AppState::Menu)));
+        app.add_systems(Update, apply_pending_load.run_if(in_state(AppState::InGame).and_then(resource_exists::<PendingLoad>)));
+        app.add_systems(Last, save_on_exit.run_if(in_state(AppState::InGame)));
+    }
+
+    fn finish(&self, app: &mut App) {
+        // F5 needs a keyboard, headless apps don't have one
+        if app.world().contains_resource::<ButtonInput<KeyCode>>() {
+            app.add_systems(Update, save_on_key.run_if(in_state(AppState::InGame)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{audio::AudioSource, ecs::system::RunSystemOnce, input::InputPlugin, state::app::StatesPlugin, window::PrimaryWindow};
+
+    use super::*;
+    use crate::{common, entities, planets, worldgen};
+
+    fn save() -> SaveGame {
+        SaveGame {
+            version: SAVE_VERSION,
+            seed: 1234,
+            planet: "planet_7".into(),
+            gravity: SavedGravity { gravity_force: 3600., terminal_velocity: 750. },
+            player: SavedPlayer { position: [10., -20., 900.], velocity: [1.5, -3.], health: 80., max_health: 100. },
+            blaster: BlasterType::Shock,
+            enemies: vec![
+                SavedEnemy { kind: "blob".into(), position: [300., 40., 900.], velocity: [-2., 0.], hp: 3., is_jumping: true, needs_jump: false, jumped: true },
+                SavedEnemy { kind: "walker".into(), position: [-150., 60., 900.], velocity: [0., 0.], hp: 1., is_jumping: false, needs_jump: true, jumped: false },
+            ],
+            elements: vec!["Air".into(), "Water".into(), "Sand".into()],
+            chunks: vec![SavedChunk {
+                chunk: (-1, 2),
+                cells: vec![SavedCell { index: 70, element: 1, color: [20, 40, 200, 180], velocity: [0.5, -1.], temperature: 12.5, sediment: 2 }],
+            }],
+            generated: vec![(-1, 2), (0, 2)],
+        }
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let save = save();
+        let loaded = SaveGame::from_bytes(&save.to_bytes().unwrap()).unwrap();
+        // no PartialEq on the save, the ron text has every field in it
+        assert_eq!(loaded.to_ron().unwrap(), save.to_ron().unwrap());
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let mut bytes = save().to_bytes().unwrap();
+        bytes[4..8].copy_from_slice(&(SAVE_VERSION + 1).to_le_bytes());
+        assert!(matches!(SaveGame::from_bytes(&bytes), Err(SaveError::UnsupportedVersion(_))));
+    }
+
+    // the game without a window or renderer, playing `planet` from SEED
+    fn play(planet: &str) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, AssetPlugin::default(), InputPlugin));
+        app.init_asset::<Image>().init_asset::<TextureAtlasLayout>().init_asset::<AudioSource>();
+        app.insert_resource(SimulationSeed(7));
+        app.init_state::<AppState>().add_sub_state::<GamePhase>();
+        app.add_plugins((
+            common::seed::SeedPlugin, SavePlugin, common::timestep::TimestepPlugin, common::state::StatePlugin,
+            common::death::DeathPlugin, common::wind::WindPlugin, common::ui::camera::CameraPlugin,
+        ));
+        app.add_plugins((
+            entities::particle::systems::ParticlePlugin, entities::debris::systems::DebrisPlugin,
+            entities::enemy::systems::EnemyPlugin, entities::player::systems::PlayerPlugin,
+            entities::spaceship::systems::SpaceshipPlugin, planets::systems::PlanetPlugin, worldgen::WorldGenPlugin,
+        ));
+        app.world_mut().spawn((Window::default(), PrimaryWindow));
+        app.finish();
+        app.update();
+
+        let phase = Planets::builtin().by_name(planet).unwrap();
+        app.world_mut().resource_mut::<NextState<AppState>>().set(AppState::InGame);
+        app.world_mut().resource_mut::<NextState<GamePhase>>().set(phase);
+        for _ in 0..3 {
+            app.update();
+        }
+        app
+    }
+
+    fn player_cell(app: &mut App) -> (i32, i32) {
+        let mut player = app.world_mut().query_filtered::<&Transform, With<Player>>();
+        let pt = player.single(app.world()).translation;
+        convert_to_grid_position(pt.x, pt.y)
+    }
+
+    fn enemy_kinds(app: &mut App) -> Vec<String> {
+        let mut enemies = app.world_mut().query::<&EnemyKind>();
+        enemies.iter(app.world()).map(|kind| kind.name.clone()).collect()
+    }
+
+    fn cells_in(map: &ParticleMap, chunk: (i32, i32)) -> usize {
+        map.chunk_cells(chunk).map_or(0, |cells| cells.iter().flatten().count())
+    }
+
+    #[test]
+    fn load_puts_the_world_back() {
+        // planet 4 is chunked, so the chunk list is saved too
+        let mut app = play("planet_4");
+        let (x, y) = player_cell(&mut app);
+        let (below, _) = chunk_of((x, y - CHUNK_SIZE));
+        let copper = app.world().resource::<ParticleMap>().registry().by_name("Copper").unwrap();
+        {
+            let mut map = app.world_mut().resource_mut::<ParticleMap>();
+            assert!(cells_in(&map, below) > 0, "the chunk under the player should have terrain in it");
+            // dig the chunk under the player out completely and put a copper block up in the air
+            for cx in 0..CHUNK_SIZE {
+                for cy in 0..CHUNK_SIZE {
+                    map.delete_at((below.0 * CHUNK_SIZE + cx, below.1 * CHUNK_SIZE + cy));
+                }
+            }
+            map.insert_at(copper, (x, y + 40), ListType::All);
+        }
+        let mut save = app.world_mut().run_system_once(|snapshot: GameSnapshot| snapshot.capture()).unwrap();
+        assert!(save.generated.contains(&below));
+        // a second walker, as if one had split
+        let walker = save.enemies[0].clone();
+        save.enemies.push(SavedEnemy { position: [walker.position[0] + 100., walker.position[1], walker.position[2]], ..walker });
+        save.player.position[0] += 20.;
+        let save = SaveGame::from_bytes(&save.to_bytes().unwrap()).unwrap();
+
+        let mut app = play("planet_4");
+        // freeze the clock, otherwise the player already falls into the dug out chunk before we look
+        app.world_mut().resource_mut::<Time<Virtual>>().pause();
+        app.insert_resource(PendingLoad(save.clone()));
+        app.update();
+        assert!(!app.world().contains_resource::<PendingLoad>());
+        app.update();
+
+        let map = app.world().resource::<ParticleMap>();
+        assert_eq!(map.get_element_at((x, y + 40)), copper);
+        assert_eq!(cells_in(map, below), 0, "the dug out chunk was generated again");
+        let mut generated: Vec<(i32, i32)> = app.world().resource::<ChunkList>().chunk_list.iter().copied().collect();
+        generated.sort();
+        assert!(save.generated.iter().all(|chunk| generated.contains(chunk)));
+        assert_eq!(enemy_kinds(&mut app), vec!["walker".to_string(), "walker".to_string()]);
+        let mut player = app.world_mut().query_filtered::<&Transform, With<Player>>();
+        assert_eq!(player.single(app.world()).translation.to_array(), save.player.position);
+    }
+}
@@ -0,0 +1,163 @@
+use bevy::{prelude::*, utils::HashMap};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::common::state::AppState;
+use crate::entities::particle::resources::ParticleMap;
+
+/* Everything random in the game (world generation, the particle automata, enemy AI) comes from this seed,
+   so the same seed and the same inputs play out exactly the same way. Set PIXELSTELLAR_SEED to replay a seed,
+   otherwise a random one is picked at startup and logged.
+
+   Never use rand::thread_rng() in game code, take a stream instead:
+     - SimulationRng::stream("name") in systems that run every frame, one stream per system
+     - SimulationSeed::rng("name") for one off things like a planet's noise permutation
+     - SimulationSeed::chunk_rng("name", chunk) for anything generated per chunk, so a chunk comes out
+       the same no matter what order chunks are loaded in
+*/
+pub const SEED_ENV_VAR: &str = "PIXELSTELLAR_SEED";
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SimulationSeed(pub u64);
+
+impl SimulationSeed {
+    pub fn from_env_or_random() -> Self {
+        match std::env::var(SEED_ENV_VAR).ok().and_then(|seed| seed.parse().ok()) {
+            Some(seed) => Self(seed),
+            None => Self(rand::thread_rng().gen()),
+        }
+    }
+
+    pub fn rng(&self, stream: &str) -> StdRng {
+        StdRng::seed_from_u64(mix(self.0, hash_name(stream)))
+    }
+
+    pub fn chunk_rng(&self, stream: &str, chunk: (i32, i32)) -> StdRng {
+        let chunk = ((chunk.0 as u32 as u64) << 32) | chunk.1 as u32 as u64;
+        StdRng::seed_from_u64(mix(mix(self.0, hash_name(stream)), chunk))
+    }
+}
+
+// FNV-1a, so stream names hash the same on every platform and compiler version
+fn hash_name(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+// splitmix64 finalizer, spreads the bits of both values over the result
+fn mix(a: u64, b: u64) -> u64 {
+    let mut z = a ^ b.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// The per system streams. They start over at the beginning of every level, so a level replays the same way
+#[derive(Resource)]
+pub struct SimulationRng {
+    seed: SimulationSeed,
+    streams: HashMap<&'static str, StdRng>,
+}
+
+impl SimulationRng {
+    pub fn new(seed: SimulationSeed) -> Self {
+        Self {
+            seed,
+            streams: HashMap::new(),
+        }
+    }
+
+    pub fn reset(&mut self, seed: SimulationSeed) {
+        self.seed = seed;
+        self.streams.clear();
+    }
+
+    pub fn stream(&mut self, name: &'static str) -> &mut StdRng {
+        let seed = self.seed;
+        self.streams.entry(name).or_insert_with(|| seed.rng(name))
+    }
+}
+
+
+// every level starts from the seed again, clear_level does the same when a level ends
+pub fn restart_rng(
+    seed: Res<SimulationSeed>,
+    mut rng: ResMut<SimulationRng>,
+    mut map: ResMut<ParticleMap>,
+) {
+    rng.reset(*seed);
+    map.reseed(seed.0);
+}
+
+fn log_seed(seed: Res<SimulationSeed>) {
+    info!("simulation seed: {} (set {} to replay it)", seed.0, SEED_ENV_VAR);
+}
+
+pub struct SeedPlugin;
+impl Plugin for SeedPlugin {
+    fn build(&self, app: &mut App) {
+        // a seed inserted before this plugin (i.e. by a test or a tool) is kept
+        if !app.world().contains_resource::<SimulationSeed>() {
+            app.insert_resource(SimulationSeed::from_env_or_random());
+        }
+        let seed = *app.world().resource::<SimulationSeed>();
+        app.insert_resource(SimulationRng::new(seed));
+        app.add_systems(Startup, (log_seed, restart_rng));
+        app.add_systems(OnEnter(AppState::InGame), restart_rng);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+
+    use super::*;
+    use crate::common::{gravity::GravityResource, state::GamePhase, timestep::SimulationStep};
+    use crate::entities::particle::{components::*, resources::*, systems::ParticlePlugin};
+    use crate::planets::definition::Planets;
+    use crate::worldgen::WorldGen;
+
+    const SEED: u64 = 42;
+
+    // planet 1 made from the seed with a strip of water and sand poured on top, then a few steps of the
+    // automata, like the particle_update bench
+    fn simulate(seed: u64, steps: usize) -> u64 {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, ParticlePlugin));
+        app.insert_resource(SimulationRng::new(SimulationSeed(seed)));
+        app.insert_resource(GravityResource::new(3600., 750.));
+
+        let mut map = app.world_mut().resource_mut::<ParticleMap>();
+        map.reseed(seed);
+        let worldgen = WorldGen::new(Planets::builtin().get(GamePhase(0)).terrain.clone(), &SimulationSeed(seed), map.registry());
+        worldgen.generate_level(&mut map, &SimulationSeed(seed));
+        for x in MIN_X..MAX_X {
+            for y in MAX_Y - 10..MAX_Y {
+                let element = if (x / 16) % 2 == 0 { ParticleElement::Water } else { ParticleElement::Sand };
+                map.insert_at(element, (x, y), ListType::OnlyAir);
+            }
+        }
+        for _ in 0..steps {
+            app.world_mut().run_schedule(SimulationStep);
+        }
+        checksum(app.world().resource::<ParticleMap>())
+    }
+
+    fn checksum(map: &ParticleMap) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let mut cells: Vec<((i32, i32), u16, [u8; 4])> = map.positions_where(|_| true).into_iter()
+            .map(|pos| (pos, map.get_element_at(pos).0, map.get_particle_at(pos).unwrap().color))
+            .collect();
+        cells.sort();
+        cells.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn same_seed_same_world() {
+        let made = simulate(SEED, 0);
+        let stepped = simulate(SEED, 20);
+        assert_eq!(made, simulate(SEED, 0));
+        assert_eq!(stepped, simulate(SEED, 20));
+        // otherwise the steps aren't checking anything
+        assert_ne!(made, stepped);
+    }
+}
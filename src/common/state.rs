@@ -1,11 +1,14 @@
-use bevy::prelude::*;
-use crate::entities::particle::resources::ParticleMap;
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+use crate::entities::particle::resources::{ChargeField, LiquidBodies, ParticleMap};
+use crate::common::seed::{SimulationRng, SimulationSeed};
+use crate::planets::definition::Planets;
 use crate::entities::player::{components::Player,
     blaster::components::Blaster};
     use crate::entities::enemy::components::Enemy;
     use crate::entities::spaceship::components::Spaceship;
+    use crate::entities::debris::components::Debris;
     
-    use crate::entities::particle::components::ParticleElement;
+    use crate::entities::particle::components::ParticleChunkSprite;
     use crate::common::ui::{
         background::Background,
         health_bar::HealthBar};
@@ -25,65 +28,80 @@ use crate::entities::player::{components::Player,
 
 
 
-#[derive(SubStates, Clone, PartialEq, Eq, Hash, Debug, Default)]
+/* Which planet is being played, the index into Planets (see src/planets) in the order they're played.
+   The planets all set themselves up the same way from their definition, so instead of OnEnter/OnExit
+   for every one of them there's one EnterPlanet and ExitPlanet schedule that runs for all of them.
+*/
+#[derive(SubStates, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
 #[source(AppState = AppState::InGame)]
-pub enum GamePhase {
-    #[default]
-    Planet1,
-    Planet2,
-    Planet3,
-    Planet4,
-    Planet5,
-    Planet6,
-    Planet7,
-    Planet8,
-    //Add other levels here
+pub struct GamePhase(pub usize);
+
+// runs whenever a planet starts (from the menu, the last planet, or a save)
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EnterPlanet;
+
+// runs whenever a planet is left (for the next one or the win screen)
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExitPlanet;
+
+// the same as bevy runs OnEnter/OnExit, see bevy_state's transitions.rs
+fn run_enter_planet(transition: In<Option<StateTransitionEvent<GamePhase>>>, world: &mut World) {
+    if let Some(StateTransitionEvent { entered: Some(entered), exited }) = transition.0 {
+        if exited != Some(entered) {
+            let _ = world.try_run_schedule(EnterPlanet);
+        }
+    }
+}
+
+fn run_exit_planet(transition: In<Option<StateTransitionEvent<GamePhase>>>, world: &mut World) {
+    if let Some(StateTransitionEvent { exited: Some(exited), entered }) = transition.0 {
+        if entered != Some(exited) {
+            let _ = world.try_run_schedule(ExitPlanet);
+        }
+    }
 }
 
 pub fn set_next_state(
     state: Res<State<GamePhase>>,
+    planets: &Planets,
     mut next_phase: ResMut<NextState<GamePhase>>,
     mut next_app_state: ResMut<NextState<AppState>>,
 
 ){
-    match state.get() {
-        GamePhase::Planet1 => next_phase.set(GamePhase::Planet2),
-        GamePhase::Planet2 => next_phase.set(GamePhase::Planet3),
-        GamePhase::Planet3 => next_phase.set(GamePhase::Planet4),
-        GamePhase::Planet4 => next_phase.set(GamePhase::Planet5),
-        GamePhase::Planet5 => next_phase.set(GamePhase::Planet6),
-        GamePhase::Planet6 => next_phase.set(GamePhase::Planet7),
-        GamePhase::Planet7 => next_phase.set(GamePhase::Planet8),
-        // add level transitions here
+    match planets.next(*state.get()) {
+        Some(next) => next_phase.set(next),
         //LAST LEVEL CHANGES THE APP STATE
-        GamePhase::Planet8 => next_app_state.set(AppState::WinScreen),
+        None => next_app_state.set(AppState::WinScreen),
     }
 }
 
 fn clear_level(
     mut commands: Commands,
     mut map: ResMut<ParticleMap>,
-    query: Query<Entity, Or<(With<Player>, With<Enemy>, With<Background>, With<ParticleElement>, With<HealthBar>, With<Blaster>, With<Spaceship>)>>,
+    seed: Res<SimulationSeed>,
+    mut sim_rng: ResMut<SimulationRng>,
+    mut charge: ResMut<ChargeField>,
+    mut liquid_bodies: ResMut<LiquidBodies>,
+    query: Query<Entity, Or<(With<Player>, With<Enemy>, With<Background>, With<ParticleChunkSprite>, With<HealthBar>, With<Blaster>, With<Spaceship>, With<Debris>)>>,
 
 ){
     for entity in query.iter() {
         commands.entity(entity).despawn();
     }
     map.reset();
+    charge.clear();
+    liquid_bodies.clear();
+    // the next level starts from the seed again
+    sim_rng.reset(*seed);
 }
 
 pub struct StatePlugin; 
 impl Plugin for StatePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnExit(GamePhase::Planet1),  clear_level);
-        app.add_systems(OnExit(GamePhase::Planet2),  clear_level);
-        app.add_systems(OnExit(GamePhase::Planet3),  clear_level);
-        app.add_systems(OnExit(GamePhase::Planet4),  clear_level);
-        app.add_systems(OnExit(GamePhase::Planet5),  clear_level);
-        app.add_systems(OnExit(GamePhase::Planet6),  clear_level);
-        app.add_systems(OnExit(GamePhase::Planet7),  clear_level);
-        app.add_systems(OnExit(GamePhase::Planet8),  clear_level);
-        // Add level clearing here
+        app.init_schedule(EnterPlanet);
+        app.init_schedule(ExitPlanet);
+        app.add_systems(StateTransition, last_transition::<GamePhase>.pipe(run_exit_planet).in_set(ExitSchedules::<GamePhase>::default()));
+        app.add_systems(StateTransition, last_transition::<GamePhase>.pipe(run_enter_planet).in_set(EnterSchedules::<GamePhase>::default()));
+        app.add_systems(ExitPlanet, clear_level);
 }
 }
-
@@ -0,0 +1,147 @@
+use bevy::{app::RunFixedMainLoop, ecs::schedule::ScheduleLabel, prelude::*, time::run_fixed_main_schedule};
+
+use crate::common::state::AppState;
+
+/* The game runs on a fixed tick instead of once per frame, so water flows and things fall at the same
+   speed at 30, 60 or 144 fps. Bevy keeps the accumulator (Time<Fixed>): every frame adds the frame time to it
+   and one tick runs for every 1/tick_rate seconds in it, so a slow frame runs several ticks and a fast one none.
+
+     FixedUpdate:    player and enemy physics, planet weather. Anything that moves things or spawns particles
+     SimulationStep: the particle automata. Cells move one step per run, so this runs `substeps` times per tick
+     Update:         input, animation, camera, UI
+
+   Entities with an Interpolated component are drawn between the positions of their last two ticks,
+   otherwise they would stutter whenever the frame rate and the tick rate don't line up.
+*/
+pub const DEFAULT_TICK_RATE: f64 = 60.;
+pub const DEFAULT_SUBSTEPS: u32 = 1;
+
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SimulationStep;
+
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct TimestepSettings {
+    pub tick_rate: f64,
+    pub substeps: u32,
+}
+
+impl Default for TimestepSettings {
+    fn default() -> Self {
+        Self {
+            tick_rate: DEFAULT_TICK_RATE,
+            substeps: DEFAULT_SUBSTEPS,
+        }
+    }
+}
+
+impl TimestepSettings {
+    // how much time one run of SimulationStep stands for, use this instead of Time in the automata
+    pub fn step_seconds(&self) -> f32 {
+        (1. / (self.tick_rate * self.substeps.max(1) as f64)) as f32
+    }
+}
+
+fn apply_tick_rate(settings: Res<TimestepSettings>, mut time: ResMut<Time<Fixed>>) {
+    time.set_timestep_hz(settings.tick_rate);
+}
+
+pub fn run_simulation_steps(world: &mut World) {
+    let substeps = world.resource::<TimestepSettings>().substeps;
+    for _ in 0..substeps {
+        world.run_schedule(SimulationStep);
+    }
+}
+
+
+// Draws an entity between where it was on the last two ticks. Teleporting it (setting its Transform
+// outside of the fixed schedules, i.e. loading a save) just works, it is noticed and not smoothed over
+#[derive(Component, Default)]
+pub struct Interpolated {
+    previous: Vec3,
+    current: Vec3,
+    drawn: Vec3,
+}
+
+impl Interpolated {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // for entities spawned during a tick, otherwise the first frame draws them sliding in from the origin
+    pub fn at(translation: Vec3) -> Self {
+        let mut interpolated = Self::default();
+        interpolated.snap(translation);
+        interpolated
+    }
+
+    // where the entity really is on the current tick, not where it is drawn
+    pub fn tick_translation(&self, transform: &Transform) -> Vec3 {
+        if transform.translation != self.drawn {
+            return transform.translation
+        }
+        self.current
+    }
+
+    fn snap(&mut self, translation: Vec3) {
+        self.previous = translation;
+        self.current = translation;
+        self.drawn = translation;
+    }
+
+    // the transform isn't where it was drawn, so something outside of the ticks moved it
+    fn catch_teleport(&mut self, translation: Vec3) {
+        if translation != self.drawn {
+            self.snap(translation);
+        }
+    }
+}
+
+fn start_interpolation(mut query: Query<(&Transform, &mut Interpolated), Added<Interpolated>>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.snap(transform.translation);
+    }
+}
+
+// ticks work on the real position, not the one that was drawn
+fn restore_tick_translation(mut query: Query<(&mut Transform, &mut Interpolated)>) {
+    for (mut transform, mut interpolated) in query.iter_mut() {
+        interpolated.catch_teleport(transform.translation);
+        interpolated.previous = interpolated.current;
+        interpolated.drawn = interpolated.current;
+        transform.translation = interpolated.current;
+    }
+}
+
+fn record_tick_translation(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.current = transform.translation;
+        interpolated.drawn = transform.translation;
+    }
+}
+
+fn interpolate_translation(fixed: Res<Time<Fixed>>, mut query: Query<(&mut Transform, &mut Interpolated)>) {
+    let alpha = fixed.overstep_fraction();
+    for (mut transform, mut interpolated) in query.iter_mut() {
+        interpolated.catch_teleport(transform.translation);
+        interpolated.drawn = interpolated.previous.lerp(interpolated.current, alpha);
+        transform.translation = interpolated.drawn;
+    }
+}
+
+pub struct TimestepPlugin;
+impl Plugin for TimestepPlugin {
+    fn build(&self, app: &mut App) {
+        // settings inserted before this plugin (i.e. by a test or a tool) are kept
+        app.init_resource::<TimestepSettings>();
+        app.init_schedule(SimulationStep);
+        app.add_systems(PreUpdate, apply_tick_rate.run_if(resource_changed::<TimestepSettings>));
+        app.add_systems(FixedUpdate, run_simulation_steps.run_if(in_state(AppState::InGame)));
+
+        app.add_systems(RunFixedMainLoop, (
+            start_interpolation.before(run_fixed_main_schedule),
+            interpolate_translation.after(run_fixed_main_schedule),
+        ));
+        app.add_systems(FixedFirst, restore_tick_translation);
+        app.add_systems(FixedLast, record_tick_translation);
+    }
+}
@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use crate::{LEVEL_H, LEVEL_W, common::state::GamePhase};
+use crate::{LEVEL_H, LEVEL_W, planets::definition::CurrentPlanet};
 
 const BG_TILE_SIZE: u32 = 100;
 
@@ -10,23 +10,13 @@ pub fn initialize_background (
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-    state: Res<State<GamePhase>>,
+    planet: CurrentPlanet,
 ) {
-    let planet: i32 = match state.get() {
-        GamePhase::Planet1 => 1,
-        GamePhase::Planet2 => 2,
-        GamePhase::Planet3 => 3,
-        GamePhase::Planet4 => 4,
-        GamePhase::Planet5 => 5,
-        GamePhase::Planet6 => 6,
-        GamePhase::Planet7 => 7,
-        GamePhase::Planet8 => 8,
-        _ => 0,
-    };
-    if planet == 4 {
+    // planets without one just have the sky colour behind them
+    let Some(background) = &planet.get().background else {
         return;
-    }
-    let bg_sheet_handle = asset_server.load(format!("planet_{planet}/background.png"));
+    };
+    let bg_sheet_handle: Handle<Image> = asset_server.load(background);
     let bg_layout = TextureAtlasLayout::from_grid(UVec2::splat(BG_TILE_SIZE), 1, 1, None, None);
     let bg_layout_handle = texture_atlases.add(bg_layout);
 
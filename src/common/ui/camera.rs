@@ -3,7 +3,8 @@ use crate::{
     entities::player::components::*,
     LEVEL_H, LEVEL_W,
     WIN_H, WIN_W,
-    common::state::*,
+    common::state::AppState,
+    planets::definition::CurrentPlanet,
 };
 
 const THRESHOLD_X: f32 = 160.;
@@ -20,7 +21,7 @@ pub fn initialize_camera(mut commands: Commands){
 pub fn move_camera(
     player: Query<&Transform, With<Player>>,
     mut camera: Query<&mut Transform, (Without<Player>, With<Camera>)>,
-    state: Res<State<GamePhase>>,
+    planet: CurrentPlanet,
 ) {
     let pt = player.single();
 
@@ -30,9 +31,8 @@ pub fn move_camera(
     let mut ct = camera.single_mut();
     let x_diff = pt.translation - ct.translation;
 
-    let game_state = state.get();
-
-    if game_state.eq(&GamePhase::Planet4) {
+    // chunked planets go on forever to the sides
+    if planet.get().terrain.chunked {
         if x_diff.x > THRESHOLD_X{ ct.translation.x = pt.translation.x - THRESHOLD_X; }
         if x_diff.x < -THRESHOLD_X { ct.translation.x = pt.translation.x + THRESHOLD_X; }
     } else {
@@ -74,7 +74,7 @@ impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, initialize_camera);
         //app.add_systems(Update, mouse_coordinates);
-        app.add_systems(Update, move_camera.after(crate::entities::player::systems::move_player).run_if(in_state(AppState::InGame)));
+        app.add_systems(Update, move_camera.run_if(in_state(AppState::InGame)));
         app.add_systems(OnEnter(AppState::WinScreen), reset_camera);
     }
 }
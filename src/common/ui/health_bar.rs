@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use crate::entities::player::components::*; 
-use crate::common::state::{AppState, GamePhase};
+use crate::common::state::{AppState, EnterPlanet};
 
 #[derive(Component)]
 pub struct HealthBar;
@@ -54,14 +54,7 @@ pub fn update_health_bar(
 pub struct HealthBarPlugin;
 impl Plugin for HealthBarPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::InGame), initialize_health_bar);
-        app.add_systems(OnEnter(GamePhase::Planet2), initialize_health_bar);
-        app.add_systems(OnEnter(GamePhase::Planet3), initialize_health_bar);
-        app.add_systems(OnEnter(GamePhase::Planet4), initialize_health_bar);
-        app.add_systems(OnEnter(GamePhase::Planet5), initialize_health_bar);
-        app.add_systems(OnEnter(GamePhase::Planet6), initialize_health_bar);
-        app.add_systems(OnEnter(GamePhase::Planet7), initialize_health_bar);
-        app.add_systems(OnEnter(GamePhase::Planet8), initialize_health_bar);
+        app.add_systems(EnterPlanet, initialize_health_bar);
 
         app.add_systems(Update, update_health_bar.run_if(in_state(AppState::InGame)));
     }
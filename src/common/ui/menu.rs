@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 //use crate::engine::update_state::AppState;
 use crate::common::state::AppState;
+use crate::common::save::{LoadGame, SaveSettings};
 
 const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
@@ -9,6 +10,10 @@ const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.75, 0.35);
 #[derive(Component)]
 pub struct StartButton;
 
+// only in the main menu, and only if there is a save to load
+#[derive(Component)]
+pub struct LoadButton;
+
 fn button_system(
     mut interaction_query: Query<
         (
@@ -16,19 +21,23 @@ fn button_system(
             &mut BackgroundColor,
             &mut BorderColor,
             &Children,
+            Has<LoadButton>,
         ),
         (Changed<Interaction>, With<Button>),
     >,
     mut text_query: Query<&mut Text>,
     mut next_state: ResMut<NextState<AppState>>,
+    mut load_game: EventWriter<LoadGame>,
     current_state: Res<State<AppState>>,
 
 ) {
-    for (interaction, mut color, mut border_color, children) in &mut interaction_query {
+    for (interaction, mut color, mut border_color, children, is_load_button) in &mut interaction_query {
         let mut text = text_query.get_mut(children[0]).unwrap();
         match *interaction {
             Interaction::Pressed => {
-                if *current_state.get()==AppState::Menu{
+                if is_load_button {
+                    load_game.send(LoadGame);}
+                else if *current_state.get()==AppState::Menu{
                     next_state.set(AppState::InGame);}
                 if *current_state.get()==AppState::WinScreen{
                     next_state.set(AppState::EndCredits); }               
@@ -38,7 +47,9 @@ fn button_system(
                 border_color.0 = Color::WHITE;
             }
             Interaction::None => {
-                if *current_state.get()==AppState::Menu{
+                if is_load_button {
+                    text.sections[0].value = "Load".to_string();}
+                else if *current_state.get()==AppState::Menu{
                     text.sections[0].value = "Play".to_string();}
                 if *current_state.get()==AppState::WinScreen{
                         text.sections[0].value = "Credits".to_string();}
@@ -48,12 +59,20 @@ fn button_system(
         }
     }
 }
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    current_state: Res<State<AppState>>,
+    save_settings: Res<SaveSettings>,
+) {
+    let font = asset_server.load("fonts/Silkscreen-Bold.ttf");
     commands
         .spawn((NodeBundle {
             style: Style {
                 width: Val::Percent(100.0),
                 height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(20.0),
                 align_items: AlignItems::Center,
                 justify_content: JustifyContent::Center,
                 ..default()
@@ -62,36 +81,44 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         },
     StartButton,))
         .with_children(|parent| {
-            parent
-                .spawn((ButtonBundle {
-                    style: Style {
-                        width: Val::Px(175.0),
-                        height: Val::Px(65.0),
-                        border: UiRect::all(Val::Px(5.0)),
-                        // horizontally center child text
-                        justify_content: JustifyContent::Center,
-                        // vertically center child text
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    border_color: BorderColor(Color::BLACK),
-                    border_radius: BorderRadius::MAX,
-                    background_color: NORMAL_BUTTON.into(),
-                    ..default()
-                }, StartButton))
-                .with_children(|parent| {
-                    parent.spawn((TextBundle::from_section(
-                        "Button",
-                        TextStyle {
-                            font: asset_server.load("fonts/Silkscreen-Bold.ttf"),
-                            font_size: 40.0,
-                            color: Color::srgb(0.9, 0.9, 0.9),
-                        },
-                    ), StartButton));
-                });
+            spawn_button(parent, font.clone());
+            if *current_state.get() == AppState::Menu && save_settings.has_save() {
+                spawn_button(parent, font.clone()).insert(LoadButton);
+            }
         });
 }
 
+fn spawn_button<'a>(parent: &'a mut ChildBuilder, font: Handle<Font>) -> bevy::ecs::system::EntityCommands<'a> {
+    let mut button = parent
+        .spawn((ButtonBundle {
+            style: Style {
+                width: Val::Px(175.0),
+                height: Val::Px(65.0),
+                border: UiRect::all(Val::Px(5.0)),
+                // horizontally center child text
+                justify_content: JustifyContent::Center,
+                // vertically center child text
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            border_color: BorderColor(Color::BLACK),
+            border_radius: BorderRadius::MAX,
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        }, StartButton));
+    button.with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "Button",
+            TextStyle {
+                font,
+                font_size: 40.0,
+                color: Color::srgb(0.9, 0.9, 0.9),
+            },
+        ), StartButton));
+    });
+    button
+}
+
 pub fn despawn_menu(
     mut commands: Commands,
     query: Query<Entity, With<StartButton>>,
@@ -4,8 +4,9 @@ use crate::entities::player::{components::Player,
 blaster::components::Blaster};
 use crate::entities::enemy::components::Enemy;
 use crate::entities::spaceship::components::Spaceship;
+use crate::entities::debris::components::Debris;
 
-use crate::entities::particle::components::ParticleElement;
+use crate::entities::particle::components::ParticleChunkSprite;
 use crate::common::ui::{
     background::Background,
     health_bar::HealthBar};
@@ -25,7 +26,7 @@ WinScreen,));
 
 fn clear_level(
     mut commands: Commands,
-    query: Query<Entity, Or<(With<Player>, With<Enemy>, With<Background>, With<ParticleElement>, With<HealthBar>, With<Blaster>, With<Spaceship>)>>,
+    query: Query<Entity, Or<(With<Player>, With<Enemy>, With<Background>, With<ParticleChunkSprite>, With<HealthBar>, With<Blaster>, With<Spaceship>, With<Debris>)>>,
 ){
     for entity in query.iter() {
         commands.entity(entity).despawn();
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::common::{
+    perlin_noise::{generate_permutation_array, get_2d_octaves},
+    seed::SimulationSeed,
+    state::{AppState, EnterPlanet},
+};
+use crate::planets::definition::CurrentPlanet;
+
+/* Wind. Every planet has its own (`wind` in its file in assets/planets): a steady breeze plus gusts that come and go.
+   The gusts are perlin noise over x and time, so columns next to each other blow about the same way and it
+   changes slowly. Gases, snow and rain drift with it (see `wind` in assets/default.elements.ron) and it pushes
+   the player a bit while they're flying.
+   Like GravityResource it's in pixels per second, the particles turn it into cells per step themselves.
+*/
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct WindSettings {
+    pub base: Vec2,        // px/s, the breeze that's always there
+    pub gusts: f32,        // px/s, how much stronger (or weaker) a gust makes it
+    pub gust_size: f32,    // cells, how wide a gust is
+    pub gust_period: f32,  // seconds, how long a gust takes to come and go
+}
+
+impl WindSettings {
+    pub const CALM: Self = Self { base: Vec2::ZERO, gusts: 0., gust_size: 1., gust_period: 1. };
+
+    pub fn new(base: Vec2, gusts: f32) -> Self {
+        Self {
+            base,
+            gusts,
+            gust_size: 96.,
+            gust_period: 6.,
+        }
+    }
+}
+
+// calm, with the usual gusts if a planet only sets how strong they are
+impl Default for WindSettings {
+    fn default() -> Self {
+        Self::new(Vec2::ZERO, 0.)
+    }
+}
+
+#[derive(Resource)]
+pub struct Wind {
+    pub settings: WindSettings,
+    time: f32, // seconds on this planet, moves the gusts along
+    perm_x: [usize; 512],
+    perm_y: [usize; 512],
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        // calm until a planet sets it, the seed doesn't matter without gusts
+        Self::new(WindSettings::CALM, &SimulationSeed(0))
+    }
+}
+
+impl Wind {
+    pub fn new(settings: WindSettings, seed: &SimulationSeed) -> Self {
+        let mut rng = seed.rng("wind");
+        Self {
+            settings,
+            time: 0.,
+            perm_x: generate_permutation_array(&mut rng),
+            perm_y: generate_permutation_array(&mut rng),
+        }
+    }
+
+    // px/s at a grid position
+    pub fn at(&self, (x, _y): (i32, i32)) -> Vec2 {
+        let settings = &self.settings;
+        if settings.gusts == 0. {
+            return settings.base
+        }
+        let (x, t) = (x as f32 / settings.gust_size, self.time / settings.gust_period);
+        let gust_x = get_2d_octaves(x, t, 1., 2, 0.5, 2., -1., 1., &self.perm_x);
+        // gusts mostly blow sideways
+        let gust_y = get_2d_octaves(x, t, 1., 2, 0.5, 2., -0.25, 0.25, &self.perm_y);
+        settings.base + Vec2::new(gust_x, gust_y) * settings.gusts
+    }
+
+    pub fn advance(&mut self, seconds: f32) {
+        self.time += seconds;
+    }
+}
+
+fn change_wind(planet: CurrentPlanet, seed: Res<SimulationSeed>, mut wind: ResMut<Wind>) {
+    *wind = Wind::new(planet.get().wind, &seed);
+}
+
+fn blow_wind(time: Res<Time>, mut wind: ResMut<Wind>) {
+    wind.advance(time.delta_seconds());
+}
+
+pub struct WindPlugin;
+impl Plugin for WindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Wind>();
+        app.add_systems(EnterPlanet, change_wind);
+        app.add_systems(FixedUpdate, blow_wind.run_if(in_state(AppState::InGame)));
+    }
+}
@@ -0,0 +1,59 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::common::hitbox::Hitbox;
+use crate::entities::particle::{components::Particle, elements::ElementClass, resources::{ParticleMap, PARTICLE_SIZE}};
+
+/* A piece of Solid terrain that lost its support (see detect_debris) and falls as one rigid body.
+   Its cells are taken out of the ParticleMap while it falls and go back in where it lands, so the
+   automata never see it. Everything is in grid cells except the velocity, which is in px/s like the player's.
+*/
+#[derive(Component)]
+pub struct Debris {
+    pub cells: Vec<((i32, i32), Particle)>, // offset from the bottom left corner of the piece
+    pub bottom: Vec<(i32, i32)>,            // the cells with nothing of the piece right below them
+    pub size: (i32, i32),
+    pub x: i32,
+    pub y: f32,                             // row of the bottom left corner, in between two rows while it falls
+    pub velocity: f32,                      // negative is down
+}
+
+impl Debris {
+    pub fn new(cells: Vec<((i32, i32), Particle)>) -> Self {
+        let min_x = cells.iter().map(|((x, _), _)| *x).min().unwrap_or(0);
+        let min_y = cells.iter().map(|((_, y), _)| *y).min().unwrap_or(0);
+        let cells: Vec<((i32, i32), Particle)> = cells.into_iter()
+            .map(|((x, y), particle)| ((x - min_x, y - min_y), particle))
+            .collect();
+        let size = (
+            cells.iter().map(|((x, _), _)| x + 1).max().unwrap_or(0),
+            cells.iter().map(|((_, y), _)| y + 1).max().unwrap_or(0),
+        );
+        let offsets: HashSet<(i32, i32)> = cells.iter().map(|(offset, _)| *offset).collect();
+        let bottom = offsets.iter()
+            .filter(|(x, y)| !offsets.contains(&(*x, y - 1)))
+            .copied()
+            .collect();
+        Self {
+            cells,
+            bottom,
+            size,
+            x: min_x,
+            y: min_y as f32,
+            velocity: 0.,
+        }
+    }
+
+    // would the piece fit with its bottom left corner on this row? it falls through anything that flows
+    pub fn fits_at(&self, map: &ParticleMap, row: i32) -> bool {
+        self.bottom.iter().all(|(x, y)| {
+            let element = map.get_element_at((self.x + x, row + y));
+            matches!(map.registry().get(element).class, ElementClass::Liquid | ElementClass::Gas)
+        })
+    }
+
+    // the bounding box of the piece, for crushing things and for the player and enemies to stand on
+    pub fn hitbox_at(&self, y: f32) -> Hitbox {
+        let size = Vec2::new(self.size.0 as f32, self.size.1 as f32) * PARTICLE_SIZE;
+        Hitbox::new(size.x, size.y, Vec2::new(self.x as f32, y) * PARTICLE_SIZE + size / 2.)
+    }
+}
@@ -0,0 +1,244 @@
+use bevy::{prelude::*, sprite::Anchor, utils::HashSet};
+use bevy::render::{
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+    texture::ImageSampler,
+};
+use rand::Rng;
+use super::components::*;
+use crate::common::{
+    death::Death, gravity::GravityResource, hitbox::Hitbox, seed::SimulationRng, state::AppState, timestep::{run_simulation_steps, Interpolated}
+};
+use crate::entities::{
+    enemy::components::{Enemy, EnemyHealth},
+    particle::{components::*, elements::{ElementClass, ElementRegistry}, resources::*},
+    player::components::{Health, Player},
+};
+
+
+
+/* Loose terrain. Solids never move on their own, so anything the deleter blaster or toxic gas cut off used to
+   float in mid air. Whenever a Solid is taken out of the map (see ParticleMap::take_support_checks) the solid
+   cells touching it are flood filled, and a piece that doesn't reach BedRock or the bottom of the level is cut
+   out of the map and falls as a Debris entity. When it lands it goes back into the map, crumbling into powder
+   (see `crumbles` in assets/default.elements.ron) if it came down fast enough.
+   Pieces bigger than MAX_DEBRIS_CELLS count as held up, so cutting into a mountain doesn't flood fill all of it.
+*/
+const MAX_DEBRIS_CELLS: usize = 2048;
+const BREAK_SPEED: f32 = 500.;      // px/s, landing faster than this crumbles the piece
+const CRUSH_MIN_SPEED: f32 = 200.;  // px/s, slower than this it just rests on whoever is below it
+const CRUSH_DAMAGE: f32 = 0.1;      // damage per px/s it comes down on someone with
+const CRUMBLE_SCATTER: f32 = 1.;    // cells per step the crumbled bits can fly off sideways
+const FELL_OUT: i32 = CHUNK_SIZE;   // how far below the level a piece can fall before it's gone for good
+
+const NEIGHBOURS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+fn is_solid(map: &ParticleMap, pos: (i32, i32)) -> bool {
+    map.registry().get(map.get_element_at(pos)).class == ElementClass::Solid
+}
+
+// every solid cell connected to `start`, or None if they're held up. cells that are held up go into `held`
+fn loose_piece(map: &ParticleMap, start: (i32, i32), held: &mut HashSet<(i32, i32)>) -> Option<Vec<(i32, i32)>> {
+    let mut piece = vec![start];
+    let mut seen: HashSet<(i32, i32)> = piece.iter().copied().collect();
+    let mut next = 0;
+    while let Some(&(x, y)) = piece.get(next) {
+        next += 1;
+        let anchored = y <= MIN_Y || map.get_element_at((x, y)) == ParticleElement::BedRock;
+        if anchored || piece.len() > MAX_DEBRIS_CELLS || NEIGHBOURS.iter().any(|(dx, dy)| held.contains(&(x + dx, y + dy))) {
+            held.extend(piece);
+            return None
+        }
+        for (dx, dy) in NEIGHBOURS {
+            let pos = (x + dx, y + dy);
+            if is_solid(map, pos) && seen.insert(pos) {
+                piece.push(pos);
+            }
+        }
+    }
+    Some(piece)
+}
+
+fn detect_debris(
+    mut commands: Commands,
+    mut map: ResMut<ParticleMap>,
+    mut images: Option<ResMut<Assets<Image>>>,
+) {
+    let mut held = HashSet::new();
+    for (x, y) in map.take_support_checks() {
+        for (dx, dy) in NEIGHBOURS {
+            let start = (x + dx, y + dy);
+            if held.contains(&start) || !is_solid(&map, start) {
+                continue;
+            }
+            let Some(piece) = loose_piece(&map, start, &mut held) else {
+                continue
+            };
+            let debris = Debris::new(piece.iter()
+                .filter_map(|pos| map.get_particle_at(*pos).map(|particle| (*pos, particle)))
+                .collect());
+            if !debris.fits_at(&map, debris.y as i32 - 1) {
+                // it's lying on something that isn't terrain (i.e. dirt), it stays where it is
+                held.extend(piece);
+                continue;
+            }
+            for pos in piece {
+                map.delete_at(pos);
+            }
+            spawn_debris(&mut commands, debris, images.as_deref_mut(), map.registry());
+        }
+    }
+}
+
+// without Assets<Image> (headless) the piece still falls, it just isn't drawn
+fn spawn_debris(commands: &mut Commands, debris: Debris, images: Option<&mut Assets<Image>>, registry: &ElementRegistry) {
+    let z = debris.cells.iter().map(|(_, particle)| registry.get(particle.element).z_layer).fold(0., f32::max) + 0.5;
+    let translation = Vec3::new(debris.x as f32 * PARTICLE_SIZE, debris.y * PARTICLE_SIZE, z);
+    let hitbox = debris.hitbox_at(debris.y);
+    let Some(images) = images else {
+        commands.spawn((TransformBundle::from_transform(Transform::from_translation(translation)), Interpolated::at(translation), hitbox, debris));
+        return
+    };
+
+    // textures go top to bottom, the map goes bottom to top
+    let (width, height) = debris.size;
+    let mut data = vec![0u8; (width * height * 4) as usize];
+    for ((x, y), particle) in debris.cells.iter() {
+        let pixel = ((height - 1 - y) * width + x) as usize * 4;
+        data[pixel..pixel + 4].copy_from_slice(&particle.color);
+    }
+    let mut image = Image::new(
+        Extent3d { width: width as u32, height: height as u32, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+
+    commands.spawn((
+        SpriteBundle {
+            texture: images.add(image),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(width as f32, height as f32) * PARTICLE_SIZE),
+                anchor: Anchor::BottomLeft,
+                ..default()
+            },
+            transform: Transform::from_translation(translation),
+            ..default()
+        },
+        Interpolated::at(translation),
+        hitbox,
+        debris,
+    ));
+}
+
+// the players and enemies a piece can come down on
+type Crushable<H> = (&'static Hitbox, &'static mut H);
+
+/* Falls like the player does (GravityResource), through air, liquids and gases. It stops on the first row
+   it doesn't fit in and goes back into the map there. A player or enemy it comes down on stops it too,
+   it rests on top of them (and hurts them if it was falling fast) until they get out of the way.
+*/
+#[allow(clippy::too_many_arguments)]
+fn fall_debris(
+    mut commands: Commands,
+    mut map: ResMut<ParticleMap>,
+    time: Res<Time>,
+    grav_res: Res<GravityResource>,
+    mut sim_rng: ResMut<SimulationRng>,
+    mut death_event: EventWriter<Death>,
+    mut debris: Query<(Entity, &mut Debris, &mut Transform, &mut Hitbox)>,
+    mut players: Query<Crushable<Health>, (With<Player>, Without<Debris>)>,
+    mut enemies: Query<Crushable<EnemyHealth>, (With<Enemy>, Without<Debris>)>,
+) {
+    let deltat = time.delta_seconds();
+    for (entity, mut piece, mut transform, mut hitbox) in debris.iter_mut() {
+        piece.velocity = (piece.velocity - grav_res.gravity_force * deltat).max(-grav_res.terminal_velocity);
+        let speed = -piece.velocity;
+        let mut y = piece.y + piece.velocity * deltat / PARTICLE_SIZE;
+        let landed = (y.floor() as i32..piece.y.floor() as i32).rev().find(|row| !piece.fits_at(&map, *row));
+        if let Some(row) = landed {
+            y = (row + 1) as f32;
+        }
+
+        let moved = piece.hitbox_at(y);
+        let mut crushed = false;
+        for (other, mut health) in players.iter_mut() {
+            if moved.collides_with(other) && !hitbox.collides_with(other) {
+                crushed = true;
+                if speed >= CRUSH_MIN_SPEED {
+                    health.take_damage(speed * CRUSH_DAMAGE);
+                    if health.current == 0. {
+                        death_event.send(Death);
+                    }
+                }
+            }
+        }
+        for (other, mut health) in enemies.iter_mut() {
+            if moved.collides_with(other) && !hitbox.collides_with(other) {
+                crushed = true;
+                if speed >= CRUSH_MIN_SPEED {
+                    health.take_damage(speed * CRUSH_DAMAGE);
+                }
+            }
+        }
+        if crushed {
+            piece.velocity = 0.;
+            continue;
+        }
+
+        if landed.is_some() {
+            settle(&mut map, &piece, y as i32, speed, sim_rng.stream("debris"));
+            commands.entity(entity).despawn();
+            continue;
+        }
+        if y < (MIN_Y - FELL_OUT) as f32 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        piece.y = y;
+        transform.translation.y = y * PARTICLE_SIZE;
+        *hitbox = moved;
+    }
+}
+
+// puts a piece that landed back into the map. whatever flowed into where it lands is pushed up out of the way
+fn settle(map: &mut ParticleMap, piece: &Debris, row: i32, speed: f32, rng: &mut impl Rng) {
+    let registry = map.shared_registry();
+    let mut cells = piece.cells.clone();
+    cells.sort_by_key(|((x, y), _)| (*y, *x));
+    for ((x, y), particle) in cells {
+        let pos = (piece.x + x, row + y);
+        make_room(map, pos);
+        map.put_particle_at(pos, particle);
+        if speed < BREAK_SPEED {
+            continue;
+        }
+        if let Some(into) = registry.crumbles_into(particle.element) {
+            map.change_element_at(pos, into);
+            map.give_velocity(pos, Vec2::new(rng.gen_range(-CRUMBLE_SCATTER..=CRUMBLE_SCATTER), 0.));
+        }
+    }
+}
+
+// if nothing is found to move it into, the cell is crushed
+fn make_room(map: &mut ParticleMap, (x, y): (i32, i32)) {
+    if map.get_element_at((x, y)) == ParticleElement::Air {
+        return
+    }
+    if let Some(up) = (y + 1..=y + CHUNK_SIZE).find(|up| map.get_element_at((x, *up)) == ParticleElement::Air) {
+        map.move_cell((x, y), (x, up));
+    }
+}
+
+
+
+pub struct DebrisPlugin;
+impl Plugin for DebrisPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Death>();
+        // after the automata, so anything they dissolved this tick is looked at right away
+        app.add_systems(FixedUpdate, (detect_debris, fall_debris).chain().after(run_simulation_steps).run_if(in_state(AppState::InGame)));
+    }
+}
@@ -1,8 +1,63 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
 #[derive(Component)]
 pub struct Enemy;
 
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Movement {
+    Walk,  // walks towards the player, jumps when it runs into something
+    Fly,   // flies straight at the player, through the terrain
+    Stay,  // doesn't go anywhere, only falls
+}
+
+/* One kind of enemy on a planet, listed under `enemies` in the planet's file (see assets/planets).
+   Every enemy carries its kind around, so the systems know how it moves and what hurts it.
+*/
+#[derive(Component, Deserialize, Clone, Debug)]
+pub struct EnemyKind {
+    pub name: String,  // unique on the planet, saves remember enemies by it
+    pub sprite: String,
+    pub frame_size: (u32, u32),
+    #[serde(default = "one")]
+    pub frames: u32,
+    pub size: (f32, f32),
+    pub health: f32,
+    pub movement: Movement,
+    pub speed: f32,
+    #[serde(default = "jump_speed")]
+    pub jump: f32,
+    #[serde(default)]
+    pub hop_chance: f64,
+    #[serde(default)]
+    pub keeps_jumping: bool,
+    pub position: (f32, f32),
+    #[serde(default = "just_one")]
+    pub count: (u32, u32),
+    #[serde(default)]
+    pub scattered: bool,
+    #[serde(default)]
+    pub always_active: bool,
+    #[serde(default)]
+    pub hides_in_quicksand: bool,
+    #[serde(default)]
+    pub water_hurts: bool,
+    #[serde(default)]
+    pub splits: bool,
+}
+
+fn one() -> u32 {
+    1
+}
+
+fn jump_speed() -> f32 {
+    250.
+}
+
+fn just_one() -> (u32, u32) {
+    (1, 1)
+}
+
 #[derive(Component)]
 pub struct Jump {
     pub is_jumping: bool,
@@ -1,42 +1,11 @@
 use bevy::prelude::*;
 
-pub const W1_ENEMY_SPEED: f32 = 100.;
-pub const W1_ACCEL_RATE_X: f32 = 5000.;
-pub const W1_ACCEL_RATE_Y: f32 = 10800.;
-pub const W1_ANIM_TIME: f32 = 0.2;
-pub const W1_SPRITE_HEIGHT: u32 = 50;
-pub const W1_SPRITE_WIDTH: u32 = 30;
-
-pub const W2_ENEMY_SPEED: f32 = 2.;
-pub const W2_ACCEL_RATE_X: f32 = 1.;
-pub const W2_ACCEL_RATE_Y: f32 = 1.;
-pub const W2_SPRITE_HEIGHT: u32 = 62;
-pub const W2_SPRITE_WIDTH: u32 = 40;
-
-
-pub const W3_ENEMY_SPEED: f32 = 2.;
-pub const W3_ACCEL_RATE_X: f32 = 1.;
-pub const W3_ACCEL_RATE_Y: f32 = 1.;
-pub const W3_SPRITE_HEIGHT: u32 = 62;
-pub const W3_SPRITE_WIDTH: u32 = 40;
-
-pub const W4_ENEMY_SPEED: f32 = 100.;
-pub const W4_ACCEL_RATE_X: f32 = 5000.;
-pub const W4_ACCEL_RATE_Y: f32 = 10800.;
-pub const W4_ANIM_TIME: f32 = 0.2;
-pub const W4_SPRITE_HEIGHT: u32 = 30;
-pub const W4_SPRITE_WIDTH: u32 = 28;
-
-pub const W6_ENEMY_SPEED: f32 = 2.;
-pub const W6_ACCEL_RATE_X: f32 = 1.;
-pub const W6_ACCEL_RATE_Y: f32 = 1.5;
-pub const W6_SPRITE_HEIGHT: u32 = 40;
-pub const W6_SPRITE_WIDTH: u32 = 40;
-
-pub const W8_ENEMY_SPEED: f32 = 100.;
-pub const W8_ACCEL_RATE_X: f32 = 5000.;
-pub const W8_ACCEL_RATE_Y: f32 = 10800.;
-pub const W8_ANIM_TIME: f32 = 0.2;
-pub const W8_SPRITE_HEIGHT: u32 = 224;
-pub const W8_SPRITE_WIDTH: u32 = 224;
-
+// the same for every kind of enemy, the rest is in the planet files (see EnemyKind)
+pub const ENEMY_ACCEL_RATE_X: f32 = 5000.;
+pub const ENEMY_ACCEL_RATE_Y: f32 = 10800.;
+pub const ENEMY_ANIM_TIME: f32 = 0.2;
+
+// damage per second from what an enemy is standing in, see check_enemy_damage
+pub const WATER_DAMAGE: f32 = 180.;
+pub const TOXIC_GAS_DAMAGE: f32 = 600.;  // times how much of the enemy is in the gas
+pub const FIRE_DAMAGE: f32 = 18.;
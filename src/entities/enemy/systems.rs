@@ -1,14 +1,14 @@
-use std::mem::take;
 use rand::Rng;
-use bevy::{asset::io::embedded, prelude::*, scene::ron::de};
+use bevy::prelude::*;
 use crate::{
     common::{
-        death::Death, gravity::{Gravity, GravityResource}, hitbox::Hitbox, state::{AppState, GamePhase}
+        death::Death, gravity::{Gravity, GravityResource}, hitbox::Hitbox, seed::SimulationRng, state::{AppState, EnterPlanet}, timestep::{run_simulation_steps, Interpolated}
     },
     entities::{
         particle::resources::ParticleMap,
         player::{components::{AnimationFrameCount, AnimationTimer, Health, Player}, resources::PlayerSoundTracker, systems::take_damage},
     },
+    planets::definition::CurrentPlanet,
     LEVEL_H,
     LEVEL_W,
     WIN_W,
@@ -18,300 +18,90 @@ use super::{
     resources::*,
 };
 
-
+// Every kind of enemy on the planet (see `enemies` in the planet's file in assets/planets)
 pub fn initialize(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-    state: Res<State<GamePhase>>,
+    planet: CurrentPlanet,
+    mut sim_rng: ResMut<SimulationRng>,
 ) {
-    let game_state =  state.get();
-    if *game_state == GamePhase::Planet1 {
-        let enemy_sheet_handle = asset_server.load("enemy_walking.png");
-        //             used to be tilesize. removed TILE_SIZE and now at 100, but change as needed  \/
-        let enemy_layout = TextureAtlasLayout::from_grid(UVec2::splat(100), 4, 1, None, None);
-        let enemy_layout_len = enemy_layout.textures.len();
-        let enemy_layout_handle = texture_atlases.add(enemy_layout);
-        commands.spawn((
-            SpriteBundle {
-                texture: enemy_sheet_handle,
-                transform: Transform {
-                    // Julianne 10/8: For now, enemy is being spawned at WIN_W. This will need to be changed eventually.
-                    translation: Vec3::new(WIN_W / 2., 100.0, 900.),
-                    ..default()
-                },
-                sprite: Sprite {
-                    flip_x: false,
-                    ..default()
-                },
-                ..default()
-            },
-            TextureAtlas {
-                layout: enemy_layout_handle,
-                index: 0,
-            },
-            AnimationTimer(Timer::from_seconds(W1_ANIM_TIME, TimerMode::Repeating)),
-            AnimationFrameCount(enemy_layout_len),
-            Velocity::new(),
-            EnemyHealth::new(2.),
-            Gravity::new(),
-            Hitbox::new(40 as f32, 40 as f32, Vec2::new(0., -210.)),
-            DamageBox::new(50.0, 50.0, Vec2::new(0., -210.)),
-            Jump::new(),  
-            Enemy,
-        ));
-    } else if *game_state == GamePhase::Planet2 {
-        let enemy_sheet_handle = asset_server.load("planet_2/ice_cream.png");
-        //             used to be tilesize. removed TILE_SIZE and now at 100, but change as needed  \/
-        let enemy_layout = TextureAtlasLayout::from_grid(UVec2::new(W3_SPRITE_WIDTH, W3_SPRITE_HEIGHT), 1, 1, None, None);
-        let enemy_layout_len = enemy_layout.textures.len();
-        let enemy_layout_handle = texture_atlases.add(enemy_layout);
-        commands.spawn((
-            SpriteBundle {
-                texture: enemy_sheet_handle,
-                transform: Transform {
-                    // Julianne 10/8: For now, enemy is being spawned at WIN_W. This will need to be changed eventually.
-                    translation: Vec3::new(WIN_W / 2., 100.0, 900.),
-                    ..default()
-                },
-                sprite: Sprite {
-                    flip_x: false,
-                    ..default()
-                },
-                ..default()
-            },
-            TextureAtlas {
-                layout: enemy_layout_handle,
-                index: 0,
-            },
-            AnimationTimer(Timer::from_seconds(W1_ANIM_TIME, TimerMode::Repeating)),
-            AnimationFrameCount(enemy_layout_len),
-            Velocity::new(),
-            EnemyHealth::new(4.),
-            Gravity::new(),
-            Hitbox::new(W3_SPRITE_WIDTH as f32, W3_SPRITE_HEIGHT as f32, Vec2::new(0., -210.)),
-            DamageBox::new(W3_SPRITE_WIDTH as f32, W3_SPRITE_HEIGHT as f32, Vec2::new(0., -210.)),
-            Jump::new(),  
-            Enemy,
-        ));
-
-    } else if *game_state == GamePhase::Planet3 {
-        let enemy_sheet_handle = asset_server.load("planet_3/ghost.png");
-        //             used to be tilesize. removed TILE_SIZE and now at 100, but change as needed  \/
-        let enemy_layout = TextureAtlasLayout::from_grid(UVec2::new(W3_SPRITE_WIDTH, W3_SPRITE_HEIGHT), 1, 1, None, None);
-        let enemy_layout_len = enemy_layout.textures.len();
-        let enemy_layout_handle = texture_atlases.add(enemy_layout);
-        commands.spawn((
-            SpriteBundle {
-                texture: enemy_sheet_handle,
-                transform: Transform {
-                    // Julianne 10/8: For now, enemy is being spawned at WIN_W. This will need to be changed eventually.
-                    translation: Vec3::new(WIN_W / 2., 100.0, 900.),
-                    ..default()
-                },
-                sprite: Sprite {
-                    flip_x: false,
-                    ..default()
-                },
-                ..default()
-            },
-            TextureAtlas {
-                layout: enemy_layout_handle,
-                index: 0,
-            },
-            AnimationTimer(Timer::from_seconds(W1_ANIM_TIME, TimerMode::Repeating)),
-            AnimationFrameCount(enemy_layout_len),
-            Velocity::new(),
-            EnemyHealth::new(4.),
-            Gravity::new(),
-            Hitbox::new(W3_SPRITE_WIDTH as f32, W3_SPRITE_HEIGHT as f32, Vec2::new(0., -210.)),
-            DamageBox::new(W3_SPRITE_WIDTH as f32, W3_SPRITE_HEIGHT as f32, Vec2::new(0., -210.)),
-            Jump::new(),  
-            Enemy,
-        ));
-    } else if *game_state == GamePhase::Planet4 {
-         //let enemy_sheet_handle = asset_server.load("enemy_walking.png");
-         let enemy_sheet_handle = asset_server.load("planet_4/enemy_walking.png");
-         //             used to be tilesize. removed TILE_SIZE and now at 100, but change as needed  \/
-         let enemy_layout = TextureAtlasLayout::from_grid(UVec2::new(W4_SPRITE_WIDTH, W4_SPRITE_HEIGHT), 4, 1, None, None);
-         let enemy_layout_len = enemy_layout.textures.len();
-         let enemy_layout_handle = texture_atlases.add(enemy_layout);
-         commands.spawn((
-             SpriteBundle {
-                 texture: enemy_sheet_handle,
-                 transform: Transform {
-                     // Julianne 10/8: For now, enemy is being spawned at WIN_W. This will need to be changed eventually.
-                     translation: Vec3::new(WIN_W / 2., 100.0, 900.),
-                     ..default()
-                 },
-                 sprite: Sprite {
-                     flip_x: false,
-                     ..default()
-                 },
-                 ..default()
-             },
-             TextureAtlas {
-                 layout: enemy_layout_handle,
-                 index: 0,
-             },
-             AnimationTimer(Timer::from_seconds(W4_ANIM_TIME, TimerMode::Repeating)),
-             AnimationFrameCount(enemy_layout_len),
-             Velocity::new(),
-             EnemyHealth::new(5.),
-             Gravity::new(),
-             Hitbox::new(40 as f32, 40 as f32, Vec2::new(0., -210.)),
-             DamageBox::new(50.0, 50.0, Vec2::new(0., -210.)),
-             Jump::new(),  
-             Enemy,
-         ));
-
-    } else if *game_state == GamePhase::Planet5 {
-         //let enemy_sheet_handle = asset_server.load("enemy_walking.png");
-        let enemy_sheet_handle = asset_server.load("planet_5/enemy_walking.png");
-        //             used to be tilesize. removed TILE_SIZE and now at 100, but change as needed  \/
-        let enemy_layout = TextureAtlasLayout::from_grid(UVec2::splat(100), 4, 1, None, None);
-        let enemy_layout_len = enemy_layout.textures.len();
-        let enemy_layout_handle = texture_atlases.add(enemy_layout);
-        commands.spawn((
-            SpriteBundle {
-                texture: enemy_sheet_handle,
-                transform: Transform {
-                    // Julianne 10/8: For now, enemy is being spawned at WIN_W. This will need to be changed eventually.
-                    translation: Vec3::new(WIN_W / 2., 100.0, 900.),
-                    ..default()
-                },
-                sprite: Sprite {
-                    flip_x: false,
-                    ..default()
-                },
-                ..default()
-            },
-            TextureAtlas {
-                layout: enemy_layout_handle,
-                index: 0,
-            },
-            AnimationTimer(Timer::from_seconds(W1_ANIM_TIME, TimerMode::Repeating)),
-            AnimationFrameCount(enemy_layout_len),
-            Velocity::new(),
-            EnemyHealth::new(5.),
-            Gravity::new(),
-            Hitbox::new(40 as f32, 40 as f32, Vec2::new(0., -210.)),
-            DamageBox::new(50.0, 50.0, Vec2::new(0., -210.)),
-            Jump::new(),  
-            Enemy,
-        ));
-    } else if *game_state == GamePhase::Planet6 {
-
-        let mut rng = rand::thread_rng();
-        for _ in (0..rng.gen_range(10..20)){
-            let p6_enemy_sheet_handle = asset_server.load("planet_6/frog.png");
-            let p6_enemy_layout = TextureAtlasLayout::from_grid(UVec2::new(W6_SPRITE_WIDTH, W6_SPRITE_HEIGHT), 1, 1, None, None);
-            let p6_enemy_layout_len = p6_enemy_layout.textures.len();
-            let p6_enemy_layout_handle = texture_atlases.add(p6_enemy_layout);
-            commands.spawn((
-                SpriteBundle {
-                    texture: p6_enemy_sheet_handle,
-                    transform: Transform {
-                        // Julianne 10/8: For now, enemy is being spawned at WIN_W. This will need to be changed eventually.
-                        translation: Vec3::new(rng.gen_range((-LEVEL_W / 2.)..(LEVEL_W / 2.)), 100.0, 900.),
-                        ..default()
-                    },
-                    sprite: Sprite {
-                        flip_x: false,
-                        ..default()
-                    },
-                    ..default()
-                },
-                TextureAtlas {
-                    layout: p6_enemy_layout_handle,
-                    index: 0,
-                },
-                AnimationTimer(Timer::from_seconds(W1_ANIM_TIME, TimerMode::Repeating)),
-                AnimationFrameCount(p6_enemy_layout_len),
-                Velocity::new(),
-                EnemyHealth::new(100.),
-                Gravity::new(),
-                Hitbox::new(W6_SPRITE_WIDTH as f32, W6_SPRITE_HEIGHT as f32, Vec2::new(0., -210.)),
-                DamageBox::new(W6_SPRITE_WIDTH as f32, W6_SPRITE_HEIGHT as f32, Vec2::new(0., -210.)),
-                Jump::new(),  
-                Enemy,
-            ));
+    for kind in planet.get().enemies.iter() {
+        let (enemy_sheet_handle, enemy_layout_handle) = enemy_sprite(&asset_server, &mut texture_atlases, kind);
+
+        let rng = sim_rng.stream("enemy_spawn");
+        let count = rng.gen_range(kind.count.0..=kind.count.1.max(kind.count.0));
+        for _ in 0..count {
+            let x = if kind.scattered {
+                rng.gen_range((-LEVEL_W / 2.)..(LEVEL_W / 2.))
+            } else {
+                kind.position.0
+            };
+            spawn_enemy(&mut commands, kind, enemy_sheet_handle.clone(), enemy_layout_handle.clone(), Vec3::new(x, kind.position.1, 900.));
         }
+    }
+}
+
+pub fn enemy_sprite(
+    asset_server: &AssetServer,
+    texture_atlases: &mut Assets<TextureAtlasLayout>,
+    kind: &EnemyKind,
+) -> (Handle<Image>, Handle<TextureAtlasLayout>) {
+    let layout = TextureAtlasLayout::from_grid(UVec2::new(kind.frame_size.0, kind.frame_size.1), kind.frames, 1, None, None);
+    (asset_server.load(&kind.sprite), texture_atlases.add(layout))
+}
 
-    } else if *game_state == GamePhase::Planet7 {
-        let enemy_sheet_handle = asset_server.load("planet_7/blob.png");
-        //             used to be tilesize. removed TILE_SIZE and now at 100, but change as needed  \/
-        let enemy_layout = TextureAtlasLayout::from_grid(UVec2::new(W3_SPRITE_WIDTH, W3_SPRITE_HEIGHT), 1, 1, None, None);
-        let enemy_layout_len = enemy_layout.textures.len();
-        let enemy_layout_handle = texture_atlases.add(enemy_layout);
-        commands.spawn((
-            SpriteBundle {
-                texture: enemy_sheet_handle,
-                transform: Transform {
-                    // Julianne 10/8: For now, enemy is being spawned at WIN_W. This will need to be changed eventually.
-                    translation: Vec3::new(WIN_W / 2., 100.0, 900.),
-                    ..default()
-                },
-                sprite: Sprite {
-                    flip_x: false,
-                    ..default()
-                },
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    kind: &EnemyKind,
+    texture: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+    translation: Vec3,
+) -> Entity {
+    commands.spawn((
+        SpriteBundle {
+            texture,
+            transform: Transform {
+                translation,
                 ..default()
             },
-            TextureAtlas {
-                layout: enemy_layout_handle,
-                index: 0,
-            },
-            AnimationTimer(Timer::from_seconds(W1_ANIM_TIME, TimerMode::Repeating)),
-            AnimationFrameCount(enemy_layout_len),
-            Velocity::new(),
-            EnemyHealth::new(10.),
-            Gravity::new(),
-            Hitbox::new(40 as f32, 40 as f32, Vec2::new(0., -210.)),
-            DamageBox::new(50.0, 50.0, Vec2::new(0., -210.)),
-            Jump::new(),  
-            Enemy,
-        ));
-        
-    
-    } else if *game_state == GamePhase::Planet8 {
-        let enemy_sheet_handle = asset_server.load("planet_8/sun.png");
-        //             used to be tilesize. removed TILE_SIZE and now at 100, but change as needed  \/
-        let enemy_layout = TextureAtlasLayout::from_grid(UVec2::new(W8_SPRITE_WIDTH, W8_SPRITE_HEIGHT), 1, 1, None, None);
-        let enemy_layout_len = enemy_layout.textures.len();
-        let enemy_layout_handle = texture_atlases.add(enemy_layout);
-        commands.spawn((
-            SpriteBundle {
-                texture: enemy_sheet_handle,
-                transform: Transform {
-                    // Julianne 10/8: For now, enemy is being spawned at WIN_W. This will need to be changed eventually.
-                    translation: Vec3::new(WIN_W / 3., 300.0, 900.),
-                    ..default()
-                },
-                sprite: Sprite {
-                    flip_x: false,
-                    ..default()
-                },
+            sprite: Sprite {
+                flip_x: false,
                 ..default()
             },
-            TextureAtlas {
-                layout: enemy_layout_handle,
-                index: 0,
-            },
-            AnimationTimer(Timer::from_seconds(W1_ANIM_TIME, TimerMode::Repeating)),
-            AnimationFrameCount(enemy_layout_len),
-            Velocity::new(),
-            EnemyHealth::new(20.),
-            Gravity::new(),
-            Hitbox::new(W8_SPRITE_WIDTH as f32, W8_SPRITE_HEIGHT as f32, Vec2::new(0., -210.)),
-            DamageBox::new(W8_SPRITE_WIDTH as f32, W8_SPRITE_HEIGHT as f32, Vec2::new(0., -210.)),
-            Jump::new(),  
-            Enemy,
-        ));
+            ..default()
+        },
+        TextureAtlas {
+            layout,
+            index: 0,
+        },
+        AnimationTimer(Timer::from_seconds(ENEMY_ANIM_TIME, TimerMode::Repeating)),
+        AnimationFrameCount(kind.frames as usize),
+        Velocity::new(),
+        EnemyHealth::new(kind.health),
+        Gravity::new(),
+        // the hitbox is moved onto the enemy the first time it moves
+        Hitbox::new(kind.size.0, kind.size.1, Vec2::new(0., -210.)),
+        DamageBox::new(kind.size.0, kind.size.1, Vec2::new(0., -210.)),
+        Jump::new(),
+        Interpolated::new(),
+        kind.clone(),
+        Enemy,
+    )).id()
+}
+
+// how far left and right an enemy can go
+fn x_bounds(kind: &EnemyKind) -> (f32, f32) {
+    let width = kind.size.0;
+    if kind.always_active {
+        (-(LEVEL_W / 2.) + width / 2., LEVEL_W / 2. - width / 2.)
+    } else {
+        (-(WIN_W / 2.) + width / 2., LEVEL_W - (WIN_W / 2. + width / 2.))
     }
 }
 
 
+
 #[derive(Component, Clone, Debug)]
 pub struct DamageBox {
     pub width: f32,
@@ -334,282 +124,65 @@ impl DamageBox {
 
 pub fn enemy_gravity(
     time: Res<Time>, 
-    mut enemy: Query<(&mut Transform, &mut Velocity, &mut Gravity, &mut Hitbox, &mut Jump), With<Enemy>>, 
+    mut enemy: Query<(&mut Transform, &mut Velocity, &mut Gravity, &mut Hitbox, &mut Jump, &EnemyKind), With<Enemy>>, 
     hitboxes: Query<&Hitbox, Without<Enemy>>,
     grav_res: ResMut<GravityResource>,
-    state: Res<State<GamePhase>>,
+    map: Res<ParticleMap>,
+    mut sim_rng: ResMut<SimulationRng>,
 ) {
-    let game_state =  state.get();
-    if *game_state == GamePhase::Planet1 {
-        /*Julianne 10/8: This function is the same as player flight, but only makes the downward force on the enemy (no flight)*/
-        for (mut pt, mut pv, mut pg, mut hb, mut e_jump) in &mut enemy{
-
-            let deltat = time.delta_seconds();
-
-            //update gravity here
-            if e_jump.needs_jump && !e_jump.jumped{
-                pg.reset_g();
-                let acc_y = W1_ACCEL_RATE_Y * deltat;
-                pv.velocity.y = f32::min(250., pv.velocity.y + (1. * acc_y));
-                e_jump.needs_jump = false;
-                e_jump.is_jumping = true;
-            }else {
-                pg.update_g(&pv.velocity.y, &deltat, &grav_res);
-                pv.velocity.y = pg.get_g();
-            }
-            
-
-            let change = pv.velocity * deltat;
-            let new_pos = pt.translation + change.extend(0.);
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32, new_pos.xy());
-            //Bound enemy to within level height
-            if new_pos.y >= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) / 2.
-                && new_pos.y <= LEVEL_H - (W1_SPRITE_HEIGHT as f32) / 2.
-                && (!new_hb.all_enemy_collisions(&hitboxes)) && !e_jump.jumped
-            {    
-
-                    pt.translation = new_pos;
-                    *hb = new_hb; 
-                    e_jump.jumped = true;
-            }  
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32,Vec2::new(new_pos.x + 1., new_pos.y));
-            // Velocity is zero when enemy hits the ground
-            if pt.translation.y <= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) ||
-                new_hb.all_enemy_collisions(&hitboxes)
-            {
-                pv.velocity.y = 0.;
-                e_jump.is_jumping = false;
-                e_jump.jumped = false;
-                
-            }
-        }
-    } else if *game_state == GamePhase::Planet2 {
-
-    } else if *game_state == GamePhase::Planet3 {
-
-    } else if *game_state == GamePhase::Planet4 {
-        /*Julianne 10/8: This function is the same as player flight, but only makes the downward force on the enemy (no flight)*/
-        for (mut pt, mut pv, mut pg, mut hb, mut e_jump) in &mut enemy{
-
-            let deltat = time.delta_seconds();
-
-            //update gravity here
-            if e_jump.needs_jump && !e_jump.jumped{
-                pg.reset_g();
-                let acc_y = W4_ACCEL_RATE_Y * deltat;
-                pv.velocity.y = f32::min(250., pv.velocity.y + (1. * acc_y));
-                e_jump.needs_jump = false;
-                e_jump.is_jumping = true;
-            }else {
-                pg.update_g(&pv.velocity.y, &deltat, &grav_res);
-                pv.velocity.y = pg.get_g();
-            }
-            
-
-            let change = pv.velocity * deltat;
-            let new_pos = pt.translation + change.extend(0.);
-            let new_hb = Hitbox::new(W4_SPRITE_WIDTH as f32, W4_SPRITE_HEIGHT as f32, new_pos.xy());
-            //Bound enemy to within level height
-            if new_pos.y >= -(LEVEL_H / 2.) + (W4_SPRITE_HEIGHT as f32) / 2.
-                && new_pos.y <= LEVEL_H - (W4_SPRITE_HEIGHT as f32) / 2.
-                && (!new_hb.all_enemy_collisions(&hitboxes)) && !e_jump.jumped
-            {    
-
-                    pt.translation = new_pos;
-                    *hb = new_hb; 
-                    e_jump.jumped = true;
-            }  
-            let new_hb = Hitbox::new(W4_SPRITE_WIDTH as f32, W4_SPRITE_HEIGHT as f32,Vec2::new(new_pos.x + 1., new_pos.y));
-            // Velocity is zero when enemy hits the ground
-            if pt.translation.y <= -(LEVEL_H / 2.) + (W4_SPRITE_HEIGHT as f32) ||
-                new_hb.all_enemy_collisions(&hitboxes)
-            {
-                pv.velocity.y = 0.;
-                e_jump.is_jumping = false;
-                e_jump.jumped = false;
-                
-            }
+    /*Julianne 10/8: This function is the same as player flight, but only makes the downward force on the enemy (no flight)*/
+    for (mut pt, mut pv, mut pg, mut hb, mut e_jump, kind) in &mut enemy{
+        // flying enemies don't fall
+        if kind.movement == Movement::Fly {
+            continue;
         }
 
-    } else if *game_state == GamePhase::Planet5 {
-        /*Julianne 10/8: This function is the same as player flight, but only makes the downward force on the enemy (no flight)*/
-        for (mut pt, mut pv, mut pg, mut hb, mut e_jump) in &mut enemy{
-
-            let deltat = time.delta_seconds();
-
-            //update gravity here
-            if e_jump.needs_jump && !e_jump.jumped{
-                pg.reset_g();
-                let acc_y = W1_ACCEL_RATE_Y * deltat;
-                pv.velocity.y = f32::min(250., pv.velocity.y + (1. * acc_y));
-                e_jump.needs_jump = false;
-                e_jump.is_jumping = true;
-            }else {
-                pg.update_g(&pv.velocity.y, &deltat, &grav_res);
-                pv.velocity.y = pg.get_g();
-            }
-            
-
-            let change = pv.velocity * deltat;
-            let new_pos = pt.translation + change.extend(0.);
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32, new_pos.xy());
-            //Bound enemy to within level height
-            if new_pos.y >= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) / 2.
-                && new_pos.y <= LEVEL_H - (W1_SPRITE_HEIGHT as f32) / 2.
-                && (!new_hb.all_enemy_collisions(&hitboxes)) && !e_jump.jumped
-            {    
-
-                    pt.translation = new_pos;
-                    *hb = new_hb; 
-                    e_jump.jumped = true;
-            }  
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32,Vec2::new(new_pos.x + 1., new_pos.y));
-            // Velocity is zero when enemy hits the ground
-            if pt.translation.y <= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) ||
-                new_hb.all_enemy_collisions(&hitboxes)
-            {
-                pv.velocity.y = 0.;
-                e_jump.is_jumping = false;
-                e_jump.jumped = false;
-                
-            }
+        let deltat = time.delta_seconds();
+        let (width, height) = kind.size;
+
+        //update gravity here
+        if e_jump.needs_jump && !e_jump.jumped{
+            pg.reset_g();
+            let acc_y = ENEMY_ACCEL_RATE_Y * deltat;
+            pv.velocity.y = f32::min(kind.jump, pv.velocity.y + (1. * acc_y));
+            e_jump.needs_jump = false;
+            e_jump.is_jumping = true;
+        }else {
+            pg.update_g(&pv.velocity.y, &deltat, &grav_res);
+            pv.velocity.y = pg.get_g();
         }
 
-    } else if *game_state == GamePhase::Planet6 {
-
-        /*Julianne 10/8: This function is the same as player flight, but only makes the downward force on the enemy (no flight)*/
-        for (mut pt, mut pv, mut pg, mut hb, mut e_jump) in &mut enemy{
-
-            let deltat = time.delta_seconds();
-
-            //update gravity here
-            if e_jump.needs_jump && !e_jump.jumped{
-                pg.reset_g();
-                let acc_y = W1_ACCEL_RATE_Y * deltat;
-                pv.velocity.y = f32::min(250., pv.velocity.y + (1. * acc_y));
-                e_jump.needs_jump = false;
-                e_jump.is_jumping = true;
-            }else {
-                pg.update_g(&pv.velocity.y, &deltat, &grav_res);
-                pv.velocity.y = pg.get_g();
-            }
-
-            let mut rng = rand::thread_rng();
-            let jump_frequency = 100;
-            if (rng.gen_range(0..jump_frequency) == 0){
+        // some of them hop around on their own
+        if kind.hop_chance > 0. {
+            let rng = sim_rng.stream("enemy_ai");
+            if rng.gen_bool(kind.hop_chance.min(1.)) {
                 pg.reset_g();
                 pv.velocity.y += rng.gen_range(300.0..900.0);
             }
-            
-
-            let change = pv.velocity * deltat;
-            let new_pos = pt.translation + change.extend(0.);
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32, new_pos.xy());
-            //Bound enemy to within level height
-            if new_pos.y >= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) / 2.
-                && new_pos.y <= LEVEL_H - (W1_SPRITE_HEIGHT as f32) / 2.
-                && (!new_hb.all_enemy_collisions(&hitboxes)) && !e_jump.jumped
-            {    
-
-                    pt.translation = new_pos;
-                    *hb = new_hb; 
-                    e_jump.jumped = true;
-            }  
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32,Vec2::new(new_pos.x + 1., new_pos.y));
-            // Velocity is zero when enemy hits the ground
-            if pt.translation.y <= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) ||
-                new_hb.all_enemy_collisions(&hitboxes)
-            {
-                pv.velocity.y = 0.;
-                e_jump.is_jumping = false;
-                e_jump.jumped = false;
-                
-            }
         }
 
-    } else if *game_state == GamePhase::Planet7 {
-        for (mut pt, mut pv, mut pg, mut hb, mut e_jump) in &mut enemy{
-
-            let deltat = time.delta_seconds();
-
-            //update gravity here
-            if e_jump.needs_jump && !e_jump.jumped{
-                pg.reset_g();
-                let acc_y = W1_ACCEL_RATE_Y * deltat;
-                pv.velocity.y = f32::min(150., pv.velocity.y + (1. * acc_y));
-                e_jump.needs_jump = false;
-                e_jump.is_jumping = true;
-            }else {
-                pg.update_g(&pv.velocity.y, &deltat, &grav_res);
-                pv.velocity.y = pg.get_g();
-            }
+        let change = pv.velocity * deltat;
+        let new_pos = pt.translation + change.extend(0.);
+        let new_hb = Hitbox::new(width, height, new_pos.xy());
+        //Bound enemy to within level height
+        if new_pos.y >= -(LEVEL_H / 2.) + height / 2.
+            && new_pos.y <= LEVEL_H - height / 2.
+            && (!new_hb.all_enemy_collisions(&hitboxes, &map)) && !e_jump.jumped
+        {    
+
+                pt.translation = new_pos;
+                *hb = new_hb; 
+                e_jump.jumped = true;
+        }  
+        let new_hb = Hitbox::new(width, height, Vec2::new(new_pos.x + 1., new_pos.y));
+        // Velocity is zero when enemy hits the ground
+        if pt.translation.y <= -(LEVEL_H / 2.) + height ||
+            new_hb.all_enemy_collisions(&hitboxes, &map)
+        {
+            pv.velocity.y = 0.;
+            e_jump.is_jumping = false;
+            e_jump.jumped = false;
             
-
-            let change = pv.velocity * deltat;
-            let new_pos = pt.translation + change.extend(0.);
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32, new_pos.xy());
-            //Bound enemy to within level height
-            if new_pos.y >= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) / 2.
-                && new_pos.y <= LEVEL_H - (W1_SPRITE_HEIGHT as f32) / 2.
-                && (!new_hb.all_enemy_collisions(&hitboxes)) && !e_jump.jumped
-            {    
-
-                    pt.translation = new_pos;
-                    *hb = new_hb; 
-                    e_jump.jumped = true;
-            }  
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32,Vec2::new(new_pos.x + 1., new_pos.y));
-            // Velocity is zero when enemy hits the ground
-            if pt.translation.y <= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) ||
-                new_hb.all_enemy_collisions(&hitboxes)
-            {
-                pv.velocity.y = 0.;
-                e_jump.is_jumping = false;
-                e_jump.jumped = false;
-                
-            }
-        }
-    } else if *game_state == GamePhase::Planet8 {
-        for (mut pt, mut pv, mut pg, mut hb, mut e_jump) in &mut enemy{
-
-            let deltat = time.delta_seconds();
-
-            //update gravity here
-            if e_jump.needs_jump && !e_jump.jumped{
-                pg.reset_g();
-                let acc_y = W1_ACCEL_RATE_Y * deltat;
-                pv.velocity.y = f32::min(250., pv.velocity.y + (1. * acc_y));
-                e_jump.needs_jump = false;
-                e_jump.is_jumping = true;
-            }else {
-                pg.update_g(&pv.velocity.y, &deltat, &grav_res);
-                pv.velocity.y = pg.get_g();
-            }
-            
-
-            let change = pv.velocity * deltat;
-            let new_pos = pt.translation + change.extend(0.);
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32, new_pos.xy());
-            //Bound enemy to within level height
-            if new_pos.y >= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) / 2.
-                && new_pos.y <= LEVEL_H - (W1_SPRITE_HEIGHT as f32) / 2.
-                && (!new_hb.all_enemy_collisions(&hitboxes)) && !e_jump.jumped
-            {    
-
-                    pt.translation = new_pos;
-                    *hb = new_hb; 
-                    e_jump.jumped = true;
-            }  
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32,Vec2::new(new_pos.x + 1., new_pos.y));
-            // Velocity is zero when enemy hits the ground
-            if pt.translation.y <= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) ||
-                new_hb.all_enemy_collisions(&hitboxes)
-            {
-                pv.velocity.y = 0.;
-                e_jump.is_jumping = false;
-                e_jump.jumped = false;
-                
-            }
         }
     }
 }
@@ -627,54 +200,19 @@ pub fn animate_enemy(
         ),
         With<Enemy>,
     >,
-    state: Res<State<GamePhase>>,
 ) {
-    let game_state =  state.get();
-    if *game_state == GamePhase::Planet1 {
-        for (v, mut texture_atlas, mut timer, frame_count) in &mut enemy {
-            //let (v, mut texture_atlas, mut timer, frame_count) = enemy.single_mut();
-            let x_vel = Vec2::new(v.velocity.x, 0.);
-            //info!(x_vel.x);
-            if x_vel.cmpne(Vec2::ZERO).any() {
-                timer.tick(time.delta());
+    // enemies with a single frame just stay on it
+    for (v, mut texture_atlas, mut timer, frame_count) in &mut enemy {
+        let x_vel = Vec2::new(v.velocity.x, 0.);
+        if x_vel.cmpne(Vec2::ZERO).any() {
+            timer.tick(time.delta());
 
-                if timer.just_finished() {
-                texture_atlas.index = (texture_atlas.index + 1) % **frame_count;
-                }
+            if timer.just_finished() {
+            texture_atlas.index = (texture_atlas.index + 1) % **frame_count;
             }
         }
-    } else if *game_state == GamePhase::Planet2 {
-
-    } else if *game_state == GamePhase::Planet3 {
-
-    } else if *game_state == GamePhase::Planet4 {
-
-    } else if *game_state == GamePhase::Planet5 {
-        for (v, mut texture_atlas, mut timer, frame_count) in &mut enemy {
-            //let (v, mut texture_atlas, mut timer, frame_count) = enemy.single_mut();
-            let x_vel = Vec2::new(v.velocity.x, 0.);
-            //info!(x_vel.x);
-            if x_vel.cmpne(Vec2::ZERO).any() {
-                timer.tick(time.delta());
-
-                if timer.just_finished() {
-                texture_atlas.index = (texture_atlas.index + 1) % **frame_count;
-                }
-            }
-        }
-
-    } else if *game_state == GamePhase::Planet6 {
-
-    } else if *game_state == GamePhase::Planet7 {
-    
-    } else if *game_state == GamePhase::Planet8 {
-
     }
 }
-use bevy::prelude::*;
-
-
-
 
 
 /*Julianne 10/8: This finds if the player is on the left or right side
@@ -682,792 +220,182 @@ use bevy::prelude::*;
  This also check if enemy is within camera frame. If they are not, they will not move*/
 pub fn track_player(
     time: Res<Time>,
-    mut enemy: Query<(&mut Transform, &mut Velocity, &mut Sprite, &mut Hitbox, &mut AnimationTimer, &mut Jump), (With<Enemy>, Without<Player>)>,
+    mut enemy: Query<(&mut Transform, &mut Velocity, &mut Sprite, &mut Hitbox, &mut AnimationTimer, &mut Jump, &EnemyKind), (With<Enemy>, Without<Player>)>,
     mut player: Query<(&mut Transform, &mut Health), (With<Player>, Without<Enemy>)>,
     hitboxes: Query<&Hitbox, Without<Enemy>>, 
-    mut player_hitbox: Query<&Hitbox, (With<Player>, Without<Enemy>)>,
-    mut camera: Query<&mut Transform, (Without<Player>, Without<Enemy>, With<Camera>)>,
+    player_hitbox: Query<&Hitbox, (With<Player>, Without<Enemy>)>,
+    camera: Query<&Transform, (Without<Player>, Without<Enemy>, With<Camera>)>,
     mut death_event: EventWriter<Death>,
     asset_server: Res<AssetServer>,
     mut commands: Commands,
     mut sound_tracker: ResMut<PlayerSoundTracker>,
     map: ResMut<ParticleMap>,
-    
-    state: Res<State<GamePhase>>,
 ){
-    let game_state =  state.get();
-    
-    if *game_state == GamePhase::Planet1 {
-         //get enemy, player and camera
-         for (mut et, mut ev, mut es, mut ehb, mut timer, mut e_jump) in &mut enemy{
-            let (pt, mut player_health) = player.single_mut();
-            let player_hb = player_hitbox.single_mut();
-            
-            let cam_t = camera.single_mut();
-            let mut deltav_x = 0.;
-        
-            // Is enemy within the camera frame?
-            if et.translation.x > cam_t.translation.x + (WIN_W/2.){
-                return}
-            else{
-                timer.tick(time.delta());
-            }
-            //face player and walk towards player
-            
-            if pt.translation.x >= et.translation.x {
-                deltav_x += 1.;
-                es.flip_x=false;
-            }
-            else{
-                deltav_x -= 1.;
-                es.flip_x = true;
-            }
-        
-            let deltat = time.delta_seconds();
-            let acc_x = W1_ACCEL_RATE_X * deltat;
-        
-            if deltav_x != 0. {
-                if ev.velocity.y >= 0. {
-                    ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-W1_ENEMY_SPEED, W1_ENEMY_SPEED);
-                }
-                else {
-                    ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-W1_ENEMY_SPEED * 0.3, W1_ENEMY_SPEED * 0.3);
-                }
-            } else if ev.velocity.x.abs() > acc_x {
-                ev.velocity.x -= ev.velocity.x.signum() * acc_x;
-            } else {
-                ev.velocity.x = 0.;
-            }
-        
-            let change = ev.velocity * deltat;
-            let new_pos = et.translation + change.extend(0.);
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32, new_pos.xy());
-            
-        
-            let mut no_jump = false;
-            if player_hb.collides_with(&new_hb) {
-                no_jump = true;
-                take_damage(&mut player_health, 1.0, &mut death_event, &asset_server, &mut commands, &mut sound_tracker, &time);
-        
-                //info!("Player hit! Current health: {:?}", player_health.current); // 记录伤害
-                if player_health.current == 0.{
-                    death_event.send(Death);
-                }
-            }
-            if new_pos.x >= -(WIN_W / 2.) + (W1_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (WIN_W / 2. + (W1_SPRITE_WIDTH as f32) / 2.)
-                && new_hb.all_enemy_collisions(&hitboxes) && !e_jump.is_jumping && !no_jump
-            {
-                ev.velocity.x = 0.;
-                e_jump.needs_jump = true;
-            }
-            if new_pos.x >= -(WIN_W / 2.) + (W1_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (WIN_W / 2. + (W1_SPRITE_WIDTH as f32) / 2.)
-                && !new_hb.all_enemy_collisions(&hitboxes)
-            {
-                et.translation = new_pos;
-                *ehb = new_hb;
-            }
-            
+    //get enemy, player and camera
+    for (mut et, mut ev, mut es, mut ehb, mut timer, mut e_jump, kind) in &mut enemy{
+        if kind.movement == Movement::Stay {
+            continue;
         }
-       
-    }
-    else if *game_state == GamePhase::Planet2 {
-        for (mut enemy_transform, mut enemy_velocity, mut enemy_sprite, mut enemy_hb, mut timer, mut enemy_jump) in &mut enemy{
-            let (player_transform, mut player_health) = player.single_mut();
-            let player_hb = player_hitbox.single_mut();
-            let cam_transform = camera.single_mut();
-            let mut deltav_x = 0.;
-            let mut deltav_y = 0.;
-
-            // Is enemy within the camera frame?
-            if enemy_transform.translation.x > cam_transform.translation.x + (WIN_W/2.){
-                return}
-            else{
-                timer.tick(time.delta());
-            }
-
-            //face player and fly towards player
-            if player_transform.translation.x >= enemy_transform.translation.x {
-                deltav_x += 1.;
-                enemy_sprite.flip_x=false;
-            } else {
-                deltav_x -= 1.;
-                enemy_sprite.flip_x = true;
-            }
-            if player_transform.translation.y >= enemy_transform.translation.y {
-                deltav_y += 1.;
-            } else {
-                deltav_y -= 1.;
-            }
-
-            let deltat = time.delta_seconds();
-            let acc_x = W3_ACCEL_RATE_X * deltat;
-            let acc_y = W3_ACCEL_RATE_Y * deltat;
-            if deltav_x != 0. || deltav_y != 0. {
-                let new_velocity = Vec2::new(deltav_x, deltav_y) + enemy_velocity.velocity;
-                if new_velocity.length() > W3_ENEMY_SPEED {
-                    enemy_velocity.velocity = new_velocity.normalize() * W3_ENEMY_SPEED;
-                } else {
-                    enemy_velocity.velocity = new_velocity;
-                }
-                
-                let new_pos = enemy_transform.translation + enemy_velocity.velocity.extend(0.);
-                if new_pos.x >= -(WIN_W / 2.) + (W1_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (WIN_W / 2. + (W1_SPRITE_WIDTH as f32) / 2.)
-                && new_pos.y >= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) / 2.
-                && new_pos.y <= LEVEL_H - (W1_SPRITE_HEIGHT as f32) / 2. {
-                    enemy_transform.translation = new_pos;
-                    *enemy_hb = Hitbox::new(W3_SPRITE_WIDTH as f32, W3_SPRITE_HEIGHT as f32, enemy_transform.translation.xy());
-                }
-                if player_hb.collides_with(&enemy_hb) {
-                    take_damage(&mut player_health, 1.0, &mut death_event, &asset_server, &mut commands, &mut sound_tracker, &time);
-                    //info!("Player hit! Current health: {:?}", player_health.current); // 记录伤害
-                    if player_health.current == 0.{
-                        death_event.send(Death);
-                    }
-                }
-            }
+        let (pt, mut player_health) = player.single_mut();
+        let player_hb = player_hitbox.single();
+        let cam_t = camera.single();
+        let (width, height) = kind.size;
+        let (min_x, max_x) = x_bounds(kind);
+
+        // if the player is hiding in quicksand, the enemy will not track player
+        //player needs to be more than half submerged to hide
+        if kind.hides_in_quicksand && player_hb.ratio_of_quicksand_grid_tiles(&map) > 0.5 {
+            continue;
         }
-    }
-    else if *game_state == GamePhase::Planet3 {
-        for (mut enemy_transform, mut enemy_velocity, mut enemy_sprite, mut enemy_hb, mut timer, mut enemy_jump) in &mut enemy{
-            let (player_transform, mut player_health) = player.single_mut();
-            let player_hb = player_hitbox.single_mut();
-            let cam_transform = camera.single_mut();
-            let mut deltav_x = 0.;
-            let mut deltav_y = 0.;
-
-            // Is enemy within the camera frame?
-            if enemy_transform.translation.x > cam_transform.translation.x + (WIN_W/2.){
-                return}
-            else{
-                timer.tick(time.delta());
-            }
 
-            //face player and fly towards player
-            if player_transform.translation.x >= enemy_transform.translation.x {
-                deltav_x += 1.;
-                enemy_sprite.flip_x=false;
-            } else {
-                deltav_x -= 1.;
-                enemy_sprite.flip_x = true;
-            }
-            if player_transform.translation.y >= enemy_transform.translation.y {
-                deltav_y += 1.;
-            } else {
-                deltav_y -= 1.;
-            }
-
-            let deltat = time.delta_seconds();
-            let acc_x = W3_ACCEL_RATE_X * deltat;
-            let acc_y = W3_ACCEL_RATE_Y * deltat;
-            if deltav_x != 0. || deltav_y != 0. {
-                let new_velocity = Vec2::new(deltav_x, deltav_y) + enemy_velocity.velocity;
-                if new_velocity.length() > W3_ENEMY_SPEED {
-                    enemy_velocity.velocity = new_velocity.normalize() * W3_ENEMY_SPEED;
-                } else {
-                    enemy_velocity.velocity = new_velocity;
-                }
-                
-                let new_pos = enemy_transform.translation + enemy_velocity.velocity.extend(0.);
-                if new_pos.x >= -(WIN_W / 2.) + (W1_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (WIN_W / 2. + (W1_SPRITE_WIDTH as f32) / 2.)
-                && new_pos.y >= -(LEVEL_H / 2.) + (W1_SPRITE_HEIGHT as f32) / 2.
-                && new_pos.y <= LEVEL_H - (W1_SPRITE_HEIGHT as f32) / 2. {
-                    enemy_transform.translation = new_pos;
-                    *enemy_hb = Hitbox::new(W3_SPRITE_WIDTH as f32, W3_SPRITE_HEIGHT as f32, enemy_transform.translation.xy());
-                }
-                if player_hb.collides_with(&enemy_hb) {
-                    take_damage(&mut player_health, 1.0, &mut death_event, &asset_server, &mut commands, &mut sound_tracker, &time);
-                    //info!("Player hit! Current health: {:?}", player_health.current); // 记录伤害
-                    if player_health.current == 0.{
-                        death_event.send(Death);
-                    }
-                }
+        // Is enemy within the camera frame?
+        if !kind.always_active {
+            if et.translation.x > cam_t.translation.x + (WIN_W/2.){
+                continue;
             }
+            timer.tick(time.delta());
         }
 
-    } else if *game_state == GamePhase::Planet4 {
-        for (mut et, mut ev, mut es, mut ehb, mut timer, mut e_jump) in &mut enemy{
-            let (pt, mut player_health) = player.single_mut();
-            let player_hb = player_hitbox.single_mut();
-            
-            let cam_t = camera.single_mut();
-            let mut deltav_x = 0.;
-        
-            // if the player is hiding in quicksand, the enemy will not track player
-            let ratio_of_quicksand_particles = player_hb.ratio_of_quicksand_grid_tiles(&map);
-            //player needs to be more than half submerged to hide
-            if ratio_of_quicksand_particles > 0.5 {
-                return;
-            }
-            
-            // Is enemy within the camera frame?
-            if et.translation.x > cam_t.translation.x + (WIN_W/2.){
-                return}
-            else{
-                timer.tick(time.delta());
-            }
-            //face player and walk towards player
-            if pt.translation.x >= et.translation.x {
-                deltav_x += 1.;
-                es.flip_x=false;
-            }
-            else{
-                deltav_x -= 1.;
-                es.flip_x = true;
-            }
-        
-            let deltat = time.delta_seconds();
-            let acc_x = W4_ACCEL_RATE_X * deltat;
-        
-            if deltav_x != 0. {
-                if ev.velocity.y >= 0. {
-                    ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-W4_ENEMY_SPEED, W4_ENEMY_SPEED);
-                }
-                else {
-                    ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-W4_ENEMY_SPEED * 0.3, W4_ENEMY_SPEED * 0.3);
-                }
-            } else if ev.velocity.x.abs() > acc_x {
-                ev.velocity.x -= ev.velocity.x.signum() * acc_x;
-            } else {
-                ev.velocity.x = 0.;
-            }
-        
-            let change = ev.velocity * deltat;
-            let new_pos = et.translation + change.extend(0.);
-            let new_hb = Hitbox::new(W4_SPRITE_WIDTH as f32, W4_SPRITE_HEIGHT as f32, new_pos.xy());
-            
-        
-            let mut no_jump = false;
-            if player_hb.collides_with(&new_hb) {
-                no_jump = true;
-                take_damage(&mut player_health, 1.0, &mut death_event, &asset_server, &mut commands, &mut sound_tracker, &time);
-        
-                //info!("Player hit! Current health: {:?}", player_health.current); // 记录伤害
-                if player_health.current == 0.{
-                    death_event.send(Death);
-                }
-            }
-            if new_pos.x >= -(WIN_W / 2.) + (W4_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (WIN_W / 2. + (W4_SPRITE_WIDTH as f32) / 2.)
-                && new_hb.all_enemy_collisions(&hitboxes) && !e_jump.is_jumping && !no_jump
-            {
-                ev.velocity.x = 0.;
-                e_jump.needs_jump = true;
-            }
-            if new_pos.x >= -(WIN_W / 2.) + (W4_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (WIN_W / 2. + (W4_SPRITE_WIDTH as f32) / 2.)
-                && !new_hb.all_enemy_collisions(&hitboxes)
-            {
-                et.translation = new_pos;
-                *ehb = new_hb;
-            }
-            
+        //face player and walk (or fly) towards player
+        let mut deltav_x = 0.;
+        if pt.translation.x >= et.translation.x {
+            deltav_x += 1.;
+            es.flip_x=false;
+        }
+        else{
+            deltav_x -= 1.;
+            es.flip_x = true;
+        }
+        if kind.keeps_jumping {
+            e_jump.needs_jump = true;
         }
-        
 
-    } else if *game_state == GamePhase::Planet5 {
-         //get enemy, player and camera
-         for (mut et, mut ev, mut es, mut ehb, mut timer, mut e_jump) in &mut enemy{
-            let (pt, mut player_health) = player.single_mut();
-            let player_hb = player_hitbox.single_mut();
-            
-            let cam_t = camera.single_mut();
-            let mut deltav_x = 0.;
-        
-            // if the player is hiding in quicksand, the enemy will not track player
-            let ratio_of_quicksand_particles = player_hb.ratio_of_quicksand_grid_tiles(&map);
-            //player needs to be more than half submerged to hide
-            if ratio_of_quicksand_particles > 0.5 {
-                return;
-            }
-            
-            // Is enemy within the camera frame?
-            if et.translation.x > cam_t.translation.x + (WIN_W/2.){
-                return}
-            else{
-                timer.tick(time.delta());
-            }
-            //face player and walk towards player
-            if pt.translation.x >= et.translation.x {
-                deltav_x += 1.;
-                es.flip_x=false;
-            }
-            else{
-                deltav_x -= 1.;
-                es.flip_x = true;
-            }
-        
-            let deltat = time.delta_seconds();
-            let acc_x = W1_ACCEL_RATE_X * deltat;
-        
-            if deltav_x != 0. {
-                if ev.velocity.y >= 0. {
-                    ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-W1_ENEMY_SPEED, W1_ENEMY_SPEED);
-                }
-                else {
-                    ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-W1_ENEMY_SPEED * 0.3, W1_ENEMY_SPEED * 0.3);
-                }
-            } else if ev.velocity.x.abs() > acc_x {
-                ev.velocity.x -= ev.velocity.x.signum() * acc_x;
+        if kind.movement == Movement::Fly {
+            let deltav_y = if pt.translation.y >= et.translation.y { 1. } else { -1. };
+            let new_velocity = Vec2::new(deltav_x, deltav_y) + ev.velocity;
+            if new_velocity.length() > kind.speed {
+                ev.velocity = new_velocity.normalize() * kind.speed;
             } else {
-                ev.velocity.x = 0.;
-            }
-        
-            let change = ev.velocity * deltat;
-            let new_pos = et.translation + change.extend(0.);
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32, new_pos.xy());
-            
-        
-            let mut no_jump = false;
-            if player_hb.collides_with(&new_hb) {
-                no_jump = true;
-                take_damage(&mut player_health, 1.0, &mut death_event, &asset_server, &mut commands, &mut sound_tracker, &time);
-        
-                //info!("Player hit! Current health: {:?}", player_health.current); // 记录伤害
-                if player_health.current == 0.{
-                    death_event.send(Death);
-                }
-            }
-            if new_pos.x >= -(WIN_W / 2.) + (W1_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (WIN_W / 2. + (W1_SPRITE_WIDTH as f32) / 2.)
-                && new_hb.all_enemy_collisions(&hitboxes) && !e_jump.is_jumping && !no_jump
-            {
-                ev.velocity.x = 0.;
-                e_jump.needs_jump = true;
+                ev.velocity = new_velocity;
             }
-            if new_pos.x >= -(WIN_W / 2.) + (W1_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (WIN_W / 2. + (W1_SPRITE_WIDTH as f32) / 2.)
-                && !new_hb.all_enemy_collisions(&hitboxes)
-            {
+
+            let new_pos = et.translation + ev.velocity.extend(0.);
+            if new_pos.x >= min_x
+            && new_pos.x <= max_x
+            && new_pos.y >= -(LEVEL_H / 2.) + height / 2.
+            && new_pos.y <= LEVEL_H - height / 2. {
                 et.translation = new_pos;
-                *ehb = new_hb;
-            }
-            
-        }
-        
-    }
-    else if *game_state == GamePhase::Planet6 {
-        //get enemy, player and camera
-        for (mut et, mut ev, mut es, mut ehb, mut timer, mut e_jump) in &mut enemy{
-            let (pt, mut player_health) = player.single_mut();
-            let player_hb = player_hitbox.single_mut();
-            let cam_t = camera.single_mut();
-            let mut deltav_x = 0.;
-        
-            
-            // Is enemy within the camera frame?
-            //face player and walk towards player
-            if pt.translation.x >= et.translation.x {
-                deltav_x += 1.;
-                es.flip_x=false;
-            }
-            else{
-                deltav_x -= 1.;
-                es.flip_x = true;
+                *ehb = Hitbox::new(width, height, et.translation.xy());
             }
-        
-            let deltat = time.delta_seconds();
-            let acc_x = W1_ACCEL_RATE_X * deltat;
-        
-            if deltav_x != 0. {
-                if ev.velocity.y >= 0. {
-                    ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-W1_ENEMY_SPEED, W1_ENEMY_SPEED);
-                }
-                else {
-                    ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-W1_ENEMY_SPEED * 0.3, W1_ENEMY_SPEED * 0.3);
-                }
-            } else if ev.velocity.x.abs() > acc_x {
-                ev.velocity.x -= ev.velocity.x.signum() * acc_x;
-            } else {
-                ev.velocity.x = 0.;
-            }
-            
-            let change = ev.velocity * deltat;
-            let new_pos = et.translation + change.extend(0.);
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32, new_pos.xy());
-            
-        
-            let mut no_jump = false;
-            if player_hb.collides_with(&new_hb) {
-                no_jump = true;
+            if player_hb.collides_with(&ehb) {
                 take_damage(&mut player_health, 1.0, &mut death_event, &asset_server, &mut commands, &mut sound_tracker, &time);
-        
                 //info!("Player hit! Current health: {:?}", player_health.current); // 记录伤害
                 if player_health.current == 0.{
                     death_event.send(Death);
                 }
             }
-            if new_pos.x >= -(LEVEL_W / 2.) + (W1_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (LEVEL_W / 2. + (W1_SPRITE_WIDTH as f32) / 2.)
-                && new_hb.all_enemy_collisions(&hitboxes) && !e_jump.is_jumping && !no_jump
-            {
-                ev.velocity.x = 0.;
-                e_jump.needs_jump = true;
-            }
-            if new_pos.x >= -(LEVEL_W / 2.) + (W1_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (LEVEL_W / 2. + (W1_SPRITE_WIDTH as f32) / 2.)
-                && !new_hb.all_enemy_collisions(&hitboxes)
-            {
-                et.translation = new_pos;
-                *ehb = new_hb;
-            }
-            
+            continue;
         }
 
-    }
-    else if *game_state == GamePhase::Planet7 {
-    
-    
+        let deltat = time.delta_seconds();
+        let acc_x = ENEMY_ACCEL_RATE_X * deltat;
 
-        //get enemy, player and camera
-        for (mut et, mut ev, mut es, mut ehb, mut timer, mut e_jump) in &mut enemy{
-            let (pt, mut player_health) = player.single_mut();
-            let player_hb = player_hitbox.single_mut();
-            
-            let cam_t = camera.single_mut();
-            let mut deltav_x = 0.;
-        
-            // Is enemy within the camera frame?
-            if et.translation.x > cam_t.translation.x + (WIN_W/2.){
-                return}
-            else{
-                timer.tick(time.delta());
-            }
-            //face player and walk towards player
-            if pt.translation.x >= et.translation.x {
-                deltav_x += 1.;
-                es.flip_x=false;
-                e_jump.needs_jump = true;
+        if deltav_x != 0. {
+            if ev.velocity.y >= 0. {
+                ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-kind.speed, kind.speed);
             }
-            else{
-                deltav_x -= 1.;
-                es.flip_x = true;
-                e_jump.needs_jump = true;
+            else {
+                ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-kind.speed * 0.3, kind.speed * 0.3);
             }
-        
-            let deltat = time.delta_seconds();
-            let acc_x = W1_ACCEL_RATE_X * deltat;
-        
-            if deltav_x != 0. {
-                if ev.velocity.y >= 0. {
-                    ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-W1_ENEMY_SPEED, W1_ENEMY_SPEED);
-                }
-                else {
-                    ev.velocity.x = (ev.velocity.x + deltav_x * acc_x).clamp(-W1_ENEMY_SPEED * 0.3, W1_ENEMY_SPEED * 0.3);
-                }
-            } else if ev.velocity.x.abs() > acc_x {
-                ev.velocity.x -= ev.velocity.x.signum() * acc_x;
-            } else {
-                ev.velocity.x = 0.;
-            }
-        
-            let change = ev.velocity * deltat;
-            let new_pos = et.translation + change.extend(0.);
-            let new_hb = Hitbox::new(W1_SPRITE_WIDTH as f32, W1_SPRITE_HEIGHT as f32, new_pos.xy());
-            
-        
-            let mut no_jump = false;
-            if player_hb.collides_with(&new_hb) {
-                no_jump = true;
-                take_damage(&mut player_health, 1.0, &mut death_event, &asset_server, &mut commands, &mut sound_tracker, &time);
-        
-                //info!("Player hit! Current health: {:?}", player_health.current); // 记录伤害
-                if player_health.current == 0.{
-                    death_event.send(Death);
-                }
-            }
-            if new_pos.x >= -(WIN_W / 2.) + (W1_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (WIN_W / 2. + (W1_SPRITE_WIDTH as f32) / 2.)
-                && new_hb.all_enemy_collisions(&hitboxes) && !e_jump.is_jumping && !no_jump
-            {
-                ev.velocity.x = 0.;
-                e_jump.needs_jump = true;
-            }
-            if new_pos.x >= -(WIN_W / 2.) + (W1_SPRITE_WIDTH as f32) / 2.
-                && new_pos.x <= LEVEL_W - (WIN_W / 2. + (W1_SPRITE_WIDTH as f32) / 2.)
-                && !new_hb.all_enemy_collisions(&hitboxes)
-            {
-                et.translation = new_pos;
-                *ehb = new_hb;
-            }
-            
+        } else if ev.velocity.x.abs() > acc_x {
+            ev.velocity.x -= ev.velocity.x.signum() * acc_x;
+        } else {
+            ev.velocity.x = 0.;
         }
-        
-    }
-    else if *game_state == GamePhase::Planet8 {
-    }
 
-    
-}
+        let change = ev.velocity * deltat;
+        let new_pos = et.translation + change.extend(0.);
+        let new_hb = Hitbox::new(width, height, new_pos.xy());
 
+        let mut no_jump = false;
+        if player_hb.collides_with(&new_hb) {
+            no_jump = true;
+            take_damage(&mut player_health, 1.0, &mut death_event, &asset_server, &mut commands, &mut sound_tracker, &time);
 
-pub fn check_enemy_damage(
-    mut query: Query<(Entity, &mut Hitbox, &mut EnemyHealth), With<Enemy>>,
-    map: ResMut<ParticleMap>,
-    state: Res<State<GamePhase>>,
-){
-    let game_state =  state.get();
-    if *game_state == GamePhase::Planet1 {
-        for (entity, ehb, mut eHealth) in query.iter_mut() {
-            if ehb.are_any_grid_tiles_water(&map) {
-                // info!("Enemy hit by water particle");
-                eHealth.hp -= 1.;
+            //info!("Player hit! Current health: {:?}", player_health.current); // 记录伤害
+            if player_health.current == 0.{
+                death_event.send(Death);
             }
         }
-    }
-    else if *game_state == GamePhase::Planet2 {
-        for (entity, ehb, mut eHealth) in query.iter_mut() {
-            if ehb.are_any_grid_tiles_water(&map) {
-                // info!("Enemy hit by water particle");
-                eHealth.hp -= 1.;
-            }
-        }
-
-    }
-    else if *game_state == GamePhase::Planet3 {
-        for (entity, ehb, mut eHealth) in query.iter_mut() {
-            if ehb.are_any_grid_tiles_water(&map) {
-                // info!("Enemy hit by water particle");
-                eHealth.hp -= 1.;
-            }
-        }
-    }
-    else if *game_state == GamePhase::Planet4 {
-        for (entity, ehb, mut eHealth) in query.iter_mut() {
-            if ehb.are_any_grid_tiles_water(&map) {
-                // info!("Enemy hit by water particle");
-                eHealth.hp -= 1.;
-            }
+        if new_pos.x >= min_x
+            && new_pos.x <= max_x
+            && new_hb.all_enemy_collisions(&hitboxes, &map) && !e_jump.is_jumping && !no_jump
+        {
+            ev.velocity.x = 0.;
+            e_jump.needs_jump = true;
         }
-
-    }
-    else if *game_state == GamePhase::Planet5 {
-
-    }
-    else if *game_state == GamePhase::Planet6 {
-
-    }
-    else if *game_state == GamePhase::Planet7 {
-        for (entity, ehb, mut eHealth) in query.iter_mut() {
-            if ehb.are_any_grid_tiles_water(&map) {
-                // info!("Enemy hit by water particle");
-                eHealth.hp -= 1.;
-            }
-        }
-    }
-    else if *game_state == GamePhase::Planet8 {
-        for (entity, ehb, mut eHealth) in query.iter_mut() {
-            if ehb.are_any_grid_tiles_water(&map) {
-                // info!("Enemy hit by water particle");
-                eHealth.hp -= 1.;
-            }
+        if new_pos.x >= min_x
+            && new_pos.x <= max_x
+            && !new_hb.all_enemy_collisions(&hitboxes, &map)
+        {
+            et.translation = new_pos;
+            *ehb = new_hb;
         }
     }
 }
-// pub fn check_enemy_death(
-//     mut commands: Commands,
-//     query: Query<(Entity, &mut Hitbox, &mut EnemyHealth), With<Enemy>>,
-// ){
-//     for (entity, ehb, e_health) in query.iter() {
-//         if e_health.hp <= 0. {
-//             commands.entity(entity).despawn();
-//         }
-//     }
 
 
-pub fn check_enemy_death(
-    mut commands: Commands,
-    mut query: Query<(
-        Entity,
-        &Transform, 
-        &Handle<Image>, 
-        &TextureAtlas,
-        &Sprite,
-        &mut Hitbox,
-        &mut EnemyHealth
-    ), With<Enemy>>,
+// everything in the level that hurts enemies, scaled by the tick so it doesn't depend on the tick rate.
+// water only hurts the kinds with water_hurts
+pub fn check_enemy_damage(
+    time: Res<Time>,
+    mut query: Query<(&Hitbox, &mut EnemyHealth, &EnemyKind), With<Enemy>>,
     map: ResMut<ParticleMap>,
-    state: Res<State<GamePhase>>,
 ){
-    //TODO: Check if collided with blaster particle 
-    for (entity, et, texture, atlas, sprite, mut ehb, mut enemy_health) in query.iter_mut() {
-        if ehb.are_any_grid_tiles_water(&map) {
-            enemy_health.take_damage(2.);
+    let dt = time.delta_seconds();
+    for (ehb, mut e_health, kind) in query.iter_mut() {
+        if kind.water_hurts && ehb.are_any_grid_tiles_water(&map) {
+            // info!("Enemy hit by water particle");
+            e_health.take_damage(WATER_DAMAGE * dt);
         }
-        if ehb.ratio_of_toxic_gas_tiles(&map) > 0.2 {
-            enemy_health.take_damage(ehb.ratio_of_toxic_gas_tiles(&map) * 10.);
+        let toxic = ehb.ratio_of_toxic_gas_tiles(&map);
+        if toxic > 0.2 {
+            e_health.take_damage(toxic * TOXIC_GAS_DAMAGE * dt);
         }
-        if (enemy_health.hp <= 0.){
-            commands.entity(entity).despawn();
+        if ehb.ratio_of_fire_grid_tiles(&map) > 0. {
+            e_health.take_damage(FIRE_DAMAGE * dt);
         }
     }
+}
 
-    for (entity, et, texture, atlas, sprite, mut ehb, mut e_health) in &query {
+pub fn check_enemy_death(
+    mut commands: Commands,
+    query: Query<(Entity, &Transform, &Handle<Image>, &TextureAtlas, &EnemyHealth, &EnemyKind), With<Enemy>>,
+){
+    for (entity, et, texture, atlas, e_health, kind) in &query {
         if e_health.hp <= 0. {
-            if *state == GamePhase::Planet7{
-            let spawn_left_x = et.translation.x - 40.0;
-            let spawn_right_x = et.translation.x + 40.0;
-
-            commands.spawn((
-                SpriteBundle {
-                    texture: texture.clone(),
-                    transform: Transform {
-                        translation: Vec3::new(spawn_left_x, et.translation.y, et.translation.z),
-                        ..Default::default()
-                    },
-                    sprite: Sprite {
-                        flip_x: sprite.flip_x,
-                        ..*sprite
-                    },
-                    ..Default::default()
-                },
-                TextureAtlas {
-                    layout: atlas.layout.clone(),
-                    index: atlas.index,
-                },
-                AnimationTimer(Timer::from_seconds(W1_ANIM_TIME, TimerMode::Repeating)),
-                AnimationFrameCount(atlas.index),
-                Velocity::new(),
-                EnemyHealth::new(10.),
-                Gravity::new(),
-                Hitbox::new(40.0, 40.0, Vec2::new(0., -210.)),
-                DamageBox::new(50.0, 50.0, Vec2::new(0., -210.)),
-                Jump::new(),
-                Enemy,
-            ));
-
-            commands.spawn((
-                SpriteBundle {
-                    texture: texture.clone(),
-                    transform: Transform {
-                        translation: Vec3::new(spawn_right_x, et.translation.y, et.translation.z),
-                        ..Default::default()
-                    },
-                    sprite: Sprite {
-                        flip_x: sprite.flip_x,
-                        ..*sprite
-                    },
-                    ..Default::default()
-                },
-                TextureAtlas {
-                    layout: atlas.layout.clone(),
-                    index: atlas.index,
-                },
-                AnimationTimer(Timer::from_seconds(W1_ANIM_TIME, TimerMode::Repeating)),
-                AnimationFrameCount(atlas.index),
-                Velocity::new(),
-                EnemyHealth::new(10.),
-                Gravity::new(),
-                Hitbox::new(40.0, 40.0, Vec2::new(0., -210.)),
-                DamageBox::new(50.0, 50.0, Vec2::new(0., -210.)),
-                Jump::new(),
-                Enemy,
-            ));
-        }
+            // i.e. the blobs, two smaller ones come out either side
+            if kind.splits {
+                for offset in [-40.0, 40.0] {
+                    spawn_enemy(&mut commands, kind, texture.clone(), atlas.layout.clone(), et.translation + Vec3::new(offset, 0., 0.));
+                }
+            }
             commands.entity(entity).despawn();
         }
     }
 }
 
-// pub fn check_enemy_death(
-//     mut commands: Commands,
-//     query: Query<(
-//         Entity, 
-//         &Transform, 
-//         &Handle<Image>, 
-//         &TextureAtlas,  
-//         &Sprite,         
-//         &EnemyHealth
-//     ), With<Enemy>>,
-//     state: Res<State<GamePhase>>,
-// ) {
-//     for (entity, et, texture, atlas, sprite, e_health) in &query {
-//         if e_health.hp <= 0. {
-//             if *state == GamePhase::Planet7{
-//             let spawn_left_x = et.translation.x - 40.0;
-//             let spawn_right_x = et.translation.x + 40.0;
-
-//             commands.spawn((
-//                 SpriteBundle {
-//                     texture: texture.clone(),
-//                     transform: Transform {
-//                         translation: Vec3::new(spawn_left_x, et.translation.y, et.translation.z),
-//                         ..Default::default()
-//                     },
-//                     sprite: Sprite {
-//                         flip_x: sprite.flip_x,
-//                         ..*sprite
-//                     },
-//                     ..Default::default()
-//                 },
-//                 TextureAtlas {
-//                     layout: atlas.layout.clone(),
-//                     index: atlas.index,
-//                 },
-//                 AnimationTimer(Timer::from_seconds(W1_ANIM_TIME, TimerMode::Repeating)),
-//                 AnimationFrameCount(atlas.index),
-//                 Velocity::new(),
-//                 EnemyHealth::new(10.),
-//                 Gravity::new(),
-//                 Hitbox::new(40.0, 40.0, Vec2::new(0., -210.)),
-//                 DamageBox::new(50.0, 50.0, Vec2::new(0., -210.)),
-//                 Jump::new(),
-//                 Enemy,
-//             ));
-
-//             commands.spawn((
-//                 SpriteBundle {
-//                     texture: texture.clone(),
-//                     transform: Transform {
-//                         translation: Vec3::new(spawn_right_x, et.translation.y, et.translation.z),
-//                         ..Default::default()
-//                     },
-//                     sprite: Sprite {
-//                         flip_x: sprite.flip_x,
-//                         ..*sprite
-//                     },
-//                     ..Default::default()
-//                 },
-//                 TextureAtlas {
-//                     layout: atlas.layout.clone(),
-//                     index: atlas.index,
-//                 },
-//                 AnimationTimer(Timer::from_seconds(W1_ANIM_TIME, TimerMode::Repeating)),
-//                 AnimationFrameCount(atlas.index),
-//                 Velocity::new(),
-//                 EnemyHealth::new(10.),
-//                 Gravity::new(),
-//                 Hitbox::new(40.0, 40.0, Vec2::new(0., -210.)),
-//                 DamageBox::new(50.0, 50.0, Vec2::new(0., -210.)),
-//                 Jump::new(),
-//                 Enemy,
-//             ));
-//         }
-//             commands.entity(entity).despawn();
-//         }
-//     }
-// }
 
 pub struct EnemyPlugin;
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::InGame), initialize)
-        //.add_systems(OnEnter(GamePhase::Planet1), initialize)
-        .add_systems(OnEnter(GamePhase::Planet2), initialize)
-        .add_systems(OnEnter(GamePhase::Planet3), initialize)
-        .add_systems(OnEnter(GamePhase::Planet4), initialize)
-        .add_systems(OnEnter(GamePhase::Planet5), initialize)
-        .add_systems(OnEnter(GamePhase::Planet6), initialize)
-        .add_systems(OnEnter(GamePhase::Planet7), initialize)
-        .add_systems(OnEnter(GamePhase::Planet8), initialize)
+        app.add_systems(EnterPlanet, initialize)
         //app.add_systems(PreUpdate,  initialize.run_if(state_changed::<GamePhase>))
-        .add_systems(Update, enemy_gravity.after(track_player).run_if(in_state(AppState::InGame)))
-        .add_systems(Update, track_player.run_if(in_state(AppState::InGame)))
-        .add_systems(Update, animate_enemy.after(track_player).run_if(in_state(AppState::InGame)))
-        .add_systems(Update, check_enemy_damage.run_if(in_state(AppState::InGame)))
-        .add_systems(Update, check_enemy_death.run_if(in_state(AppState::InGame)).after(check_enemy_damage));
+        // enemy physics runs on the fixed tick, see common/timestep.rs
+        .add_systems(FixedUpdate, enemy_gravity.after(track_player).run_if(in_state(AppState::InGame)))
+        .add_systems(FixedUpdate, track_player.run_if(in_state(AppState::InGame)))
+        .add_systems(Update, animate_enemy.run_if(in_state(AppState::InGame)))
+        // damage is per tick, so it's the same at any frame rate. after the particles have moved this tick
+        .add_systems(FixedUpdate, (check_enemy_damage, check_enemy_death).chain().after(run_simulation_steps).run_if(in_state(AppState::InGame)));
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,6 @@
 // We could just move all entities into here...
 
+pub mod debris;
 pub mod enemy;
 pub mod particle;
 pub mod player;
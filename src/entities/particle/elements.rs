@@ -0,0 +1,319 @@
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::HashMap,
+};
+use rand::Rng;
+use serde::Deserialize;
+
+use super::components::{ParticleElement, BUILTIN_ELEMENTS};
+
+/* The element registry. Everything about how an element looks and behaves lives in
+   assets/default.elements.ron, so adding an element is adding an entry there.
+   A copy of the file is compiled in (ElementRegistry::builtin) so the ParticleMap works
+   before the asset server has loaded anything, or without one at all.
+*/
+pub const ELEMENTS_ASSET: &str = "default.elements.ron";
+const BUILTIN_ELEMENTS_RON: &str = include_str!("../../../assets/default.elements.ron");
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ElementClass {
+    Solid,
+    Powder,
+    Liquid,
+    Gas,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum ElementColor {
+    Range { min: [u8; 3], max: [u8; 3] },
+    Gray { min: u8, max: u8 },
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct Sparkle {
+    pub chance: f64,
+    pub amount: u8,
+}
+
+// turns into `into` once its temperature goes past `at`
+#[derive(Deserialize, Clone, Debug)]
+pub struct PhaseChange {
+    pub at: f32,
+    pub into: String,
+}
+
+// blows up (see ParticleMap::explode) once its temperature goes past `at`
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct Explosion {
+    pub at: f32,
+    pub radius: i32,
+    pub power: f32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ElementDefinition {
+    pub name: String,
+    pub color: ElementColor,
+    #[serde(default = "opaque")]
+    pub alpha: u8,
+    #[serde(default)]
+    pub sparkle: Option<Sparkle>,
+    #[serde(default)]
+    pub z_layer: f32,
+    pub class: ElementClass,
+    #[serde(default)]
+    pub density: f32,
+    #[serde(default = "no_viscosity")]
+    pub viscosity: f32,
+    #[serde(default = "one_cell")]
+    pub dispersion: i32,
+    #[serde(default)]
+    pub decay: f32,
+    #[serde(default = "room_temperature")]
+    pub temperature: f32,
+    #[serde(default)]
+    pub conductivity: f32,
+    #[serde(default)]
+    pub air_exchange: f32,
+    #[serde(default)]
+    pub melts: Option<PhaseChange>,
+    #[serde(default)]
+    pub boils: Option<PhaseChange>,
+    #[serde(default)]
+    pub freezes: Option<PhaseChange>,
+    #[serde(default)]
+    pub crumbles: Option<String>,
+    #[serde(default)]
+    pub hardness: f32,
+    #[serde(default)]
+    pub explodes: Option<Explosion>,
+    #[serde(default)]
+    pub flammability: f32,
+    #[serde(default)]
+    pub conducts_charge: bool,
+    #[serde(default)]
+    pub wind: f32,
+    #[serde(default)]
+    pub erodes: f32,
+    #[serde(default)]
+    pub erodibility: f32,
+    #[serde(default)]
+    pub blocks_player: bool,
+}
+
+fn opaque() -> u8 {
+    255
+}
+
+fn no_viscosity() -> f32 {
+    1.
+}
+
+fn one_cell() -> i32 {
+    1
+}
+
+fn room_temperature() -> f32 {
+    20.
+}
+
+impl ElementDefinition {
+    // every placed particle gets its own colour from the range, so the world doesn't look flat
+    pub fn random_color(&self, rng: &mut impl Rng) -> [u8; 4] {
+        let mut rgb = match self.color {
+            ElementColor::Range { min, max } => [
+                rng.gen_range(min[0]..=max[0]),
+                rng.gen_range(min[1]..=max[1]),
+                rng.gen_range(min[2]..=max[2]),
+            ],
+            ElementColor::Gray { min, max } => [rng.gen_range(min..=max); 3],
+        };
+        if let Some(sparkle) = self.sparkle {
+            if rng.gen_bool(sparkle.chance) {
+                rgb = rgb.map(|c| c.saturating_add(sparkle.amount));
+            }
+        }
+        [rgb[0], rgb[1], rgb[2], self.alpha]
+    }
+}
+
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct ElementRegistry {
+    elements: Vec<ElementDefinition>,
+    by_name: HashMap<String, ParticleElement>,
+    phase_changes: Vec<PhaseChanges>,
+    crumbles: Vec<Option<ParticleElement>>,
+}
+
+// melts/boils/freezes with the element names looked up
+#[derive(Clone, Copy, Default, Debug)]
+struct PhaseChanges {
+    melts: Option<(f32, ParticleElement)>,
+    boils: Option<(f32, ParticleElement)>,
+    freezes: Option<(f32, ParticleElement)>,
+}
+
+impl ElementRegistry {
+    /* The built in elements always get the index of their ParticleElement constant,
+       the rest are numbered after them in the order they are listed.
+    */
+    pub fn from_ron(text: &str) -> Result<Self, ElementRegistryError> {
+        let definitions: Vec<ElementDefinition> = ron::from_str(text)?;
+
+        let mut slots: Vec<Option<ElementDefinition>> = vec![None; BUILTIN_ELEMENTS.len()];
+        let mut by_name = HashMap::new();
+        for definition in definitions {
+            if by_name.contains_key(&definition.name) {
+                return Err(ElementRegistryError::DuplicateElement(definition.name));
+            }
+            let index = match BUILTIN_ELEMENTS.iter().position(|name| *name == definition.name) {
+                Some(index) => index,
+                None => {
+                    slots.push(None);
+                    slots.len() - 1
+                }
+            };
+            by_name.insert(definition.name.clone(), ParticleElement(index as u16));
+            slots[index] = Some(definition);
+        }
+
+        let mut elements = Vec::with_capacity(slots.len());
+        for (index, slot) in slots.into_iter().enumerate() {
+            match slot {
+                Some(definition) => elements.push(definition),
+                None => return Err(ElementRegistryError::MissingElement(BUILTIN_ELEMENTS[index])),
+            }
+        }
+
+        let resolve = |change: &Option<PhaseChange>| -> Result<Option<(f32, ParticleElement)>, ElementRegistryError> {
+            match change {
+                Some(change) => match by_name.get(&change.into) {
+                    Some(into) => Ok(Some((change.at, *into))),
+                    None => Err(ElementRegistryError::UnknownElement(change.into.clone())),
+                },
+                None => Ok(None),
+            }
+        };
+        let mut phase_changes = Vec::with_capacity(elements.len());
+        let mut crumbles = Vec::with_capacity(elements.len());
+        for definition in elements.iter() {
+            phase_changes.push(PhaseChanges {
+                melts: resolve(&definition.melts)?,
+                boils: resolve(&definition.boils)?,
+                freezes: resolve(&definition.freezes)?,
+            });
+            crumbles.push(match &definition.crumbles {
+                Some(name) => Some(*by_name.get(name).ok_or_else(|| ElementRegistryError::UnknownElement(name.clone()))?),
+                None => None,
+            });
+        }
+        Ok(Self { elements, by_name, phase_changes, crumbles })
+    }
+
+    pub fn builtin() -> Self {
+        Self::from_ron(BUILTIN_ELEMENTS_RON).expect("assets/default.elements.ron is not a valid element list")
+    }
+
+    // unknown elements (i.e. from a registry that was swapped out) are treated as air
+    pub fn get(&self, element: ParticleElement) -> &ElementDefinition {
+        self.elements.get(element.0 as usize).unwrap_or(&self.elements[0])
+    }
+
+    // what a particle turns into at this temperature, if it's past one of its thresholds. boiling is checked before melting
+    pub fn phase_change(&self, element: ParticleElement, temperature: f32) -> Option<ParticleElement> {
+        let changes = self.phase_changes.get(element.0 as usize)?;
+        [changes.boils, changes.melts].into_iter().flatten()
+            .find(|(at, _)| temperature > *at)
+            .or(changes.freezes.filter(|(at, _)| temperature < *at))
+            .map(|(_, into)| into)
+    }
+
+    // what a falling piece of this element breaks into when it lands hard, see entities/debris
+    pub fn crumbles_into(&self, element: ParticleElement) -> Option<ParticleElement> {
+        self.crumbles.get(element.0 as usize).copied().flatten()
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<ParticleElement> {
+        self.by_name.get(name).copied()
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = (ParticleElement, &ElementDefinition)> {
+        self.elements.iter().enumerate().map(|(i, definition)| (ParticleElement(i as u16), definition))
+    }
+
+    // every distinct z_layer, lowest first. the renderer draws one texture per layer
+    pub fn layers(&self) -> Vec<f32> {
+        let mut layers: Vec<f32> = Vec::new();
+        for definition in self.elements.iter() {
+            if !layers.contains(&definition.z_layer) {
+                layers.push(definition.z_layer);
+            }
+        }
+        layers.sort_by(|a, b| a.total_cmp(b));
+        layers
+    }
+}
+
+
+#[derive(Debug)]
+pub enum ElementRegistryError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    MissingElement(&'static str),
+    DuplicateElement(String),
+    UnknownElement(String),
+}
+
+impl fmt::Display for ElementRegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElementRegistryError::Io(e) => write!(f, "could not read element list: {e}"),
+            ElementRegistryError::Ron(e) => write!(f, "could not parse element list: {e}"),
+            ElementRegistryError::MissingElement(name) => write!(f, "element list is missing {name}"),
+            ElementRegistryError::DuplicateElement(name) => write!(f, "element {name} is listed twice"),
+            ElementRegistryError::UnknownElement(name) => write!(f, "there is no element called {name}"),
+        }
+    }
+}
+
+impl std::error::Error for ElementRegistryError {}
+
+impl From<std::io::Error> for ElementRegistryError {
+    fn from(e: std::io::Error) -> Self {
+        ElementRegistryError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for ElementRegistryError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        ElementRegistryError::Ron(e)
+    }
+}
+
+
+#[derive(Default)]
+pub struct ElementRegistryLoader;
+
+impl AssetLoader for ElementRegistryLoader {
+    type Asset = ElementRegistry;
+    type Settings = ();
+    type Error = ElementRegistryError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<ElementRegistry, ElementRegistryError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).await?;
+        ElementRegistry::from_ron(&text)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["elements.ron"]
+    }
+}
@@ -11,5 +11,7 @@
 //  >main.rs
 
 pub mod components;
+pub mod elements;
+pub mod reactions;
 pub mod resources;
 pub mod systems;
\ No newline at end of file
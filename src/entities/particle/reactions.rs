@@ -0,0 +1,105 @@
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use serde::Deserialize;
+
+/* The reaction table. What happens when two elements touch (water + lava = obsidian and friends)
+   is listed in assets/default.reactions.ron, elements are referred to by their name in the element list.
+   Like the element list a copy is compiled in, so reactions work without the asset server.
+*/
+pub const REACTIONS_ASSET: &str = "default.reactions.ron";
+const BUILTIN_REACTIONS_RON: &str = include_str!("../../../assets/default.reactions.ron");
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ReactionDefinition {
+    pub reactants: (String, String),
+    pub products: (String, String),
+    #[serde(default = "always")]
+    pub chance: f64,
+    #[serde(default)]
+    pub heat: f32,
+}
+
+fn always() -> f64 {
+    1.
+}
+
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct ReactionList {
+    pub reactions: Vec<ReactionDefinition>,
+}
+
+impl ReactionList {
+    pub fn from_ron(text: &str) -> Result<Self, ReactionListError> {
+        Ok(Self { reactions: ron::from_str(text)? })
+    }
+
+    pub fn builtin() -> Self {
+        Self::from_ron(BUILTIN_REACTIONS_RON).expect("assets/default.reactions.ron is not a valid reaction list")
+    }
+}
+
+// Sent for every reaction that gives off (or takes in, if negative) heat, at the first reactant's cell
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ReactionHeat {
+    pub position: (i32, i32),
+    pub heat: f32,
+}
+
+
+#[derive(Debug)]
+pub enum ReactionListError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for ReactionListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReactionListError::Io(e) => write!(f, "could not read reaction list: {e}"),
+            ReactionListError::Ron(e) => write!(f, "could not parse reaction list: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ReactionListError {}
+
+impl From<std::io::Error> for ReactionListError {
+    fn from(e: std::io::Error) -> Self {
+        ReactionListError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for ReactionListError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        ReactionListError::Ron(e)
+    }
+}
+
+
+#[derive(Default)]
+pub struct ReactionListLoader;
+
+impl AssetLoader for ReactionListLoader {
+    type Asset = ReactionList;
+    type Settings = ();
+    type Error = ReactionListError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<ReactionList, ReactionListError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).await?;
+        ReactionList::from_ron(&text)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["reactions.ron"]
+    }
+}
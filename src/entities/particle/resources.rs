@@ -3,14 +3,11 @@ use bevy::utils::{
     HashMap,
     HashSet,
 };
-use crate::{entities::particle::components::*, LEVEL_W, LEVEL_H};
+use std::sync::Arc;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use crate::{entities::particle::{components::*, elements::{ElementClass, ElementRegistry}, reactions::ReactionList}, LEVEL_W, LEVEL_H};
 
 pub const PARTICLE_SIZE: f32 = 4.;
-pub const WATER_VISCOSITY: f32 = 0.75; //range 0-1, 1 is low viscosity, 0 doesnt move (higher is more viscous)
-pub const LAVA_VISCOSITY: f32 = 0.35;
-pub const HEALING_SPRING_VISCOSITY: f32 = 0.55;
-pub const QUICKSAND_VISCOSITY: f32 = 0.20;
-pub const SLIME_VISCOSITY: f32 = 0.40;
 pub enum ListType {
     All,
     OnlyAir,
@@ -18,130 +15,810 @@ pub enum ListType {
     Blacklist(Vec<ParticleElement>),  // Replace any particle except those specified
 }
 
+impl ListType {
+    // can a particle be put over `element`?
+    fn allows(&self, element: ParticleElement) -> bool {
+        match self {
+            ListType::All => true,
+            ListType::OnlyAir => element == ParticleElement::Air,
+            ListType::Whitelist(whitelist) => whitelist.contains(&element),
+            ListType::Blacklist(blacklist) => !blacklist.contains(&element),
+        }
+    }
+}
+
 pub const MIN_X: i32 = ((-LEVEL_W / 2.) / PARTICLE_SIZE) as i32;
 pub const MAX_X: i32 = ((LEVEL_W / 2.) / PARTICLE_SIZE) as i32;
 pub const MIN_Y: i32 = ((-LEVEL_H / 2.) / PARTICLE_SIZE) as i32;
 pub const MAX_Y: i32 = ((LEVEL_H / 2.) / PARTICLE_SIZE) as i32;
 
+// The map is split into CHUNK_SIZE x CHUNK_SIZE chunks, each one a flat array of cells.
+// Chunks inside the level bounds (plus a one chunk margin) live in a dense Vec so a lookup is just
+// some integer math and an index. Anything further out (planet 4 scrolls forever) goes in outer_chunks.
+pub const CHUNK_SIZE: i32 = 64;
+const CHUNK_AREA: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+const DENSE_MIN_CHUNK_X: i32 = MIN_X.div_euclid(CHUNK_SIZE) - 1;
+const DENSE_MAX_CHUNK_X: i32 = MAX_X.div_euclid(CHUNK_SIZE) + 1;
+const DENSE_MIN_CHUNK_Y: i32 = MIN_Y.div_euclid(CHUNK_SIZE) - 1;
+const DENSE_MAX_CHUNK_Y: i32 = MAX_Y.div_euclid(CHUNK_SIZE) + 1;
+const DENSE_CHUNKS_W: i32 = DENSE_MAX_CHUNK_X - DENSE_MIN_CHUNK_X + 1;
+const DENSE_CHUNKS_H: i32 = DENSE_MAX_CHUNK_Y - DENSE_MIN_CHUNK_Y + 1;
+
+struct ParticleChunk {
+    cells: Box<[Option<Particle>]>,
+    // set whenever a cell changes so render_particles knows to redraw this chunk's textures
+    dirty: bool,
+    // set whenever a cell or its temperature changes so update_temperature looks at this chunk next frame
+    heat_active: bool,
+    // the cells the automata look at this step, and the ones they will look at next step (see begin_step)
+    awake: Option<ActiveRect>,
+    waking: Option<ActiveRect>,
+}
+
+impl ParticleChunk {
+    fn new() -> Self {
+        Self {
+            cells: vec![None; CHUNK_AREA].into_boxed_slice(),
+            dirty: false,
+            heat_active: false,
+            awake: None,
+            waking: None,
+        }
+    }
+}
+
+// A rectangle of cells inside one chunk, min and max are both included
+#[derive(Clone, Copy, Debug)]
+pub struct ActiveRect {
+    pub min: (i32, i32),
+    pub max: (i32, i32),
+}
+
+impl ActiveRect {
+    fn include(&mut self, min: (i32, i32), max: (i32, i32)) {
+        self.min = (self.min.0.min(min.0), self.min.1.min(min.1));
+        self.max = (self.max.0.max(max.0), self.max.1.max(max.1));
+    }
+}
+
+// One blast that went off, see ParticleMap::explode. The grid position of its middle, radius in cells
+#[derive(Clone, Copy, Debug)]
+pub struct Blast {
+    pub center: (i32, i32),
+    pub radius: i32,
+    pub power: f32,
+}
+
+const BLAST_THROW: f32 = 2.;      // cells per step a blast throws a cell for every point of power it has left over
+const MAX_BLAST_THROW: f32 = 6.;
+
+// returns the chunk a grid position is in, and the index of that position inside the chunk
+pub fn chunk_of(pos: (i32, i32)) -> ((i32, i32), usize) {
+    let chunk = (pos.0.div_euclid(CHUNK_SIZE), pos.1.div_euclid(CHUNK_SIZE));
+    let local = (pos.0.rem_euclid(CHUNK_SIZE), pos.1.rem_euclid(CHUNK_SIZE));
+    (chunk, (local.1 * CHUNK_SIZE + local.0) as usize)
+}
+
+fn dense_index(chunk: (i32, i32)) -> Option<usize> {
+    if chunk.0 < DENSE_MIN_CHUNK_X || chunk.0 > DENSE_MAX_CHUNK_X ||
+       chunk.1 < DENSE_MIN_CHUNK_Y || chunk.1 > DENSE_MAX_CHUNK_Y {
+        return None
+    }
+    Some(((chunk.1 - DENSE_MIN_CHUNK_Y) * DENSE_CHUNKS_W + (chunk.0 - DENSE_MIN_CHUNK_X)) as usize)
+}
+
+fn dense_chunk_pos(index: usize) -> (i32, i32) {
+    let index = index as i32;
+    (index % DENSE_CHUNKS_W + DENSE_MIN_CHUNK_X, index / DENSE_CHUNKS_W + DENSE_MIN_CHUNK_Y)
+}
+
+/* The particle world. Each cell is plain data (see Particle), nothing here touches the ECS,
+   so the simulation can run without a window or a renderer. render_particles picks up the
+   chunks that changed through take_dirty_chunks/take_removed_chunks and redraws their textures.
+*/
 #[derive(Resource)]
 pub struct ParticleMap {
-    pub particle_map: HashMap<(i32, i32), (Entity, ParticleElement)>,
+    chunks: Vec<Option<ParticleChunk>>,
+    outer_chunks: HashMap<(i32, i32), ParticleChunk>,
+    dirty_chunks: Vec<(i32, i32)>,
+    heat_active_chunks: Vec<(i32, i32)>,
+    awake_chunks: Vec<(i32, i32)>,
+    waking_chunks: Vec<(i32, i32)>,
+    removed_chunks: Vec<(i32, i32)>,
+    // where a Solid was removed or turned into something else, the terrain around it might be loose now
+    support_checks: Vec<(i32, i32)>,
+    // every blast since the last take_blasts, so they can hurt the player and enemies
+    blasts: Vec<Blast>,
+    cleared: bool,
+    registry: Arc<ElementRegistry>,
+    // particle colours are rolled from this, so the same SimulationSeed gives the same looking map
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Default for ParticleMap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ParticleMap {
     pub fn new() -> Self {
         Self {
-            particle_map: HashMap::new(),
+            chunks: (0..DENSE_CHUNKS_W * DENSE_CHUNKS_H).map(|_| None).collect(),
+            outer_chunks: HashMap::new(),
+            dirty_chunks: Vec::new(),
+            heat_active_chunks: Vec::new(),
+            awake_chunks: Vec::new(),
+            waking_chunks: Vec::new(),
+            removed_chunks: Vec::new(),
+            support_checks: Vec::new(),
+            blasts: Vec::new(),
+            cleared: false,
+            registry: Arc::new(ElementRegistry::builtin()),
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
         }
     }
     pub fn reset(&mut self) {
-        self.particle_map.clear();
+        self.chunks.iter_mut().for_each(|chunk| *chunk = None);
+        self.outer_chunks.clear();
+        self.dirty_chunks.clear();
+        self.heat_active_chunks.clear();
+        self.awake_chunks.clear();
+        self.waking_chunks.clear();
+        self.removed_chunks.clear();
+        self.support_checks.clear();
+        self.blasts.clear();
+        self.cleared = true;
+        self.rng = StdRng::seed_from_u64(self.seed);
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn registry(&self) -> &ElementRegistry {
+        &self.registry
+    }
+
+    // for systems that need to look things up in the registry while they change the map
+    pub fn shared_registry(&self) -> Arc<ElementRegistry> {
+        self.registry.clone()
+    }
+
+    // swaps in a new element list (i.e. when assets/default.elements.ron is loaded) and redraws everything
+    pub fn set_registry(&mut self, registry: ElementRegistry) {
+        self.registry = Arc::new(registry);
+        self.mark_all_dirty();
+        // densities and classes might have changed, everything has to be looked at again
+        self.wake_all();
+    }
+
+    fn chunk(&self, chunk: (i32, i32)) -> Option<&ParticleChunk> {
+        match dense_index(chunk) {
+            Some(i) => self.chunks[i].as_ref(),
+            None => self.outer_chunks.get(&chunk),
+        }
+    }
+
+    fn chunk_mut(&mut self, chunk: (i32, i32)) -> &mut ParticleChunk {
+        match dense_index(chunk) {
+            Some(i) => self.chunks[i].get_or_insert_with(ParticleChunk::new),
+            None => self.outer_chunks.entry(chunk).or_insert_with(ParticleChunk::new),
+        }
+    }
+
+    fn chunk_if_loaded_mut(&mut self, chunk: (i32, i32)) -> Option<&mut ParticleChunk> {
+        match dense_index(chunk) {
+            Some(i) => self.chunks[i].as_mut(),
+            None => self.outer_chunks.get_mut(&chunk),
+        }
+    }
+
+    fn take_chunk(&mut self, chunk: (i32, i32)) -> Option<ParticleChunk> {
+        match dense_index(chunk) {
+            Some(i) => self.chunks[i].take(),
+            None => self.outer_chunks.remove(&chunk),
+        }
+    }
+
+    fn get_cell(&self, pos: (i32, i32)) -> Option<Particle> {
+        let (chunk, index) = chunk_of(pos);
+        self.chunk(chunk).and_then(|c| c.cells[index])
+    }
+
+    fn set_cell(&mut self, pos: (i32, i32), cell: Option<Particle>) -> Option<Particle> {
+        let (chunk_pos, index) = chunk_of(pos);
+        if cell.is_none() && self.chunk(chunk_pos).is_none() {
+            return None
+        }
+        let chunk = self.chunk_mut(chunk_pos);
+        if !chunk.dirty {
+            chunk.dirty = true;
+            self.dirty_chunks.push(chunk_pos);
+        }
+        self.wake_heat(chunk_pos);
+        let before = std::mem::replace(&mut self.chunk_mut(chunk_pos).cells[index], cell);
+        self.wake_around(pos);
+        if self.is_solid(before) && !self.is_solid(cell) {
+            self.support_checks.push(pos);
+        }
+        before
+    }
+
+    fn is_solid(&self, cell: Option<Particle>) -> bool {
+        cell.is_some_and(|particle| self.registry.get(particle.element).class == ElementClass::Solid)
+    }
+
+    fn wake_heat(&mut self, chunk_pos: (i32, i32)) {
+        let chunk = self.chunk_mut(chunk_pos);
+        if !chunk.heat_active {
+            chunk.heat_active = true;
+            self.heat_active_chunks.push(chunk_pos);
+        }
+    }
+
+    /* Sleeping cells. The automata only look at cells that are awake, so a lake that has settled costs nothing.
+       A cell wakes up (for the next step) when it or one of its 8 neighbours changes. Cells that could still do
+       something but didn't this step (i.e. water that failed its viscosity roll) have to call keep_awake,
+       everything else falls asleep on its own. Awake cells are tracked as one rectangle per chunk.
+    */
+    fn wake_around(&mut self, pos: (i32, i32)) {
+        let (min_chunk, _) = chunk_of((pos.0 - 1, pos.1 - 1));
+        let (max_chunk, _) = chunk_of((pos.0 + 1, pos.1 + 1));
+        for chunk_y in min_chunk.1..=max_chunk.1 {
+            for chunk_x in min_chunk.0..=max_chunk.0 {
+                let origin = (chunk_x * CHUNK_SIZE, chunk_y * CHUNK_SIZE);
+                let min = ((pos.0 - 1 - origin.0).max(0), (pos.1 - 1 - origin.1).max(0));
+                let max = ((pos.0 + 1 - origin.0).min(CHUNK_SIZE - 1), (pos.1 + 1 - origin.1).min(CHUNK_SIZE - 1));
+                self.wake_rect((chunk_x, chunk_y), min, max);
+            }
+        }
+    }
+
+    fn wake_rect(&mut self, chunk_pos: (i32, i32), min: (i32, i32), max: (i32, i32)) {
+        // a chunk that doesn't exist is all air, there is nothing to wake up
+        if self.chunk(chunk_pos).is_none() {
+            return
+        }
+        let chunk = self.chunk_mut(chunk_pos);
+        match &mut chunk.waking {
+            Some(rect) => rect.include(min, max),
+            None => {
+                chunk.waking = Some(ActiveRect { min, max });
+                self.waking_chunks.push(chunk_pos);
+            }
+        }
+    }
+
+    // keeps a cell awake for the next step even though it didn't change
+    pub fn keep_awake(&mut self, pos: (i32, i32)) {
+        let (chunk, index) = chunk_of(pos);
+        let local = (index as i32 % CHUNK_SIZE, index as i32 / CHUNK_SIZE);
+        self.wake_rect(chunk, local, local);
+    }
+
+    // called once at the start of every simulation step: what woke up during the last one is what gets simulated now
+    pub fn begin_step(&mut self) {
+        for chunk_pos in std::mem::take(&mut self.awake_chunks) {
+            if let Some(chunk) = self.chunk_if_loaded_mut(chunk_pos) {
+                chunk.awake = None;
+            }
+        }
+        let mut awake = Vec::new();
+        for chunk_pos in std::mem::take(&mut self.waking_chunks) {
+            // a chunk can be listed twice if it was despawned and made again
+            let Some(chunk) = self.chunk_if_loaded_mut(chunk_pos) else {
+                continue
+            };
+            if let Some(rect) = chunk.waking.take() {
+                chunk.awake = Some(rect);
+                awake.push(chunk_pos);
+            }
+        }
+        // bottom row of chunks first, same order as positions_where
+        awake.sort_by_key(|&(x, y)| (y, x));
+        self.awake_chunks = awake;
+    }
+
+    // wakes every cell, i.e. when the element list changed
+    pub fn wake_all(&mut self) {
+        for chunk in self.loaded_chunks() {
+            self.wake_rect(chunk, (0, 0), (CHUNK_SIZE - 1, CHUNK_SIZE - 1));
+        }
+    }
+
+    // the awake rectangles of this step in grid positions, for the debug overlay
+    pub fn awake_regions(&self) -> Vec<ActiveRect> {
+        self.awake_chunks.iter()
+            .filter_map(|&chunk_pos| {
+                let rect = self.chunk(chunk_pos)?.awake?;
+                let origin = (chunk_pos.0 * CHUNK_SIZE, chunk_pos.1 * CHUNK_SIZE);
+                Some(ActiveRect {
+                    min: (origin.0 + rect.min.0, origin.1 + rect.min.1),
+                    max: (origin.0 + rect.max.0, origin.1 + rect.max.1),
+                })
+            })
+            .collect()
+    }
+
+    // same as positions_where, but only the cells that are awake this step
+    pub fn awake_positions_where(&self, filter: impl Fn(&Particle) -> bool) -> Vec<(i32, i32)> {
+        let mut positions = Vec::new();
+        for &chunk_pos in self.awake_chunks.iter() {
+            let Some(chunk) = self.chunk(chunk_pos) else {
+                continue
+            };
+            let Some(rect) = chunk.awake else {
+                continue
+            };
+            for y in rect.min.1..=rect.max.1 {
+                for x in rect.min.0..=rect.max.0 {
+                    if let Some(particle) = &chunk.cells[(y * CHUNK_SIZE + x) as usize] {
+                        if filter(particle) {
+                            positions.push((chunk_pos.0 * CHUNK_SIZE + x, chunk_pos.1 * CHUNK_SIZE + y));
+                        }
+                    }
+                }
+            }
+        }
+        positions
+    }
+
+    // the chunks with awake cells this step, bottom row first
+    pub fn awake_chunks(&self) -> &[(i32, i32)] {
+        &self.awake_chunks
+    }
+
+    // copies out the awake part of a chunk plus WINDOW_MARGIN cells around it. None if nothing in it is awake
+    pub fn window(&self, chunk_pos: (i32, i32), rng: StdRng) -> Option<ChunkWindow> {
+        let rect = self.chunk(chunk_pos)?.awake?;
+        let origin = (chunk_pos.0 * CHUNK_SIZE, chunk_pos.1 * CHUNK_SIZE);
+        let active = ActiveRect {
+            min: (origin.0 + rect.min.0, origin.1 + rect.min.1),
+            max: (origin.0 + rect.max.0, origin.1 + rect.max.1),
+        };
+        let min = (active.min.0 - WINDOW_MARGIN, active.min.1 - WINDOW_MARGIN);
+        let size = (active.max.0 - active.min.0 + 1 + 2 * WINDOW_MARGIN, active.max.1 - active.min.1 + 1 + 2 * WINDOW_MARGIN);
+        let mut cells = Vec::with_capacity((size.0 * size.1) as usize);
+        for y in min.1..min.1 + size.1 {
+            // a row at a time, one slice per chunk it crosses
+            let mut x = min.0;
+            while x < min.0 + size.0 {
+                let (chunk, index) = chunk_of((x, y));
+                let run = (CHUNK_SIZE - x.rem_euclid(CHUNK_SIZE)).min(min.0 + size.0 - x) as usize;
+                match self.chunk(chunk) {
+                    Some(chunk) => cells.extend_from_slice(&chunk.cells[index..index + run]),
+                    None => cells.extend(std::iter::repeat_n(None, run)),
+                }
+                x += run as i32;
+            }
+        }
+        Some(ChunkWindow {
+            active,
+            min,
+            size,
+            changed: vec![false; cells.len()],
+            cells,
+            awake: Vec::new(),
+            registry: self.registry.clone(),
+            rng,
+        })
+    }
+
+    // puts what the automata did to a window back into the map
+    pub fn apply_window(&mut self, window: ChunkWindow) {
+        for (index, cell) in window.cells.iter().enumerate() {
+            if !window.changed[index] {
+                continue;
+            }
+            let pos = window.position(index);
+            let looks_the_same = match (self.get_cell(pos), cell) {
+                (Some(before), Some(after)) => before.element == after.element && before.color == after.color,
+                (None, None) => true,
+                _ => false,
+            };
+            if !looks_the_same {
+                self.set_cell(pos, *cell);
+                continue;
+            }
+            // only its velocity or temperature changed, same as give_velocity and set_temperature_at
+            let (chunk_pos, chunk_index) = chunk_of(pos);
+            if let Some(chunk) = self.chunk_if_loaded_mut(chunk_pos) {
+                chunk.cells[chunk_index] = *cell;
+                self.wake_heat(chunk_pos);
+            }
+        }
+        for pos in window.awake {
+            self.keep_awake(pos);
+        }
     }
 
     pub fn get_element_at(&self, pos: (i32, i32)) -> ParticleElement {
-        self.particle_map.get(&pos)
-            .map(|(_, particle_element)| *particle_element)
+        self.get_cell(pos)
+            .map(|particle| particle.element)
             .unwrap_or(ParticleElement::Air)
     }
 
+    pub fn get_particle_at(&self, pos: (i32, i32)) -> Option<Particle> {
+        self.get_cell(pos)
+    }
+
+    pub fn get_velocity_at(&self, pos: (i32, i32)) -> Vec2 {
+        self.get_cell(pos)
+            .map(|particle| particle.velocity)
+            .unwrap_or(Vec2::ZERO)
+    }
+
+    pub fn get_temperature_at(&self, pos: (i32, i32)) -> Option<f32> {
+        self.get_cell(pos).map(|particle| particle.temperature)
+    }
+
+    pub fn get_sediment_at(&self, pos: (i32, i32)) -> ParticleElement {
+        self.get_cell(pos).map_or(ParticleElement::Air, |particle| particle.sediment)
+    }
+
+    // the particle picks up `sediment` and its colour is mixed with `tint` so you can see it's carrying something
+    pub fn carry_sediment(&mut self, pos: (i32, i32), sediment: ParticleElement, tint: [u8; 4]) {
+        if let Some(particle) = self.get_cell(pos) {
+            self.set_cell(pos, Some(Particle { sediment, color: muddy(particle.color, tint), ..particle }));
+        }
+    }
+
+    // like velocity this doesn't change how the particle looks, but update_temperature has to look at the chunk again
+    pub fn set_temperature_at(&mut self, pos: (i32, i32), temperature: f32) {
+        let (chunk, index) = chunk_of(pos);
+        if self.chunk(chunk).is_none() {
+            return
+        }
+        if let Some(particle) = &mut self.chunk_mut(chunk).cells[index] {
+            particle.temperature = temperature;
+            self.wake_heat(chunk);
+        }
+    }
+
+    // turns a particle into another element (i.e. ice melting) but keeps its temperature and velocity
+    pub fn change_element_at(&mut self, pos: (i32, i32), element: ParticleElement) {
+        let Some(particle) = self.get_cell(pos) else {
+            return
+        };
+        if element == ParticleElement::Air {
+            self.delete_at(pos);
+            return
+        }
+        let color = self.registry.get(element).random_color(&mut self.rng);
+        self.set_cell(pos, Some(Particle { element, color, sediment: ParticleElement::Air, ..particle }));
+    }
+
+    // puts a particle back exactly as it was (colour, velocity, temperature), i.e. when loading a save
+    pub fn put_particle_at(&mut self, pos: (i32, i32), particle: Particle) {
+        if particle.element == ParticleElement::Air {
+            self.delete_at(pos);
+            return
+        }
+        self.set_cell(pos, Some(particle));
+    }
+
+    /* Blows up everything within `radius` cells of `center`. The blast gets weaker towards its edge and only breaks
+       a cell where it's stronger than the element's hardness (BedRock never breaks). Near the middle cells are blown
+       away, further out they are thrown outwards instead, solids as whatever they crumble into.
+       Anything explosive it reaches goes off as well.
+    */
+    pub fn explode(&mut self, center: (i32, i32), radius: i32, power: f32) {
+        let registry = self.registry.clone();
+        let mut blasts = vec![Blast { center, radius, power }];
+        while let Some(blast) = blasts.pop() {
+            let (cx, cy) = blast.center;
+            for y in cy - blast.radius..=cy + blast.radius {
+                for x in cx - blast.radius..=cx + blast.radius {
+                    let offset = Vec2::new((x - cx) as f32, (y - cy) as f32);
+                    let distance = offset.length();
+                    let Some(particle) = self.get_cell((x, y)) else {
+                        continue
+                    };
+                    if distance > blast.radius as f32 || particle.element == ParticleElement::BedRock {
+                        continue;
+                    }
+                    let definition = registry.get(particle.element);
+                    if let Some(explosion) = definition.explodes {
+                        self.delete_at((x, y));
+                        blasts.push(Blast { center: (x, y), radius: explosion.radius, power: explosion.power });
+                        continue;
+                    }
+                    let strength = blast.power * (1. - distance / (blast.radius + 1) as f32);
+                    if strength <= definition.hardness {
+                        continue;
+                    }
+                    if distance <= blast.radius as f32 / 2. {
+                        self.delete_at((x, y));
+                        continue;
+                    }
+                    if definition.class == ElementClass::Solid {
+                        match registry.crumbles_into(particle.element) {
+                            Some(into) => self.change_element_at((x, y), into),
+                            None => {
+                                self.delete_at((x, y));
+                                continue;
+                            }
+                        }
+                    }
+                    let throw = ((strength - definition.hardness) * BLAST_THROW).min(MAX_BLAST_THROW);
+                    self.give_velocity((x, y), offset.normalize_or_zero() * throw);
+                }
+            }
+            self.blasts.push(blast);
+        }
+    }
+
+    // sets off an explosive particle (see `explodes` in the element list), returns false for anything else
+    pub fn ignite(&mut self, pos: (i32, i32)) -> bool {
+        let Some(particle) = self.get_cell(pos) else {
+            return false
+        };
+        let Some(explosion) = self.registry.get(particle.element).explodes else {
+            return false
+        };
+        self.delete_at(pos);
+        self.explode(pos, explosion.radius, explosion.power);
+        true
+    }
 
-    /* useful for anyone implementing other functions here: you can get the actual Particle or whatever values based off of Entity. */
-    pub fn get_entity_at(&self, pos: (i32, i32)) -> Option<Entity> {
-        self.particle_map.get(&pos).map(|(entity, _)| *entity)
+    /* Moving particles around. The cells are moved as they are (colour, velocity, temperature and all),
+       so nothing is made or thrown away and the colours don't flicker as things flow.
+         swap(a, b):          a ends up where b was and b where a was, either of them can be air
+         move_cell(from, to): only moves into air, returns false (and does nothing) if `to` is taken
+    */
+    pub fn swap(&mut self, a: (i32, i32), b: (i32, i32)) {
+        let (cell_a, cell_b) = (self.get_cell(a), self.get_cell(b));
+        if cell_a.is_none() && cell_b.is_none() {
+            return
+        }
+        self.set_cell(a, cell_b);
+        self.set_cell(b, cell_a);
     }
-    /* for example was useful here. */
-    pub fn give_velocity(&mut self, commands: &mut Commands, pos: (i32, i32), vel: Vec2) {
-        if let Some(entity) = self.get_entity_at(pos) {
-            // .insert replaces the component/bundle/whateveritscalled with the new one
-            commands.entity(entity).insert(ParticlePosVel { grid_x: pos.0, grid_y: pos.1, velocity: vel });
+
+    pub fn move_cell(&mut self, from: (i32, i32), to: (i32, i32)) -> bool {
+        if from == to || self.get_cell(to).is_some() {
+            return false
+        }
+        let Some(particle) = self.get_cell(from) else {
+            return false
+        };
+        self.set_cell(to, Some(particle));
+        self.set_cell(from, None);
+        true
+    }
+
+    // velocity doesn't change how a particle looks, so this doesn't mark the chunk dirty
+    pub fn give_velocity(&mut self, pos: (i32, i32), vel: Vec2) {
+        let (chunk, index) = chunk_of(pos);
+        if self.chunk(chunk).is_none() {
+            return
+        }
+        if let Some(particle) = &mut self.chunk_mut(chunk).cells[index] {
+            particle.velocity = vel;
+            self.keep_awake(pos);
         }
     }
 
 
     /* Usage: 
         Replace regardless of whats there:
-            particle_map.insert_at::<WaterParticle>(&mut commands, (x, y), ListType::ReplaceAll);
+            particle_map.insert_at(ParticleElement::Water, (x, y), ListType::All);
         Replace only if air is there:
-            particle_map.insert_at::<WaterParticle>(&mut commands, (x, y), ListType::ReplaceOnlyAir);
+            particle_map.insert_at(ParticleElement::Water, (x, y), ListType::OnlyAir);
         Replace only if ParticleElement is in list (whitelist). i.e. will only replace air and stone:
-            particle_map.insert_at::<WaterParticle>(&mut commands, (x, y), ListType::WhiteList(vec![ParticleElement::Air, ParticleElement::Stone]))
+            particle_map.insert_at(ParticleElement::Water, (x, y), ListType::Whitelist(vec![ParticleElement::Air, ParticleElement::Stone]))
         Replace if particleElement is NOT in list (blacklist). i.e. will replace everything except for air and stone:
-            particle_map.insert_at::<WaterParticle>(&mut commands, (x, y), ListType::Blacklist(vec![ParticleElement::Air, ParticleElement::Stone]))
+            particle_map.insert_at(ParticleElement::Water, (x, y), ListType::Blacklist(vec![ParticleElement::Air, ParticleElement::Stone]))
+        The colour is picked from the element's range in the ElementRegistry.
     */
-    pub fn insert_at<P: NewParticle + Bundle>(&mut self, commands: &mut Commands, pos: (i32, i32), list: ListType) -> bool {
-        // do not spawn particles if they are outside of map. will need to change later with chunks
-        // if  (pos.0 < -(LEVEL_W/2.) as i32) || (pos.0 > (LEVEL_W/2.) as i32) ||
-        //     (pos.1 < -(LEVEL_H/2.) as i32) || (pos.1 > (LEVEL_H/2.) as i32){
-        //     return false
-        // }
-        let element_at_pos = self.get_element_at(pos);
-    
-        let should_replace = match list {
-            ListType::All => true,
-            ListType::OnlyAir => element_at_pos == ParticleElement::Air,
-            ListType::Whitelist(ref whitelist) => whitelist.contains(&element_at_pos),
-            ListType::Blacklist(ref blacklist) => !blacklist.contains(&element_at_pos),
-        };
-    
-        if should_replace{
-            // If we need to replace a non-air element, delete it
-            if element_at_pos != ParticleElement::Air {
-                self.delete_at(commands, pos);
-            }
-            let particle_instance = P::new(pos.0, pos.1, Vec2::splat(0.));
-            let entity = commands.spawn(particle_instance).id();
-            let element = P::ELEMENT;
-            self.particle_map.insert(pos, (entity, element));
-            return true;
+    pub fn insert_at(&mut self, element: ParticleElement, pos: (i32, i32), list: ListType) -> bool {
+        if !self.should_replace(pos, &list) {
+            return false;
         }
-        
-        false
+        let color = self.registry.get(element).random_color(&mut self.rng);
+        self.place(element, pos, color);
+        true
     }
 
+    // same as insert_at, but the colour comes from the given rng (i.e. a chunk's own stream in world generation)
+    pub fn insert_at_with_rng(&mut self, element: ParticleElement, pos: (i32, i32), list: ListType, rng: &mut impl Rng) -> bool {
+        if !self.should_replace(pos, &list) {
+            return false;
+        }
+        let color = self.registry.get(element).random_color(rng);
+        self.place(element, pos, color);
+        true
+    }
 
-    /* Usage: particle_map.delete(&mut commands, (x,y));
+    fn should_replace(&self, pos: (i32, i32), list: &ListType) -> bool {
+        list.allows(self.get_element_at(pos))
+    }
+
+    fn place(&mut self, element: ParticleElement, pos: (i32, i32), color: [u8; 4]) {
+        if element == ParticleElement::Air {
+            self.delete_at(pos);
+            return;
+        }
+        let temperature = self.registry.get(element).temperature;
+        self.set_cell(pos, Some(Particle {
+            element,
+            color,
+            velocity: Vec2::ZERO,
+            temperature,
+            sediment: ParticleElement::Air,
+        }));
+    }
+
+
+    /* Usage: particle_map.delete_at((x,y));
             Will delete the particle from a pos if there is something there.
             if its air it doesnt do anything since no particle exists there.
      */
-    pub fn delete_at(&mut self, commands: &mut Commands, pos: (i32, i32)){
-        if let Some(old_entity) = self.particle_map.get(&pos).map(|(entity, _)| *entity) {
-            commands.entity(old_entity).despawn();
-            self.particle_map.remove(&pos);
+    pub fn delete_at(&mut self, pos: (i32, i32)){
+        if self.get_cell(pos).is_some() {
+            self.set_cell(pos, None);
+        }
+    }
+
+    pub fn despawn_chunk(&mut self, chunk: (i32, i32)) {
+        if self.take_chunk(chunk).is_some() {
+            self.removed_chunks.push(chunk);
+        }
+    }
+
+    /* Positions of every particle of one element. The automata in systems.rs loop over this
+       instead of querying for particle entities. It's a snapshot, so check the cell is still
+       the same element before moving it.
+    */
+    pub fn positions_of(&self, element: ParticleElement) -> Vec<(i32, i32)> {
+        self.positions_where(|particle| particle.element == element)
+    }
+
+    // same as positions_of, for every particle the filter is true for
+    pub fn positions_where(&self, filter: impl Fn(&Particle) -> bool) -> Vec<(i32, i32)> {
+        let mut positions = Vec::new();
+        let dense = self.chunks.iter().enumerate()
+            .filter_map(|(i, chunk)| chunk.as_ref().map(|chunk| (dense_chunk_pos(i), chunk)));
+        let outer = self.outer_chunks.iter().map(|(pos, chunk)| (*pos, chunk));
+        for (chunk_pos, chunk) in dense.chain(outer) {
+            for (index, cell) in chunk.cells.iter().enumerate() {
+                if let Some(particle) = cell {
+                    if filter(particle) {
+                        let index = index as i32;
+                        positions.push((
+                            chunk_pos.0 * CHUNK_SIZE + index % CHUNK_SIZE,
+                            chunk_pos.1 * CHUNK_SIZE + index / CHUNK_SIZE,
+                        ));
+                    }
+                }
+            }
+        }
+        positions
+    }
+
+    // Used by render_particles. Hands out the chunks that changed since the last call.
+    pub fn take_dirty_chunks(&mut self) -> Vec<(i32, i32)> {
+        let dirty = std::mem::take(&mut self.dirty_chunks);
+        for chunk in dirty.iter() {
+            match dense_index(*chunk) {
+                Some(i) => if let Some(c) = &mut self.chunks[i] { c.dirty = false },
+                None => if let Some(c) = self.outer_chunks.get_mut(chunk) { c.dirty = false },
+            }
+        }
+        dirty
+    }
+
+    // Used by update_temperature, same as take_dirty_chunks
+    pub fn take_heat_active_chunks(&mut self) -> Vec<(i32, i32)> {
+        let active = std::mem::take(&mut self.heat_active_chunks);
+        for chunk in active.iter() {
+            match dense_index(*chunk) {
+                Some(i) => if let Some(c) = &mut self.chunks[i] { c.heat_active = false },
+                None => if let Some(c) = self.outer_chunks.get_mut(chunk) { c.heat_active = false },
+            }
         }
+        active
+    }
+
+    // every chunk that has been created and not despawned
+    pub fn loaded_chunks(&self) -> Vec<(i32, i32)> {
+        let dense = self.chunks.iter().enumerate()
+            .filter(|(_, chunk)| chunk.is_some())
+            .map(|(i, _)| dense_chunk_pos(i));
+        dense.chain(self.outer_chunks.keys().copied()).collect()
+    }
+
+    pub fn take_removed_chunks(&mut self) -> Vec<(i32, i32)> {
+        std::mem::take(&mut self.removed_chunks)
     }
 
-    pub fn despawn_chunk(&mut self, commands: &mut Commands, chunk: (i32, i32)) {
-        let x_start = chunk.0 * 64;
-        let y_start = chunk.1 * 64;
+    // Used by damage_from_blasts, the blasts that went off since the last call
+    pub fn take_blasts(&mut self) -> Vec<Blast> {
+        std::mem::take(&mut self.blasts)
+    }
 
-        let min = IVec2::new(x_start, y_start);
-        let max = IVec2::new(x_start + 64, y_start + 64);
+    // Used by detect_debris. Every position a Solid was taken out of since the last call
+    pub fn take_support_checks(&mut self) -> Vec<(i32, i32)> {
+        std::mem::take(&mut self.support_checks)
+    }
 
-        for x in min.x..max.x {
-            for y in min.y..max.y {
-                self.delete_at(commands, (x, y));
+    // makes render_particles redraw every chunk, i.e. when the layers in the registry changed
+    pub fn mark_all_dirty(&mut self) {
+        let mut all = Vec::new();
+        for (i, chunk) in self.chunks.iter_mut().enumerate() {
+            if let Some(chunk) = chunk {
+                if !chunk.dirty {
+                    chunk.dirty = true;
+                    all.push(dense_chunk_pos(i));
+                }
+            }
+        }
+        for (pos, chunk) in self.outer_chunks.iter_mut() {
+            if !chunk.dirty {
+                chunk.dirty = true;
+                all.push(*pos);
             }
         }
+        self.dirty_chunks.extend(all);
+    }
+
+    // true once after reset(), so the renderer can throw all of its textures away
+    pub fn take_cleared(&mut self) -> bool {
+        std::mem::replace(&mut self.cleared, false)
+    }
+
+    // every cell of a chunk, row by row from the bottom. None for chunks that were never written to
+    pub fn chunk_cells(&self, chunk: (i32, i32)) -> Option<&[Option<Particle>]> {
+        self.chunk(chunk).map(|c| &c.cells[..])
     }
     
+    // see ParticleGrid::ray
+    pub fn ray(&self, start: (i32, i32), end: (i32, i32), list: ListType) -> Option<(i32, i32)> {
+        ParticleGrid::ray(self, start, end, list)
+    }
+}
+
+
+/* The automata in systems.rs are written against this instead of ParticleMap, so the same rules can run
+   on the whole map on one thread or on ChunkWindows on the compute task pool (see ParticleUpdateMode).
+*/
+pub trait ParticleGrid {
+    fn get_element_at(&self, pos: (i32, i32)) -> ParticleElement;
+    fn get_velocity_at(&self, pos: (i32, i32)) -> Vec2;
+    fn get_temperature_at(&self, pos: (i32, i32)) -> Option<f32>;
+    fn get_sediment_at(&self, pos: (i32, i32)) -> ParticleElement;
+    fn carry_sediment(&mut self, pos: (i32, i32), sediment: ParticleElement, tint: [u8; 4]);
+    fn set_temperature_at(&mut self, pos: (i32, i32), temperature: f32);
+    fn give_velocity(&mut self, pos: (i32, i32), vel: Vec2);
+    fn insert_at(&mut self, element: ParticleElement, pos: (i32, i32), list: ListType) -> bool;
+    fn delete_at(&mut self, pos: (i32, i32));
+    fn swap(&mut self, a: (i32, i32), b: (i32, i32));
+    fn move_cell(&mut self, from: (i32, i32), to: (i32, i32)) -> bool;
+    fn keep_awake(&mut self, pos: (i32, i32));
+
     /* Returns the first position between start (x0, y0) and end (x1, y1) that is defined by list. For example
 
           start          1            2            3             4            5            6           end
         y   +------------+------------+------------+-----------Stone----------+------------+------------+
 
-            ray(&mut commands, (0,y), (7,y), ListType::OnlyAir)
+            ray((0,y), (7,y), ListType::OnlyAir)
                 will return (3, y).
-            ray(&mut commands, (0,y), (7,y), ListType::WhiteList(vec![ParticleElement::Air, ParticleElement::Stone])         
+            ray((0,y), (7,y), ListType::WhiteList(vec![ParticleElement::Air, ParticleElement::Stone])         
                 will return (7, y).
                 whitelist says you're allowed to move through Air and through Stone.
                 blacklist is opposite of course. so you can say
-            ray(&mut commands, (0,y), (7,y), ListType::Blacklist(vec![ParticleElement::Stone])
+            ray((0,y), (7,y), ListType::Blacklist(vec![ParticleElement::Stone])
                 only if i hit a particle of type stone do i move towards it
 
             this makes it possible to move a particle from start to end, and getting the first position hit, move to that location.
             you can check that ray(...) != end_position if you just want to detect that nothing was hit
     */
-    pub fn ray(&mut self, commands: &mut Commands, start: (i32, i32), end: (i32, i32), list: ListType) -> Option<(i32, i32)> {
+    fn ray(&self, start: (i32, i32), end: (i32, i32), list: ListType) -> Option<(i32, i32)> {
         let (mut x0, mut y0) = start;
         let (x1, y1) = end;
     
@@ -158,16 +835,18 @@ impl ParticleMap {
         if e2 > -dy { err -= dy; x0 += sx; }
         if e2 < dx { err += dx; y0 += sy; }
 
-        while (x0, y0) != (x1, y1) {
-            let element = self.get_element_at((x0, y0));
-            let should_hit = match list {
+        let should_hit = |pos: (i32, i32)| {
+            let element = self.get_element_at(pos);
+            match list {
                 ListType::All => true, // Also includes air, i think this is entirely useless here
                 ListType::OnlyAir => element != ParticleElement::Air,
                 ListType::Whitelist(ref whitelist) => !whitelist.contains(&element), // What are you allowed to move through
                 ListType::Blacklist(ref blacklist) => blacklist.contains(&element), // What are you NOT allowed to move through
-            };
-    
-            if should_hit {
+            }
+        };
+
+        while (x0, y0) != (x1, y1) {
+            if should_hit((x0, y0)) {
                 // returns the spot before a hit
                 return Some(previous);
                 // returns the location it hit first
@@ -186,10 +865,499 @@ impl ParticleMap {
                 y0 += sy;
             }
         }
+        // the end can be hit too, or something one cell away from a wall would end up inside it
+        if should_hit((x1, y1)) {
+            return Some(previous);
+        }
         Some((x1, y1))
     }
 }
 
+impl ParticleGrid for ParticleMap {
+    fn get_element_at(&self, pos: (i32, i32)) -> ParticleElement {
+        ParticleMap::get_element_at(self, pos)
+    }
+    fn get_velocity_at(&self, pos: (i32, i32)) -> Vec2 {
+        ParticleMap::get_velocity_at(self, pos)
+    }
+    fn get_temperature_at(&self, pos: (i32, i32)) -> Option<f32> {
+        ParticleMap::get_temperature_at(self, pos)
+    }
+    fn get_sediment_at(&self, pos: (i32, i32)) -> ParticleElement {
+        ParticleMap::get_sediment_at(self, pos)
+    }
+    fn carry_sediment(&mut self, pos: (i32, i32), sediment: ParticleElement, tint: [u8; 4]) {
+        ParticleMap::carry_sediment(self, pos, sediment, tint)
+    }
+    fn set_temperature_at(&mut self, pos: (i32, i32), temperature: f32) {
+        ParticleMap::set_temperature_at(self, pos, temperature)
+    }
+    fn give_velocity(&mut self, pos: (i32, i32), vel: Vec2) {
+        ParticleMap::give_velocity(self, pos, vel)
+    }
+    fn insert_at(&mut self, element: ParticleElement, pos: (i32, i32), list: ListType) -> bool {
+        ParticleMap::insert_at(self, element, pos, list)
+    }
+    fn delete_at(&mut self, pos: (i32, i32)) {
+        ParticleMap::delete_at(self, pos)
+    }
+    fn swap(&mut self, a: (i32, i32), b: (i32, i32)) {
+        ParticleMap::swap(self, a, b)
+    }
+    fn move_cell(&mut self, from: (i32, i32), to: (i32, i32)) -> bool {
+        ParticleMap::move_cell(self, from, to)
+    }
+    fn keep_awake(&mut self, pos: (i32, i32)) {
+        ParticleMap::keep_awake(self, pos)
+    }
+}
+
+
+/* A copy of the cells around the awake part of one chunk, so the chunk can be updated on another thread.
+   The rules never reach further than WINDOW_MARGIN cells from the cell they update: rays stop at the edge
+   of the window and everything outside it looks like bedrock. Chunks that are updated at the same time are
+   two chunks apart (see update_chunks), so with a margin of half a chunk their windows never overlap.
+*/
+pub const WINDOW_MARGIN: i32 = CHUNK_SIZE / 2;
+
+pub struct ChunkWindow {
+    // the awake cells of the chunk, in grid positions. only these get updated, the rest is just there to be looked at
+    active: ActiveRect,
+    min: (i32, i32),
+    size: (i32, i32),
+    cells: Vec<Option<Particle>>,
+    changed: Vec<bool>,
+    awake: Vec<(i32, i32)>,
+    registry: Arc<ElementRegistry>,
+    // for particle colours, like the map's own rng
+    rng: StdRng,
+}
+
+impl ChunkWindow {
+    fn index(&self, pos: (i32, i32)) -> Option<usize> {
+        let (x, y) = (pos.0 - self.min.0, pos.1 - self.min.1);
+        if x < 0 || y < 0 || x >= self.size.0 || y >= self.size.1 {
+            return None
+        }
+        Some((y * self.size.0 + x) as usize)
+    }
+
+    fn position(&self, index: usize) -> (i32, i32) {
+        let index = index as i32;
+        (self.min.0 + index % self.size.0, self.min.1 + index / self.size.0)
+    }
+
+    fn get_cell(&self, pos: (i32, i32)) -> Option<Particle> {
+        self.index(pos).and_then(|index| self.cells[index])
+    }
+
+    fn set_cell(&mut self, pos: (i32, i32), cell: Option<Particle>) {
+        if let Some(index) = self.index(pos) {
+            self.cells[index] = cell;
+            self.changed[index] = true;
+        }
+    }
+
+    // same as ParticleMap::positions_where, for the awake cells of the chunk this window is for
+    pub fn positions_where(&self, filter: impl Fn(&Particle) -> bool) -> Vec<(i32, i32)> {
+        let mut positions = Vec::new();
+        for y in self.active.min.1..=self.active.max.1 {
+            for x in self.active.min.0..=self.active.max.0 {
+                if self.get_cell((x, y)).is_some_and(|particle| filter(&particle)) {
+                    positions.push((x, y));
+                }
+            }
+        }
+        positions
+    }
+}
+
+impl ParticleGrid for ChunkWindow {
+    fn get_element_at(&self, pos: (i32, i32)) -> ParticleElement {
+        match self.index(pos) {
+            Some(index) => self.cells[index].map_or(ParticleElement::Air, |particle| particle.element),
+            None => ParticleElement::BedRock,
+        }
+    }
+    fn get_velocity_at(&self, pos: (i32, i32)) -> Vec2 {
+        self.get_cell(pos).map_or(Vec2::ZERO, |particle| particle.velocity)
+    }
+    fn get_temperature_at(&self, pos: (i32, i32)) -> Option<f32> {
+        self.get_cell(pos).map(|particle| particle.temperature)
+    }
+    fn get_sediment_at(&self, pos: (i32, i32)) -> ParticleElement {
+        self.get_cell(pos).map_or(ParticleElement::Air, |particle| particle.sediment)
+    }
+    fn carry_sediment(&mut self, pos: (i32, i32), sediment: ParticleElement, tint: [u8; 4]) {
+        if let Some(particle) = self.get_cell(pos) {
+            self.set_cell(pos, Some(Particle { sediment, color: muddy(particle.color, tint), ..particle }));
+        }
+    }
+    fn set_temperature_at(&mut self, pos: (i32, i32), temperature: f32) {
+        if let Some(particle) = self.get_cell(pos) {
+            self.set_cell(pos, Some(Particle { temperature, ..particle }));
+        }
+    }
+    fn give_velocity(&mut self, pos: (i32, i32), vel: Vec2) {
+        if let Some(particle) = self.get_cell(pos) {
+            self.set_cell(pos, Some(Particle { velocity: vel, ..particle }));
+            self.keep_awake(pos);
+        }
+    }
+    fn insert_at(&mut self, element: ParticleElement, pos: (i32, i32), list: ListType) -> bool {
+        if self.index(pos).is_none() || !list.allows(self.get_element_at(pos)) {
+            return false;
+        }
+        if element == ParticleElement::Air {
+            self.delete_at(pos);
+            return true;
+        }
+        let definition = self.registry.get(element);
+        let particle = Particle {
+            element,
+            color: definition.random_color(&mut self.rng),
+            velocity: Vec2::ZERO,
+            temperature: definition.temperature,
+            sediment: ParticleElement::Air,
+        };
+        self.set_cell(pos, Some(particle));
+        true
+    }
+    fn delete_at(&mut self, pos: (i32, i32)) {
+        if self.get_cell(pos).is_some() {
+            self.set_cell(pos, None);
+        }
+    }
+    // both ends have to be inside the window, anything moved out of it would be lost
+    fn swap(&mut self, a: (i32, i32), b: (i32, i32)) {
+        let (Some(index_a), Some(index_b)) = (self.index(a), self.index(b)) else {
+            return
+        };
+        self.cells.swap(index_a, index_b);
+        self.changed[index_a] = true;
+        self.changed[index_b] = true;
+    }
+    fn move_cell(&mut self, from: (i32, i32), to: (i32, i32)) -> bool {
+        if from == to || self.index(to).is_none() || self.get_cell(to).is_some() || self.get_cell(from).is_none() {
+            return false
+        }
+        self.swap(from, to);
+        true
+    }
+    fn keep_awake(&mut self, pos: (i32, i32)) {
+        self.awake.push(pos);
+    }
+}
+
+// Which way the automata run (see benches/particle_update.rs).
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ParticleUpdateMode {
+    // a job per chunk on the compute task pool
+    #[default]
+    Parallel,
+    // the same jobs one at a time on this thread, comes out exactly like Parallel
+    Serial,
+    // the old one system at a time, one cell at a time update over the whole map, kept around to compare against
+    WholeMap,
+}
+
+
+
+// Elements
+// the element list loaded through the asset server, copied into the ParticleMap once it's loaded
+#[derive(Resource)]
+pub struct ElementRegistryHandle(pub Handle<ElementRegistry>);
+
+// Heat
+// Temperature of the air on the current planet, particles with air_exchange drift towards it while they touch air
+#[derive(Resource)]
+pub struct AmbientTemperature(pub f32);
+
+impl Default for AmbientTemperature {
+    fn default() -> Self {
+        Self(20.)
+    }
+}
+
+// F3 toggles a heat map drawn over the particles
+#[derive(Resource, Default)]
+pub struct HeatOverlay {
+    pub enabled: bool,
+    pub chunks: HashMap<(i32, i32), (Entity, Handle<Image>)>,
+}
+
+// F4 outlines the parts of each chunk that are awake, everything outside them is asleep and costs nothing
+#[derive(Resource, Default)]
+pub struct ActiveRegionOverlay {
+    pub enabled: bool,
+}
+
+// Reactions
+#[derive(Resource)]
+pub struct ReactionListHandle(pub Handle<ReactionList>);
+
+#[derive(Clone, Copy, Debug)]
+pub struct Reaction {
+    pub products: (ParticleElement, ParticleElement),
+    pub chance: f64,
+    pub heat: f32,
+}
+
+/* The reaction list with the element names looked up in the ElementRegistry, keyed by the pair of
+   elements that react. Every reaction is stored both ways round so the order of a pair doesn't matter.
+   It has to be resolved again whenever the list or the registry changes.
+*/
+#[derive(Resource)]
+pub struct Reactions {
+    list: ReactionList,
+    by_pair: HashMap<(ParticleElement, ParticleElement), Vec<Reaction>>,
+    reactants: HashSet<ParticleElement>,
+}
+
+impl Reactions {
+    pub fn new(list: ReactionList, registry: &ElementRegistry) -> Self {
+        let mut reactions = Self {
+            list,
+            by_pair: HashMap::new(),
+            reactants: HashSet::new(),
+        };
+        reactions.resolve(registry);
+        reactions
+    }
+
+    pub fn set_list(&mut self, list: ReactionList, registry: &ElementRegistry) {
+        self.list = list;
+        self.resolve(registry);
+    }
+
+    // reactions naming an element the registry doesn't have are skipped
+    pub fn resolve(&mut self, registry: &ElementRegistry) {
+        self.by_pair.clear();
+        self.reactants.clear();
+        for definition in self.list.reactions.iter() {
+            let names = [&definition.reactants.0, &definition.reactants.1, &definition.products.0, &definition.products.1];
+            if let Some(missing) = names.iter().find(|name| registry.by_name(name).is_none()) {
+                warn!("skipping reaction {:?}: there is no element called {missing}", definition.reactants);
+                continue;
+            }
+            let [a, b, into_a, into_b] = names.map(|name| registry.by_name(name).unwrap());
+            let chance = definition.chance.clamp(0., 1.);
+            self.by_pair.entry((a, b)).or_default()
+                .push(Reaction { products: (into_a, into_b), chance, heat: definition.heat });
+            if a != b {
+                self.by_pair.entry((b, a)).or_default()
+                    .push(Reaction { products: (into_b, into_a), chance, heat: definition.heat });
+            }
+            self.reactants.insert(a);
+            self.reactants.insert(b);
+        }
+    }
+
+    // what can happen when `a` touches `b`, in the order they're listed. the products are in the same order as (a, b)
+    pub fn between(&self, a: ParticleElement, b: ParticleElement) -> &[Reaction] {
+        self.by_pair.get(&(a, b)).map_or(&[], |reactions| reactions.as_slice())
+    }
+
+    pub fn reacts(&self, element: ParticleElement) -> bool {
+        self.reactants.contains(&element)
+    }
+}
+
+/* Electricity. Charge put into a cell that conducts (see `conducts_charge` in the element list) spreads through
+   every conducting cell connected to it, CHARGE_SPREAD cells further each tick, and each cell stays charged for
+   CHARGE_TICKS ticks. It only lasts a moment so it's kept next to the map instead of in the cells.
+*/
+pub const CHARGE_SPREAD: usize = 6;
+pub const CHARGE_TICKS: u32 = 20;
+const MAX_CHARGED_CELLS: usize = 4096;
+
+#[derive(Resource, Default)]
+pub struct ChargeField {
+    // ticks left for every charged cell
+    charged: HashMap<(i32, i32), u32>,
+    // the cells the charge spreads out of next tick
+    front: Vec<(i32, i32)>,
+}
+
+fn conducts(map: &ParticleMap, pos: (i32, i32)) -> bool {
+    map.registry().get(map.get_element_at(pos)).conducts_charge
+}
+
+impl ChargeField {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // returns false (and does nothing) if the cell doesn't conduct
+    pub fn shock(&mut self, map: &ParticleMap, pos: (i32, i32)) -> bool {
+        if !conducts(map, pos) {
+            return false
+        }
+        self.charged.insert(pos, CHARGE_TICKS);
+        self.front.push(pos);
+        true
+    }
+
+    // one tick: old charge runs out (or flowed away with its water) and the rest spreads further
+    pub fn update(&mut self, map: &ParticleMap) {
+        self.charged.retain(|pos, ticks| {
+            *ticks -= 1;
+            *ticks > 0 && conducts(map, *pos)
+        });
+        for _ in 0..CHARGE_SPREAD {
+            let mut next = Vec::new();
+            for (x, y) in self.front.drain(..) {
+                for neighbour in [(x+1, y), (x-1, y), (x, y+1), (x, y-1)] {
+                    if self.charged.len() < MAX_CHARGED_CELLS && !self.charged.contains_key(&neighbour) && conducts(map, neighbour) {
+                        self.charged.insert(neighbour, CHARGE_TICKS);
+                        next.push(neighbour);
+                    }
+                }
+            }
+            self.front = next;
+        }
+    }
+
+    pub fn is_charged(&self, pos: (i32, i32)) -> bool {
+        self.charged.contains_key(&pos)
+    }
+
+    pub fn charged_cells(&self) -> impl Iterator<Item = &(i32, i32)> {
+        self.charged.keys()
+    }
+
+    pub fn clear(&mut self) {
+        self.charged.clear();
+        self.front.clear();
+    }
+}
+
+/* Bodies of liquid for the pressure pass (level_liquids in systems.rs). Flood filling a lake is the slow part,
+   so the bodies are kept for BODY_REFILL_STEPS steps and then all filled again at once. In between, a cell
+   moved out of the top of a body into one of its outlets updates the body's surface and outlets, and
+   everything is checked against the map again before a cell is moved, so a body can go a bit out of date
+   (i.e. the automata moved some of its cells) without anything going wrong, it just moves a bit less.
+   Bodies are only filled MAX_BODY_CELLS cells out from the cell they're found from, the rest of a big lake is asleep anyway.
+*/
+pub const BODY_REFILL_STEPS: u32 = 8;
+const MAX_BODY_CELLS: usize = 16384;
+
+pub struct LiquidBody {
+    pub element: ParticleElement,
+    // cells with air above them, the top of the body. highest first
+    pub surface: Vec<(i32, i32)>,
+    // air next to the body and which way out of the body it is. lowest first
+    pub outlets: Vec<((i32, i32), Vec2)>,
+}
+
+#[derive(Resource, Default)]
+pub struct LiquidBodies {
+    bodies: Vec<LiquidBody>,
+    // which body each of their cells is in
+    owner: HashMap<(i32, i32), usize>,
+    steps: u32,
+}
+
+impl LiquidBodies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // every BODY_REFILL_STEPS steps the bodies are forgotten, so they get filled again
+    pub fn begin_step(&mut self) {
+        self.steps += 1;
+        if self.steps >= BODY_REFILL_STEPS {
+            self.clear();
+        }
+    }
+
+    // the body a still liquid cell is in, it's filled from that cell if it isn't in one yet.
+    // the fill doesn't go into cells another body already has
+    pub fn body_at(&mut self, map: &ParticleMap, start: (i32, i32)) -> usize {
+        if let Some(&id) = self.owner.get(&start) {
+            return id
+        }
+        let id = self.bodies.len();
+        let element = map.get_element_at(start);
+        let mut body = LiquidBody { element, surface: Vec::new(), outlets: Vec::new() };
+        let mut outlets_seen = HashSet::new();
+        let mut cells = vec![start];
+        self.owner.insert(start, id);
+        let mut next = 0;
+        while let Some(&(x, y)) = cells.get(next) {
+            next += 1;
+            for (dx, dy) in [(0, 1), (1, 0), (-1, 0), (0, -1)] {
+                let to = (x + dx, y + dy);
+                let neighbour = map.get_element_at(to);
+                if neighbour == ParticleElement::Air {
+                    if dy == 1 {
+                        body.surface.push((x, y));
+                    }
+                    if grid_coords_within_map(to) && outlets_seen.insert(to) {
+                        body.outlets.push((to, Vec2::new(dx as f32, dy as f32)));
+                    }
+                } else if neighbour == element && cells.len() < MAX_BODY_CELLS && map.get_velocity_at(to) == Vec2::ZERO
+                    && !self.owner.contains_key(&to) {
+                    // anything flying isn't part of the body (yet)
+                    self.owner.insert(to, id);
+                    cells.push(to);
+                }
+            }
+        }
+        // highest cells go to the lowest outlets first
+        body.surface.sort_by_key(|(_, y)| -y);
+        body.outlets.sort_by_key(|((_, y), _)| *y);
+        self.bodies.push(body);
+        id
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut LiquidBody> {
+        self.bodies.get_mut(id)
+    }
+
+    // a cell was moved off the top of a body into one of its outlets (it's in the map already). the cell under it
+    // is the top now, and the air over where it went is an outlet
+    pub fn moved(&mut self, map: &ParticleMap, id: usize, from: (i32, i32), to: (i32, i32)) {
+        let Some(body) = self.bodies.get_mut(id) else {
+            return
+        };
+        body.surface.retain(|pos| *pos != from);
+        body.outlets.retain(|(pos, _)| *pos != to);
+        self.owner.remove(&from);
+        self.owner.insert(to, id);
+
+        let below = (from.0, from.1 - 1);
+        if self.owner.get(&below) == Some(&id) && map.get_element_at(below) == body.element {
+            let index = body.surface.partition_point(|(_, y)| *y > below.1);
+            body.surface.insert(index, below);
+        }
+        let above = (to.0, to.1 + 1);
+        if map.get_element_at(above) == ParticleElement::Air && grid_coords_within_map(above)
+            && !body.outlets.iter().any(|(pos, _)| *pos == above) {
+            let index = body.outlets.partition_point(|((_, y), _)| *y <= above.1);
+            body.outlets.insert(index, (above, Vec2::Y));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bodies.clear();
+        self.owner.clear();
+        self.steps = 0;
+    }
+}
+
+// Rendering
+// The sprite entity and texture for each layer of a chunk
+pub type ChunkLayers = Vec<(Entity, Handle<Image>)>;
+
+// Every chunk render_particles has drawn. every distinct z_layer in the ElementRegistry gets its own
+// texture per chunk, so water can still be drawn over the player
+#[derive(Resource, Default)]
+pub struct ParticleChunkImages {
+    pub layers: Vec<f32>,
+    pub chunks: HashMap<(i32, i32), ChunkLayers>,
+}
+
 
 
 // Utility functions.
@@ -200,6 +1368,12 @@ pub fn convert_to_grid_position(x: f32, y: f32) -> (i32, i32) {
 }
 
 
+// half the liquid's colour and half the sediment's, alpha stays the liquid's
+fn muddy(color: [u8; 4], tint: [u8; 4]) -> [u8; 4] {
+    let mix = |a: u8, b: u8| ((a as u16 + b as u16) / 2) as u8;
+    [mix(color[0], tint[0]), mix(color[1], tint[1]), mix(color[2], tint[2]), color[3]]
+}
+
 pub fn grid_coords_within_map(pos: (i32, i32)) -> bool {
     let x = pos.0 as f32 * PARTICLE_SIZE;
     let y = pos.1 as f32 * PARTICLE_SIZE;
@@ -219,7 +1393,7 @@ pub struct ChunkList {
 impl ChunkList {
     pub fn new() -> Self {
         Self {
-            size: 64.,
+            size: CHUNK_SIZE as f32,
             chunk_list: HashSet::new(),
         }
     }
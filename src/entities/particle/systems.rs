@@ -1,508 +1,802 @@
-use bevy::{prelude::*, ui::update};
-use rand::Rng;
-use super::{components::*, resources::*};
-use crate::common::{gravity::{Gravity, GravityResource}, hitbox::Hitbox, perlin_noise::{generate_permutation_array, get_1d_octaves, get_2d_octaves}, state::{AppState, GamePhase}};
-use crate::entities::player::components::Player;
-use crate::{LEVEL_W, LEVEL_H};
+use bevy::{prelude::*, utils::HashSet};
+use bevy::render::{
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+    texture::ImageSampler,
+};
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+use rand::{rngs::StdRng, Rng};
+use super::{components::*, elements::*, reactions::*, resources::*};
+use crate::common::{death::Death, gravity::GravityResource, wind::Wind, hitbox::Hitbox, seed::{SimulationRng, SimulationSeed}, timestep::{run_simulation_steps, SimulationStep, TimestepSettings}, state::AppState};
+use crate::entities::{enemy::components::{Enemy, EnemyHealth}, player::components::{Health, Player}};
 
+// Update functions (cellular automata) make sure to update plugins at the bottom -------------------------------
 
+/* One set of movement rules for every element that isn't Solid. What an element does comes from the
+   ElementRegistry: its class picks the rules, density decides what it can sink (or float up) through,
+   viscosity is the chance it moves at all this frame and dispersion is how far a gas drifts in one go.
+   Only awake cells are looked at (see ParticleMap::keep_awake), so anything that could still move but
+   didn't has to keep itself awake or it would freeze in place.
+   A particle with a velocity flies instead (see fly), that's anything falling freely, thrown, shot or blown up.
+*/
+const GAS_BUOYANCY_BIAS: f64 = 0.3; // chance a gas drifts towards where it's floating/sinking instead of any direction
+const FALL_START_SPEED: f32 = 1.;   // cells per step something has when it starts to fall freely, it speeds up from there
+const SPLASH_MIN_SPEED: f32 = 1.5;  // anything slower than this just stops when it hits something
+const LIQUID_SPLASH: f32 = 0.5;     // how much of its speed a liquid keeps when it lands, sideways and a bit up
+const POWDER_SCATTER: f32 = 0.3;    // same for powders, they only scatter sideways
+const GAS_DRAG: f32 = 0.8;          // gases don't fall, they slow down to this much of their speed every step
+const GAS_MIN_SPEED: f32 = 0.5;     // and stop once they're slower than this
+const WIND_DRAG: f32 = 0.1;         // how much of the difference to the wind's speed something flying catches up every step (times `wind`)
 
+fn update_particles(
+    mut map: ResMut<ParticleMap>,
+    timestep: Res<TimestepSettings>,
+    grav_res: Res<GravityResource>,
+    wind: Res<Wind>,
+    player_hb_query: Query<&Hitbox, With<Player>>,
+    mut sim_rng: ResMut<SimulationRng>,
+) {
+    let registry = map.shared_registry();
+    let step = StepContext {
+        registry: &registry,
+        player_hb: player_hb_query.get_single().ok(),
+        deltat: timestep.step_seconds(),
+        grav_res: &grav_res,
+        wind: &wind,
+    };
+    let positions = map.awake_positions_where(|particle| registry.get(particle.element).class != ElementClass::Solid);
+    move_particles(&mut *map, positions, &step, sim_rng.stream("particles"));
+}
 
+// what the rules need besides the grid, shared by every chunk in update_chunks
+struct StepContext<'a> {
+    registry: &'a ElementRegistry,
+    player_hb: Option<&'a Hitbox>,
+    deltat: f32,
+    grav_res: &'a GravityResource,
+    wind: &'a Wind,
+}
 
+impl StepContext<'_> {
+    // Wind is in pixels per second, this is cells per step
+    fn wind_at(&self, pos: (i32, i32)) -> Vec2 {
+        self.wind.at(pos) * self.deltat / PARTICLE_SIZE
+    }
+}
 
+fn move_particles(map: &mut impl ParticleGrid, positions: Vec<(i32, i32)>, step: &StepContext, rng: &mut impl Rng) {
+    let StepContext { registry, player_hb, .. } = *step;
+    let particles: Vec<((i32, i32), ParticleElement)> = positions
+        .into_iter()
+        .map(|pos| (pos, map.get_element_at(pos)))
+        .collect();
 
+    for (pos, element) in particles {
+        // the list is from before this loop, so skip anything that already moved or got replaced
+        if map.get_element_at(pos) != element {
+            continue;
+        }
+        let definition = registry.get(element);
 
+        let velocity = map.get_velocity_at(pos);
+        if velocity != Vec2::ZERO {
+            fly(map, pos, definition, velocity, step, rng);
+            continue;
+        }
 
+        if definition.decay > 0. {
+            if rng.gen_bool(definition.decay as f64) && surrounded_by_air(map, pos) {
+                map.delete_at(pos);
+                continue;
+            }
+            map.keep_awake(pos);
+        }
+        if !rng.gen_bool(definition.viscosity.clamp(0., 1.) as f64) {
+            // water at the bottom of a lake would fail this every few steps and never get to sleep
+            if could_move(map, registry, definition, pos) || map.get_sediment_at(pos) != ParticleElement::Air {
+                map.keep_awake(pos);
+            }
+            continue;
+        }
 
+        // stuff that blocks the player shouldn't fall into them and get them stuck.
+        // the player moving doesn't change the map, so wait for them to move out of the way
+        let blocked = |map: &mut dyn ParticleGrid, to: (i32, i32)| {
+            let blocked = definition.blocks_player && player_hb.is_some_and(|hb| hb.is_particle_in_hitbox(to));
+            if blocked {
+                map.keep_awake(pos);
+            }
+            blocked
+        };
+        let (x, y) = pos;
+        let left_first = rng.gen_bool(0.5);
+        let (first, second) = if left_first { (-1, 1) } else { (1, -1) };
+        match definition.class {
+            ElementClass::Powder => {
+                for to in [(x, y-1), (x+first, y-1), (x+second, y-1)] {
+                    let target = map.get_element_at(to);
+                    if !blocked(map, to) && can_displace(registry, definition.density, target, true) {
+                        map.swap(pos, to);
+                        start_falling(map, pos, to, target);
+                        break;
+                    }
+                }
+            }
+            ElementClass::Liquid => {
+                let mut moved_to = None;
+                for to in [(x, y-1), (x+first, y-1), (x+second, y-1)] {
+                    let target = map.get_element_at(to);
+                    if !blocked(map, to) && can_displace(registry, definition.density, target, true) {
+                        map.swap(pos, to);
+                        start_falling(map, pos, to, target);
+                        moved_to = Some(to);
+                        break;
+                    }
+                }
+                if moved_to.is_none() {
+                    for to in [(x+first, y), (x+second, y)] {
+                        if !blocked(map, to) && map.move_cell(pos, to) {
+                            moved_to = Some(to);
+                            break;
+                        }
+                    }
+                }
+                match moved_to {
+                    Some(to) => erode(map, registry, element, to, 1., rng),
+                    None => drop_sediment(map, pos, rng),
+                }
+            }
+            ElementClass::Gas => {
+                // a gas picks a random spot to drift to, so it only sleeps once it's boxed in
+                let wind = step.wind_at(pos) * definition.wind;
+                if !drift_gas(map, registry, pos, element, wind, rng) && touches_air(map, pos) {
+                    map.keep_awake(pos);
+                }
+            }
+            ElementClass::Solid => {}
+        }
+    }
+}
 
+// is there anywhere update_particles could move this cell to? same checks as above without the dice
+fn could_move(map: &impl ParticleGrid, registry: &ElementRegistry, definition: &ElementDefinition, (x, y): (i32, i32)) -> bool {
+    let below = [(x, y-1), (x-1, y-1), (x+1, y-1)];
+    match definition.class {
+        ElementClass::Powder => below.iter().any(|&to| can_displace(registry, definition.density, map.get_element_at(to), true)),
+        ElementClass::Liquid => {
+            below.iter().any(|&to| can_displace(registry, definition.density, map.get_element_at(to), true))
+                || [(x-1, y), (x+1, y)].iter().any(|&to| map.get_element_at(to) == ParticleElement::Air)
+        }
+        // gases can bubble through things too, so don't bother and keep them awake
+        ElementClass::Gas => true,
+        ElementClass::Solid => false,
+    }
+}
 
+// can something of `density` move into a cell of `target`? anything can move into air,
+// and it can sink through (or float up through) liquids and gases lighter (or heavier) than itself
+fn can_displace(registry: &ElementRegistry, density: f32, target: ParticleElement, sinking: bool) -> bool {
+    if target == ParticleElement::Air {
+        return true;
+    }
+    let target = registry.get(target);
+    if target.class != ElementClass::Liquid && target.class != ElementClass::Gas {
+        return false;
+    }
+    if sinking {
+        target.density < density
+    } else {
+        target.density > density
+    }
+}
 
-// Update functions (cellular automata) make sure to update plugins at the bottom -------------------------------
-fn update_water(
-    mut map: ResMut<ParticleMap>,
-    time: Res<Time>, 
-    mut commands: Commands,
-    mut particles: Query<&mut ParticlePosVel, With<ParticleTagWater>>,
-    grav_res: ResMut<GravityResource>,
-    player_hb_query: Query<& Hitbox, With<Player>>,
-) {
-    let deltat = time.delta_seconds() ;
+// a particle that dropped straight down into air is falling freely, from now on it flies
+fn start_falling(map: &mut impl ParticleGrid, from: (i32, i32), to: (i32, i32), target: ParticleElement) {
+    if target == ParticleElement::Air && to == (from.0, from.1 - 1) {
+        map.give_velocity(to, Vec2::new(0., -FALL_START_SPEED));
+    }
+}
 
-    let player_hb: &Hitbox = player_hb_query.single();
-    let mut rng = rand::thread_rng();
-    for mut position in &mut particles {
-        if position.velocity.x != 0. && position.velocity.y != 0.{
-            let new_pos = ((position.grid_x as f32 + position.velocity.x) as i32, (position.grid_y as f32 + position.velocity.y) as i32);
-            position.velocity.y = Gravity::update_gravity(&position.velocity.y, &deltat, &grav_res);
+/* Particles with a velocity (in cells per step) fly in a straight line every step, falling with the planet's
+   gravity, and stop in front of the first thing in their way. A hard landing turns into a splash for liquids
+   and a scatter for powders (see impact), so falling sand, blaster shots and explosions all end up the same way.
+   Gases don't fall, they just slow down.
+*/
+fn fly(map: &mut impl ParticleGrid, pos: (i32, i32), definition: &ElementDefinition, mut velocity: Vec2, step: &StepContext, rng: &mut impl Rng) {
+    if definition.class == ElementClass::Gas {
+        velocity *= GAS_DRAG;
+        if velocity.length() < GAS_MIN_SPEED {
+            velocity = Vec2::ZERO;
+        }
+    } else {
+        velocity.y = fall(velocity.y, step.deltat, step.grav_res);
+    }
+    if definition.wind > 0. {
+        velocity.x += (step.wind_at(pos).x - velocity.x) * definition.wind * WIND_DRAG;
+    }
 
-            let p = map.ray(&mut commands, (position.grid_x, position.grid_y), new_pos, ListType::OnlyAir);
-            if let Some(position_of_part) = p {
-                if position_of_part != new_pos{
-                    position.velocity = Vec2::splat(0.);
-                }
-                map.delete_at(&mut commands, (position.grid_x, position.grid_y));
-                map.insert_at::<WaterParticle>(&mut commands, position_of_part, ListType::OnlyAir);
-                map.give_velocity(&mut commands, position_of_part, Vec2::new(position.velocity.x, position.velocity.y), );
-            }
-        } else {
-            let mut rng = rand::thread_rng();
-            let viscosity = rng.gen::<f64>() < WATER_VISCOSITY as f64;
-                let (x, y) = (position.grid_x, position.grid_y);
-                if viscosity && map.insert_at::<WaterParticle>(&mut commands, (x, y-1), ListType::OnlyAir) {
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x,y-1)) == ParticleElement::Lava && !player_hb.is_particle_in_hitbox((x, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                    map.delete_at(&mut commands, (x, y-1));
-                    map.insert_at::<ObsidianParticle>(&mut commands, (x, y-1), ListType::OnlyAir);
-                } else if viscosity && map.get_element_at((x,y-1)) == ParticleElement::Healing_Spring && !player_hb.is_particle_in_hitbox((x, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                }
+    let target = (pos.0 + velocity.x.round() as i32, pos.1 + velocity.y.round() as i32);
+    let mut stop = map.ray(pos, target, ListType::OnlyAir).unwrap_or(pos);
+    // stuff that blocks the player stops in front of them instead of landing inside them
+    if definition.blocks_player && step.player_hb.is_some_and(|hb| hb.is_particle_in_hitbox(stop)) {
+        stop = pos;
+    }
+    map.move_cell(pos, stop);
+    if stop != target {
+        // a jet of water (i.e. the water blaster) digs into what it hits
+        let element = map.get_element_at(stop);
+        erode(map, step.registry, element, stop, velocity.length(), rng);
+        velocity = impact(map, stop, definition, velocity, rng);
+    }
+    map.give_velocity(stop, velocity);
+}
 
-                else if viscosity && map.insert_at::<WaterParticle>(&mut commands, (x-1, y-1), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x-1,y-1)) == ParticleElement::Lava && !player_hb.is_particle_in_hitbox((x-1, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                    map.delete_at(&mut commands, (x-1, y-1));
-                    map.insert_at::<ObsidianParticle>(&mut commands, (x-1, y-1), ListType::OnlyAir);
-                } else if viscosity && map.get_element_at((x-1,y-1)) == ParticleElement::Healing_Spring && !player_hb.is_particle_in_hitbox((x-1, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                }
+// GravityResource is in pixels per second like the player's, particle velocities are in cells per step.
+// gravity doesn't slow down something that's already going faster than terminal velocity (i.e. shot downwards)
+fn fall(velocity_y: f32, deltat: f32, grav_res: &GravityResource) -> f32 {
+    let gravity = grav_res.gravity_force * deltat * deltat / PARTICLE_SIZE;
+    let terminal = grav_res.terminal_velocity * deltat / PARTICLE_SIZE;
+    if velocity_y <= -terminal {
+        return velocity_y;
+    }
+    (velocity_y - gravity).max(-terminal)
+}
 
-                else if viscosity && map.insert_at::<WaterParticle>(&mut commands, (x+1, y-1), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x+1,y-1)) == ParticleElement::Lava && !player_hb.is_particle_in_hitbox((x+1, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                    map.delete_at(&mut commands, (x+1, y-1));
-                    map.insert_at::<ObsidianParticle>(&mut commands, (x+1, y-1), ListType::OnlyAir);
-                } else if viscosity && map.get_element_at((x+1,y-1)) == ParticleElement::Healing_Spring && !player_hb.is_particle_in_hitbox((x+1, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                }
+// the velocity a particle has left after hitting something at `pos`
+fn impact(map: &impl ParticleGrid, (x, y): (i32, i32), definition: &ElementDefinition, velocity: Vec2, rng: &mut impl Rng) -> Vec2 {
+    let speed = velocity.length();
+    let keep = match definition.class {
+        ElementClass::Liquid => LIQUID_SPLASH,
+        ElementClass::Powder => POWDER_SCATTER,
+        _ => return Vec2::ZERO,
+    };
+    if speed < SPLASH_MIN_SPEED {
+        return Vec2::ZERO;
+    }
+    let landed = velocity.y < 0. && map.get_element_at((x, y-1)) != ParticleElement::Air;
+    let hit_ceiling = velocity.y > 0. && map.get_element_at((x, y+1)) != ParticleElement::Air;
+    if landed {
+        // spread out the way it was going, or either way if it came straight down
+        let side = if velocity.x.abs() > 0.1 { velocity.x.signum() } else if rng.gen_bool(0.5) { 1. } else { -1. };
+        let up = if definition.class == ElementClass::Liquid { speed * keep / 2. } else { 0. };
+        Vec2::new(side * speed * keep, up)
+    } else if hit_ceiling {
+        Vec2::new(velocity.x * keep, 0.)
+    } else {
+        // hit a wall, bounce back off it and let gravity do the rest
+        Vec2::new(-velocity.x * keep, 0.)
+    }
+}
 
-                else if viscosity && map.insert_at::<WaterParticle>(&mut commands, (x-1, y), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x-1, y)) == ParticleElement::Lava && !player_hb.is_particle_in_hitbox((x-1, y)) {
-                    map.delete_at(&mut commands, (x, y));
-                    map.delete_at(&mut commands, (x-1, y));
-                    map.insert_at::<ObsidianParticle>(&mut commands, (x-1, y), ListType::OnlyAir);
-                } else if viscosity && map.get_element_at((x-1, y)) == ParticleElement::Healing_Spring && !player_hb.is_particle_in_hitbox((x-1, y)) {
-                    map.delete_at(&mut commands, (x, y));
-                }
-                 
-                else if viscosity && map.insert_at::<WaterParticle>(&mut commands, (x+1, y), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x+1, y)) == ParticleElement::Lava && !player_hb.is_particle_in_hitbox((x+1, y)){
-                    map.delete_at(&mut commands, (x, y));
-                    map.delete_at(&mut commands, (x+1, y));
-                    map.insert_at::<ObsidianParticle>(&mut commands, (x+1, y), ListType::OnlyAir);
-                } else if viscosity && map.get_element_at((x+1, y)) == ParticleElement::Healing_Spring && !player_hb.is_particle_in_hitbox((x+1, y)){
-                    map.delete_at(&mut commands, (x, y));
-                }
+// gases wander around randomly, leaning up if they are lighter than air and down if they're heavier,
+// and get blown along by `wind` (cells per step). true if it moved
+fn drift_gas(map: &mut impl ParticleGrid, registry: &ElementRegistry, pos: (i32, i32), element: ParticleElement, wind: Vec2, rng: &mut impl Rng) -> bool {
+    let definition = registry.get(element);
+    let rising = definition.density < registry.get(ParticleElement::Air).density;
+    let (x, y) = pos;
+
+    // bubble up (or down) through liquids
+    let next = if rising { (x, y+1) } else { (x, y-1) };
+    let next_element = map.get_element_at(next);
+    if next_element != ParticleElement::Air && can_displace(registry, definition.density, next_element, !rising) {
+        map.swap(pos, next);
+        return true;
+    }
+
+    let radius: i32 = rng.gen_range(1..=definition.dispersion.max(1));
+    let angle = if rng.gen_bool(GAS_BUOYANCY_BIAS) {
+        let (from, to) = if rising { (1., 3.) } else { (-3., -1.) };
+        rng.gen_range(from * std::f32::consts::FRAC_PI_4..=to * std::f32::consts::FRAC_PI_4)
+    } else {
+        rng.gen_range(0.0..=2.0 * std::f32::consts::PI)
+    };
+    let dx = (radius as f32 * angle.cos()).round() as i32 + round_randomly(wind.x, rng);
+    let dy = (radius as f32 * angle.sin()).round() as i32 + round_randomly(wind.y, rng);
+    let new_pos = (x + dx, y + dy);
+
+    if let Some(position_of_part) = map.ray(pos, new_pos, ListType::Whitelist(vec!(element, ParticleElement::Air))) {
+        // Check that the new coordinates are within bounds before moving
+        if grid_coords_within_map(position_of_part) && map.move_cell(pos, position_of_part) {
+            return true;
         }
     }
+    false
 }
 
+// 0.3 is 1 three times out of ten, so a slow wind still adds up over a few steps
+fn round_randomly(value: f32, rng: &mut impl Rng) -> i32 {
+    let whole = value.trunc();
+    let extra = rng.gen_bool((value - whole).abs().min(1.) as f64);
+    whole as i32 + if extra { value.signum() as i32 } else { 0 }
+}
 
-fn update_lava(
-    mut map: ResMut<ParticleMap>,
-    time: Res<Time>, 
-    mut commands: Commands,
-    mut particles: Query<&mut ParticlePosVel, With<ParticleTagLava>>,
-    player_hb_query: Query<& Hitbox, With<Player>>,
-    grav_res: ResMut<GravityResource>,
-) {
-    let deltat = time.delta_seconds() ;
-    
-    let player_hb: &Hitbox = player_hb_query.single();
-    let mut rng = rand::thread_rng();
-    for mut position in &mut particles {
-        if position.velocity.x != 0. && position.velocity.y != 0.{
-            let new_pos = ((position.grid_x as f32 + position.velocity.x) as i32, (position.grid_y as f32 + position.velocity.y) as i32);
-            position.velocity.y = Gravity::update_gravity(&position.velocity.y, &deltat, &grav_res);
-
-            let p = map.ray(&mut commands, (position.grid_x, position.grid_y), new_pos, ListType::OnlyAir);
-            if let Some(position_of_part) = p {
-                if position_of_part != new_pos{
-                    position.velocity = Vec2::splat(0.);
-                }
-                map.delete_at(&mut commands, (position.grid_x, position.grid_y));
-                map.insert_at::<LavaParticle>(&mut commands, position_of_part, ListType::OnlyAir);
-                map.give_velocity(&mut commands, position_of_part, Vec2::new(position.velocity.x, position.velocity.y), );
-            }
-        } else {
-            let mut rng = rand::thread_rng();
-            let viscosity = rng.gen::<f64>() < LAVA_VISCOSITY as f64;
-                let (x, y) = (position.grid_x, position.grid_y);
-                if viscosity && map.insert_at::<LavaParticle>(&mut commands, (x, y-1), ListType::OnlyAir) {
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x, y-1)) == ParticleElement::Water && !player_hb.is_particle_in_hitbox((x, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                    map.delete_at(&mut commands, (x, y-1));
-                    map.insert_at::<ObsidianParticle>(&mut commands, (x, y-1), ListType::OnlyAir);
-                } else if viscosity && map.get_element_at((x, y-1)) == ParticleElement::Healing_Spring && !player_hb.is_particle_in_hitbox((x, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                }
+fn touches_air(map: &impl ParticleGrid, (x, y): (i32, i32)) -> bool {
+    [(x-1, y-1), (x, y-1), (x+1, y-1), (x-1, y), (x+1, y), (x-1, y+1), (x, y+1), (x+1, y+1)]
+        .iter()
+        .any(|&neighbour| map.get_element_at(neighbour) == ParticleElement::Air)
+}
 
-                else if viscosity && map.insert_at::<LavaParticle>(&mut commands, (x-1, y-1), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x-1, y-1)) == ParticleElement::Water && !player_hb.is_particle_in_hitbox((x-1, y-1)){
-                    map.delete_at(&mut commands, (x, y));
-                    map.delete_at(&mut commands, (x-1, y-1));
-                    map.insert_at::<ObsidianParticle>(&mut commands, (x-1, y-1), ListType::OnlyAir);
-                } else if viscosity && map.get_element_at((x-1, y-1)) == ParticleElement::Healing_Spring && !player_hb.is_particle_in_hitbox((x-1, y-1)){
-                    map.delete_at(&mut commands, (x, y));
-                }
+// This decay logic just says if the positions 10 away in each cardinal direction is air
+fn surrounded_by_air(map: &impl ParticleGrid, (x, y): (i32, i32)) -> bool {
+    map.get_element_at((x+10, y)) == ParticleElement::Air &&
+    map.get_element_at((x, y+10)) == ParticleElement::Air &&
+    map.get_element_at((x-10, y)) == ParticleElement::Air &&
+    map.get_element_at((x, y-10)) == ParticleElement::Air
+}
 
-                else if viscosity && map.insert_at::<LavaParticle>(&mut commands, (x+1, y-1), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x+1, y-1)) == ParticleElement::Water && !player_hb.is_particle_in_hitbox((x+1, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                    map.delete_at(&mut commands, (x+1, y-1));
-                    map.insert_at::<ObsidianParticle>(&mut commands, (x+1, y-1), ListType::OnlyAir);
-                } else if viscosity && map.get_element_at((x+1, y-1)) == ParticleElement::Healing_Spring && !player_hb.is_particle_in_hitbox((x+1, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                }
 
-                else if viscosity && map.insert_at::<LavaParticle>(&mut commands, (x-1, y), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x-1, y)) == ParticleElement::Water && !player_hb.is_particle_in_hitbox((x-1, y)){
-                    map.delete_at(&mut commands, (x, y));
-                    map.delete_at(&mut commands, (x-1, y));
-                    map.insert_at::<ObsidianParticle>(&mut commands, (x-1, y), ListType::OnlyAir);
-                } else if viscosity && map.get_element_at((x-1, y)) == ParticleElement::Healing_Spring && !player_hb.is_particle_in_hitbox((x-1, y)){
-                    map.delete_at(&mut commands, (x, y));
-                }
+/* Erosion. A liquid that's flowing (it moved this step) can pick up a cell of what it flows past, the chance
+   is the liquid's `erodes` times the cell's `erodibility`. The cell turns into more of the liquid and the
+   flowing cell carries it along as sediment (and looks muddy). Once it stops flowing the sediment drops out
+   again and the liquid cell turns back into it, so water cuts channels where it runs and builds up deltas
+   where it pools. Nothing is lost, one cell of ground becomes one cell of water and back.
+   Solids are carried as what they crumble into (grass as dirt), see `crumbles`.
+*/
+const SEDIMENT_DROP: f64 = 0.02; // chance per step a liquid that isn't flowing drops what it carries
 
-                else if viscosity && map.insert_at::<LavaParticle>(&mut commands, (x+1, y), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x+1, y)) == ParticleElement::Water && !player_hb.is_particle_in_hitbox((x+1, y)) {
-                    map.delete_at(&mut commands, (x, y));
-                    map.delete_at(&mut commands, (x+1, y));
-                    map.insert_at::<ObsidianParticle>(&mut commands, (x+1, y), ListType::OnlyAir);
-                } else if viscosity && map.get_element_at((x+1, y)) == ParticleElement::Healing_Spring && !player_hb.is_particle_in_hitbox((x+1, y)) {
-                    map.delete_at(&mut commands, (x, y));
-                }
+// `strength` is 1 for flowing liquid, a jet hitting something erodes as much more as it's fast
+fn erode(map: &mut impl ParticleGrid, registry: &ElementRegistry, liquid: ParticleElement, (x, y): (i32, i32), strength: f32, rng: &mut impl Rng) {
+    let erodes = registry.get(liquid).erodes * strength;
+    if erodes <= 0. || map.get_sediment_at((x, y)) != ParticleElement::Air {
+        return
+    }
+    for next in [(x, y-1), (x-1, y), (x+1, y)] {
+        let element = map.get_element_at(next);
+        let chance = erodes * registry.get(element).erodibility;
+        if chance > 0. && rng.gen_bool(chance.min(1.) as f64) {
+            let sediment = loose_form(registry, element);
+            map.insert_at(liquid, next, ListType::All);
+            map.carry_sediment((x, y), sediment, registry.get(sediment).random_color(rng));
+            return
+        }
+    }
+}
+
+fn drop_sediment(map: &mut impl ParticleGrid, pos: (i32, i32), rng: &mut impl Rng) {
+    let sediment = map.get_sediment_at(pos);
+    if sediment == ParticleElement::Air {
+        return
+    }
+    if rng.gen_bool(SEDIMENT_DROP) {
+        let temperature = map.get_temperature_at(pos);
+        map.insert_at(sediment, pos, ListType::All);
+        if let Some(temperature) = temperature {
+            map.set_temperature_at(pos, temperature);
+        }
+    } else {
+        // still has to drop it, so it can't fall asleep yet
+        map.keep_awake(pos);
+    }
+}
+
+// what a solid ends up as once it's broken up, i.e. grass -> dirt
+fn loose_form(registry: &ElementRegistry, mut element: ParticleElement) -> ParticleElement {
+    for _ in 0..4 {
+        if registry.get(element).class != ElementClass::Solid {
+            break;
+        }
+        match registry.crumbles_into(element) {
+            Some(into) => element = into,
+            None => break,
         }
     }
+    element
 }
 
-fn update_healing_spring(
+/* Pressure. The rules above only look at the cells right next to a liquid, so a lake takes forever to level out
+   and water never goes up the other side of a U bend. After the automata every body of liquid with something
+   awake in it (see LiquidBodies) has cells taken off the top of it and put into the lowest open cells next
+   to it (its outlets), which is where the pressure at the bottom would push them.
+   The deeper an outlet is under the top the more often that happens (flow goes with sqrt(head)), and a cell
+   pushed out of a deep outlet leaves it at sqrt(2 g head) like Torricelli says, so a hole in a tank sprays
+   and a pipe pointing up makes a fountain.
+*/
+const FULL_FLOW_HEAD: f32 = 16.; // cells of head at which an outlet takes a cell every step (times the viscosity)
+const MAX_FLOW: usize = 64;      // cells one body can move in one step
+const JET_KEEP: f32 = 0.7;       // how much of the sqrt(2 g head) speed a jet really gets
+
+fn level_liquids(
     mut map: ResMut<ParticleMap>,
-    time: Res<Time>, 
-    mut commands: Commands,
-    mut particles: Query<&mut ParticlePosVel, With<ParticleTagHealing_Spring>>,
-    player_hb_query: Query<& Hitbox, With<Player>>,
-    grav_res: ResMut<GravityResource>,
+    mut bodies: ResMut<LiquidBodies>,
+    timestep: Res<TimestepSettings>,
+    grav_res: Res<GravityResource>,
+    mut sim_rng: ResMut<SimulationRng>,
 ) {
-    let deltat = time.delta_seconds() ;
-    
-    let player_hb: &Hitbox = player_hb_query.single();
-    let mut rng = rand::thread_rng();
-    for mut position in &mut particles {
-        if position.velocity.x != 0. && position.velocity.y != 0.{
-            let new_pos = ((position.grid_x as f32 + position.velocity.x) as i32, (position.grid_y as f32 + position.velocity.y) as i32);
-            position.velocity.y = Gravity::update_gravity(&position.velocity.y, &deltat, &grav_res);
-
-            let p = map.ray(&mut commands, (position.grid_x, position.grid_y), new_pos, ListType::OnlyAir);
-            if let Some(position_of_part) = p {
-                if position_of_part != new_pos{
-                    position.velocity = Vec2::splat(0.);
-                }
-                map.delete_at(&mut commands, (position.grid_x, position.grid_y));
-                map.insert_at::<Healing_SpringParticle>(&mut commands, position_of_part, ListType::OnlyAir);
-                map.give_velocity(&mut commands, position_of_part, Vec2::new(position.velocity.x, position.velocity.y), );
-            }
-        } else {
-            let mut rng = rand::thread_rng();
-            let viscosity = rng.gen::<f64>() < HEALING_SPRING_VISCOSITY as f64;
-                let (x, y) = (position.grid_x, position.grid_y);
-                if viscosity && map.insert_at::<Healing_SpringParticle>(&mut commands, (x, y-1), ListType::OnlyAir) {
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x, y-1)) == ParticleElement::Water && !player_hb.is_particle_in_hitbox((x, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                }
+    let registry = map.shared_registry();
+    let rng = sim_rng.stream("pressure");
+    let deltat = timestep.step_seconds();
+    // in cells per step per step, same as in fall
+    let gravity = grav_res.gravity_force * deltat * deltat / PARTICLE_SIZE;
+    let liquids = map.awake_positions_where(|particle| {
+        registry.get(particle.element).class == ElementClass::Liquid && particle.velocity == Vec2::ZERO
+    });
 
-                else if viscosity && map.insert_at::<Healing_SpringParticle>(&mut commands, (x-1, y-1), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x-1, y-1)) == ParticleElement::Water && !player_hb.is_particle_in_hitbox((x-1, y-1)){
-                    map.delete_at(&mut commands, (x, y));
-                } 
-
-                else if viscosity && map.insert_at::<Healing_SpringParticle>(&mut commands, (x+1, y-1), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x+1, y-1)) == ParticleElement::Water && !player_hb.is_particle_in_hitbox((x+1, y-1)) {
-                    map.delete_at(&mut commands, (x, y));
-                } 
-
-                else if viscosity && map.insert_at::<Healing_SpringParticle>(&mut commands, (x-1, y), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x-1, y)) == ParticleElement::Water && !player_hb.is_particle_in_hitbox((x-1, y)){
-                    map.delete_at(&mut commands, (x, y));
-                }
-
-                else if viscosity && map.insert_at::<Healing_SpringParticle>(&mut commands, (x+1, y), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } else if viscosity && map.get_element_at((x+1, y)) == ParticleElement::Water && !player_hb.is_particle_in_hitbox((x+1, y)) {
-                    map.delete_at(&mut commands, (x, y));
-                }
+    bodies.begin_step();
+    let mut levelled = HashSet::new();
+    for start in liquids {
+        let id = bodies.body_at(&map, start);
+        if !levelled.insert(id) {
+            continue;
+        }
+        let Some(body) = bodies.get_mut(id) else {
+            continue
+        };
+        let element = body.element;
+        let viscosity = registry.get(element).viscosity;
+        // the body might be a few steps old, the automata could have moved some of these since
+        let surface: Vec<(i32, i32)> = body.surface.iter()
+            .filter(|&&(x, y)| map.get_element_at((x, y)) == element && map.get_velocity_at((x, y)) == Vec2::ZERO
+                && map.get_element_at((x, y + 1)) == ParticleElement::Air)
+            .take(MAX_FLOW)
+            .copied()
+            .collect();
+        let outlets: Vec<((i32, i32), Vec2)> = body.outlets.iter()
+            .filter(|(to, _)| map.get_element_at(*to) == ParticleElement::Air)
+            .take(MAX_FLOW)
+            .copied()
+            .collect();
+        let mut moved = Vec::new();
+        for (from, (to, out)) in surface.into_iter().zip(outlets) {
+            let head = (from.1 - to.1) as f32;
+            if head <= 0. {
+                break;
+            }
+            if !rng.gen_bool(((head / FULL_FLOW_HEAD).sqrt() * viscosity).clamp(0., 1.) as f64) {
+                // it isn't level yet, so it can't fall asleep
+                map.keep_awake(from);
+                continue;
+            }
+            if !map.move_cell(from, to) {
+                continue;
+            }
+            let speed = (2. * gravity * head).sqrt() * JET_KEEP;
+            if speed >= SPLASH_MIN_SPEED {
+                map.give_velocity(to, out * speed);
+            }
+            moved.push((from, to));
+        }
+        for (from, to) in moved {
+            bodies.moved(&map, id, from, to);
         }
     }
 }
 
-fn update_quicksand(
+
+/* The reaction pass. Every pair of touching cells is looked up in the Reactions table once per frame:
+   each cell only checks the neighbours to its right and below it, the other half of its neighbours check it.
+   Nothing solid is made inside the player so they can't get stuck in i.e. obsidian.
+   Two cells that could react but didn't roll it this time stay awake until they do.
+*/
+fn update_reactions(
     mut map: ResMut<ParticleMap>,
-    time: Res<Time>, 
-    mut commands: Commands,
-    mut particles: Query<&mut ParticlePosVel, With<ParticleTagQuickSand>>,
-    grav_res: ResMut<GravityResource>,
-    player_hb_query: Query<& Hitbox, With<Player>>,
+    reactions: Res<Reactions>,
+    player_hb_query: Query<&Hitbox, With<Player>>,
+    mut heat_events: EventWriter<ReactionHeat>,
+    mut sim_rng: ResMut<SimulationRng>,
 ) {
-    let deltat = time.delta_seconds() ;
-
-    let player_hb: &Hitbox = player_hb_query.single();
-    let mut rng = rand::thread_rng();
-    for mut position in &mut particles {
-        if position.velocity.x != 0. && position.velocity.y != 0.{
-            let new_pos = ((position.grid_x as f32 + position.velocity.x) as i32, (position.grid_y as f32 + position.velocity.y) as i32);
-            position.velocity.y = Gravity::update_gravity(&position.velocity.y, &deltat, &grav_res);
+    let registry = map.shared_registry();
+    let positions = map.awake_positions_where(|particle| reacts_or_burns(&reactions, particle));
+    let heat = react(&mut *map, positions, &reactions, &registry, player_hb_query.get_single().ok(), sim_rng.stream("reactions"));
+    heat_events.send_batch(heat);
+}
 
-            let p = map.ray(&mut commands, (position.grid_x, position.grid_y), new_pos, ListType::OnlyAir);
-            if let Some(position_of_part) = p {
-                if position_of_part != new_pos{
-                    position.velocity = Vec2::splat(0.);
-                }
-                map.delete_at(&mut commands, (position.grid_x, position.grid_y));
-                map.insert_at::<QuickSandParticle>(&mut commands, position_of_part, ListType::OnlyAir);
-                map.give_velocity(&mut commands, position_of_part, Vec2::new(position.velocity.x, position.velocity.y), );
-            }
-        } else {
-            let mut rng = rand::thread_rng();
-            let viscosity = rng.gen::<f64>() < QUICKSAND_VISCOSITY as f64;
-                let (x, y) = (position.grid_x, position.grid_y);
-                if viscosity && map.insert_at::<QuickSandParticle>(&mut commands, (x, y-1), ListType::OnlyAir) {
-                    map.delete_at(&mut commands, (x, y));
-                }
+/* Fire. Anything burning hot (fire itself, lava, hellstone) sets the flammable cells around it alight, each one
+   with its own chance per frame (its flammability). The fuel turns into Fire, which cools down into Smoke
+   once it has burnt for a bit (see Fire in assets/default.elements.ron).
+*/
+const IGNITION_TEMPERATURE: f32 = 400.;
 
-                else if viscosity && map.insert_at::<QuickSandParticle>(&mut commands, (x-1, y-1), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } 
+// the cells react looks at, anything in the reaction list and anything hot enough to light things
+fn reacts_or_burns(reactions: &Reactions, particle: &Particle) -> bool {
+    reactions.reacts(particle.element) || particle.temperature >= IGNITION_TEMPERATURE
+}
 
-                else if viscosity && map.insert_at::<QuickSandParticle>(&mut commands, (x+1, y-1), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                } 
+fn spread_fire(map: &mut impl ParticleGrid, registry: &ElementRegistry, (x, y): (i32, i32), rng: &mut impl Rng) {
+    for neighbour in [(x+1, y), (x-1, y), (x, y+1), (x, y-1), (x+1, y+1), (x-1, y+1), (x+1, y-1), (x-1, y-1)] {
+        let flammability = registry.get(map.get_element_at(neighbour)).flammability;
+        if flammability <= 0. {
+            continue;
+        }
+        if rng.gen_bool(flammability.min(1.) as f64) {
+            map.delete_at(neighbour);
+            map.insert_at(ParticleElement::Fire, neighbour, ListType::All);
+        } else {
+            // try again next frame
+            map.keep_awake((x, y));
+        }
+    }
+}
 
-                else if viscosity && map.insert_at::<QuickSandParticle>(&mut commands, (x-1, y), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
-                }
-                 
-                else if viscosity && map.insert_at::<QuickSandParticle>(&mut commands, (x+1, y), ListType::OnlyAir){
-                    map.delete_at(&mut commands, (x, y));
+// returns the heat given off, the grid can't send events
+fn react(
+    map: &mut impl ParticleGrid,
+    positions: Vec<(i32, i32)>,
+    reactions: &Reactions,
+    registry: &ElementRegistry,
+    player_hb: Option<&Hitbox>,
+    rng: &mut impl Rng,
+) -> Vec<ReactionHeat> {
+    let mut heat = Vec::new();
+    for (x, y) in positions {
+        if map.get_temperature_at((x, y)).is_some_and(|temperature| temperature >= IGNITION_TEMPERATURE) {
+            spread_fire(map, registry, (x, y), rng);
+        }
+        let element = map.get_element_at((x, y));
+        for other_pos in [(x+1, y), (x-1, y-1), (x, y-1), (x+1, y-1)] {
+            let other = map.get_element_at(other_pos);
+            let possible = reactions.between(element, other);
+            if possible.is_empty() {
+                continue;
+            }
+            let Some(reaction) = possible.iter().find(|reaction| rng.gen_bool(reaction.chance)) else {
+                map.keep_awake((x, y));
+                map.keep_awake(other_pos);
+                continue;
+            };
+            let products = [((x, y), element, reaction.products.0), (other_pos, other, reaction.products.1)];
+            if products.iter().any(|(pos, _, product)| registry.get(*product).blocks_player && player_hb.is_some_and(|hb| hb.is_particle_in_hitbox(*pos))) {
+                map.keep_awake((x, y));
+                map.keep_awake(other_pos);
+                continue;
+            }
+            for (pos, before, product) in products {
+                if product != before {
+                    map.delete_at(pos);
+                    map.insert_at(product, pos, ListType::All);
                 }
+            }
+            if reaction.heat != 0. {
+                heat.push(ReactionHeat { position: (x, y), heat: reaction.heat });
+            }
+            break;
         }
     }
+    heat
+}
+
+
+/* The same two passes as update_reactions and update_particles, split into one job per awake chunk and run
+   on the compute task pool. Neighbouring chunks would fight over the cells along their border, so the chunks
+   are done in four phases like a checkerboard: every chunk in a phase is two chunks away from the others and
+   works on its own copy of the cells around it (a ChunkWindow), which is copied back once the phase is done.
+   Each chunk gets its own rng from the step's seed and its position, and the windows are copied back in the
+   same order every time, so the result doesn't depend on how many threads there are or which finishes first.
+   ParticleUpdateMode::Serial does the exact same jobs one after another on this thread, which has to come out
+   the same as the pool (see the tests at the bottom).
+*/
+const CHECKERBOARD_PHASES: [(i32, i32); 4] = [(0, 0), (1, 0), (0, 1), (1, 1)];
+
+// one chunk's job, it only sees its window
+fn update_window(mut window: ChunkWindow, mut rng: StdRng, reactions: &Reactions, step: &StepContext) -> (ChunkWindow, Vec<ReactionHeat>) {
+    let positions = window.positions_where(|particle| reacts_or_burns(reactions, particle));
+    let heat = react(&mut window, positions, reactions, step.registry, step.player_hb, &mut rng);
+    let positions = window.positions_where(|particle| step.registry.get(particle.element).class != ElementClass::Solid);
+    move_particles(&mut window, positions, step, &mut rng);
+    (window, heat)
 }
 
-fn update_gas(
+#[allow(clippy::too_many_arguments)]
+fn update_chunks(
     mut map: ResMut<ParticleMap>,
-    mut commands: Commands,
-    mut particles: Query<&mut ParticlePosVel, With<ParticleTagGas>>,
+    mode: Res<ParticleUpdateMode>,
+    reactions: Res<Reactions>,
+    timestep: Res<TimestepSettings>,
+    grav_res: Res<GravityResource>,
+    wind: Res<Wind>,
+    player_hb_query: Query<&Hitbox, With<Player>>,
+    mut heat_events: EventWriter<ReactionHeat>,
+    mut sim_rng: ResMut<SimulationRng>,
 ) {
-    for mut position in &mut particles {
-        let mut rng = rand::thread_rng();
-        let move_r = 2;
-        let decay_rate = 50;
-        // This decay logic just says if the positions 10 away in each cardinal direction is air theres a small chance to despawn.
-        // just means that 
-        //               1 to disable or just comment out
-        if rng.gen_range(0..decay_rate) == 0 {
-            if map.get_element_at((position.grid_x+10, position.grid_y)) == ParticleElement::Air &&
-               map.get_element_at((position.grid_x, position.grid_y+10)) == ParticleElement::Air &&
-               map.get_element_at((position.grid_x-10, position.grid_y)) == ParticleElement::Air &&
-               map.get_element_at((position.grid_x, position.grid_y-10)) == ParticleElement::Air {
-                map.delete_at(&mut commands, (position.grid_x, position.grid_y));
-               }
-        } else if rng.gen_range(0..=1) == 0{
-            let radius: i32 = rng.gen_range(1..=6);
-            let center_x = position.grid_x;
-            let center_y = position.grid_y;
-            
-
-            // We can use this to give certain elements different densities. some may float very quickly, some may disperse more, etc.
-            //70% chance to pick an upward angle, 30% chance for any angle
-            let angle = if rng.gen_bool(0.2) {
-                // Bias towards an upward angle (between π/4 and 3π/4)
-                rng.gen_range(std::f32::consts::FRAC_PI_4..=3.0*std::f32::consts::FRAC_PI_4)
-            } else {
-                rng.gen_range(0.0..=2.0 * std::f32::consts::PI)
-            };
-            // or just be random.
-            //let angle = rng.gen_range(0.0..=2.0 * std::f32::consts::PI);
+    let registry = map.shared_registry();
+    let step = StepContext {
+        registry: &registry,
+        player_hb: player_hb_query.get_single().ok(),
+        deltat: timestep.step_seconds(),
+        grav_res: &grav_res,
+        wind: &wind,
+    };
+    let reactions = &*reactions;
+    let step_seed = SimulationSeed(sim_rng.stream("chunks").gen());
+    let awake = map.awake_chunks().to_vec();
+    let pool = ComputeTaskPool::get_or_init(TaskPool::default);
 
-            let dx = (radius as f32 * angle.cos()).round() as i32;
-            let dy = (radius as f32 * angle.sin()).round() as i32;
-            let new_pos = (center_x + dx, center_y + dy);
-            
-            if let Some(position_of_part) = map.ray(&mut commands, (center_x, center_y), new_pos, ListType::Whitelist(vec!(ParticleElement::Gas, ParticleElement::Air))) {
-                if map.get_element_at(position_of_part) == ParticleElement::Air {
-                    map.delete_at(&mut commands, (center_x, center_y));
-                    // Check that the new coordinates are within bounds before spawning
-                    if grid_coords_within_map(position_of_part) {
-                        map.insert_at::<GasParticle>(&mut commands, position_of_part, ListType::OnlyAir);
-                    }
+    for phase in CHECKERBOARD_PHASES {
+        let windows: Vec<(ChunkWindow, StdRng)> = awake.iter()
+            .filter(|chunk| (chunk.0.rem_euclid(2), chunk.1.rem_euclid(2)) == phase)
+            .filter_map(|&chunk| {
+                let window = map.window(chunk, step_seed.chunk_rng("colours", chunk))?;
+                Some((window, step_seed.chunk_rng("particles", chunk)))
+            })
+            .collect();
+        if windows.is_empty() {
+            continue;
+        }
+        let step = &step;
+        let done = if *mode == ParticleUpdateMode::Parallel {
+            pool.scope(|scope| {
+                for (window, rng) in windows {
+                    scope.spawn(async move { update_window(window, rng, reactions, step) });
                 }
-            }
+            })
+        } else {
+            windows.into_iter().map(|(window, rng)| update_window(window, rng, reactions, step)).collect()
+        };
+        // scope hands them back in the order they were spawned in
+        for (window, heat) in done {
+            map.apply_window(window);
+            heat_events.send_batch(heat);
         }
     }
 }
 
 
 
-fn get_surrounding_toxic_gas_ratio(
-    mut map: &ResMut<ParticleMap>,
-    x: i32,
-    y: i32,
-) -> f32 {
-    let mut count: f32 = 1.0;
-    if (map.get_element_at((x-1, y-1)) == ParticleElement::ToxicGas){ count += 1.0; }
-    if (map.get_element_at((x-1, y  )) == ParticleElement::ToxicGas){ count += 1.0; }
-    if (map.get_element_at((x-1, y+1)) == ParticleElement::ToxicGas){ count += 1.0; }
-    if (map.get_element_at((x  , y-1)) == ParticleElement::ToxicGas){ count += 1.0; }
-    if (map.get_element_at((x  , y+1)) == ParticleElement::ToxicGas){ count += 1.0; }
-    if (map.get_element_at((x+1, y-1)) == ParticleElement::ToxicGas){ count += 1.0; }
-    if (map.get_element_at((x+1, y  )) == ParticleElement::ToxicGas){ count += 1.0; }
-    if (map.get_element_at((x+1, y+1)) == ParticleElement::ToxicGas){ count += 1.0; }
-    return (count / 8.0)
-}
-fn update_toxic_gas(
+/* Heat. Only chunks where something changed since last frame are looked at, a chunk where every
+   temperature has settled is skipped until a particle moves into it or a neighbour heats it up again.
+   Neighbouring particles even out their temperatures at the lower of their conductivities, and anything
+   touching air drifts towards the planet's AmbientTemperature. Past a threshold they melt, boil or freeze (or blow up).
+*/
+const HEAT_EPSILON: f32 = 0.01; // changes smaller than this don't wake the chunk up again
+
+fn update_temperature(
     mut map: ResMut<ParticleMap>,
-    mut commands: Commands,
-    mut particles: Query<&mut ParticlePosVel, With<ParticleTagToxicGas>>,
+    ambient: Res<AmbientTemperature>,
+    player_hb_query: Query<&Hitbox, With<Player>>,
 ) {
-    for mut position in &mut particles {
-        let mut rng = rand::thread_rng();
-        let move_r = 2;
-        let decay_rate = 50;
-        let acidity_rate = 4;
-        // This decay logic just says if the positions 10 away in each cardinal direction is air theres a small chance to despawn.
-        // just means that 
-        //               1 to disable or just comment out
-        if rng.gen_range(1..decay_rate) == 0 {
-            if map.get_element_at((position.grid_x+10, position.grid_y)) == ParticleElement::Air &&
-               map.get_element_at((position.grid_x, position.grid_y+10)) == ParticleElement::Air &&
-               map.get_element_at((position.grid_x-10, position.grid_y)) == ParticleElement::Air &&
-               map.get_element_at((position.grid_x, position.grid_y-10)) == ParticleElement::Air {
-                map.delete_at(&mut commands, (position.grid_x, position.grid_y));
-               }
-        } else if rng.gen_range(0..=1) == 0{
-            let radius: i32 = rng.gen_range(1..=3);
-            let center_x = position.grid_x;
-            let center_y = position.grid_y;
-            
+    let player_hb = player_hb_query.get_single().ok();
+    let registry = map.shared_registry();
+    let active = map.take_heat_active_chunks();
+    let active_set: HashSet<(i32, i32)> = active.iter().copied().collect();
+
+    for chunk in active {
+        let Some(cells) = map.chunk_cells(chunk) else {
+            continue
+        };
+        let conductive: Vec<(i32, i32)> = cells.iter().enumerate()
+            .filter(|(_, cell)| cell.is_some_and(|particle| {
+                let definition = registry.get(particle.element);
+                definition.conductivity > 0. || definition.air_exchange > 0.
+            }))
+            .map(|(index, _)| {
+                let index = index as i32;
+                (chunk.0 * CHUNK_SIZE + index % CHUNK_SIZE, chunk.1 * CHUNK_SIZE + index / CHUNK_SIZE)
+            })
+            .collect();
 
-            // We can use this to give certain elements different densities. some may float very quickly, some may disperse more, etc.
-            //70% chance to pick an upward angle, 30% chance for any angle
-            let angle = if rng.gen_bool(0.4) {
-                // Bias towards an upward angle (between π/4 and 3π/4)
-                rng.gen_range(-3.0*std::f32::consts::FRAC_PI_4..=-1.0*std::f32::consts::FRAC_PI_4)
-            } else {
-                rng.gen_range(0.0..=2.0 * std::f32::consts::PI)
+        for (x, y) in conductive {
+            let Some(particle) = map.get_particle_at((x, y)) else {
+                continue
             };
-            // or just be random.
-            //let angle = rng.gen_range(0.0..=2.0 * std::f32::consts::PI);
+            let definition = registry.get(particle.element);
+            let mut temperature = particle.temperature;
 
-            let dx = (radius as f32 * angle.cos()).round() as i32;
-            let dy = (radius as f32 * angle.sin()).round() as i32;
-            let new_pos = (center_x + dx, center_y + dy);
+            // each pair of neighbours is evened out once: every cell does the ones to its right and above,
+            // and the ones left and below if they're in a chunk that isn't being updated this frame
+            let mut touches_air = false;
+            for (neighbour, always) in [((x+1, y), true), ((x, y+1), true), ((x-1, y), false), ((x, y-1), false)] {
+                let Some(other) = map.get_particle_at(neighbour) else {
+                    touches_air = true;
+                    continue
+                };
+                if !always && active_set.contains(&chunk_of(neighbour).0) {
+                    continue
+                }
+                let conductivity = definition.conductivity.min(registry.get(other.element).conductivity);
+                if conductivity <= 0. {
+                    continue
+                }
+                let flow = conductivity * (other.temperature - temperature) / 2.;
+                if flow.abs() > HEAT_EPSILON {
+                    temperature += flow;
+                    map.set_temperature_at(neighbour, other.temperature - flow);
+                }
+            }
+            if touches_air {
+                temperature += definition.air_exchange * (ambient.0 - temperature);
+            }
+            if (temperature - particle.temperature).abs() > HEAT_EPSILON {
+                map.set_temperature_at((x, y), temperature);
+            }
 
-            let dx2 = ((radius + 1) as f32 * angle.cos()).round() as i32;
-            let dy2 = ((radius + 1) as f32 * angle.sin()).round() as i32;
-            let new_pos2 = (center_x + dx2, center_y + dy2);
-            
-            if let Some(position_of_part) = map.ray(&mut commands, (center_x, center_y), new_pos, ListType::Whitelist(vec!(ParticleElement::ToxicGas, ParticleElement::Air, ParticleElement::Stone))) {
-                if map.get_element_at(position_of_part) == ParticleElement::Air {
-                    map.delete_at(&mut commands, (center_x, center_y));
-                    // Check that the new coordinates are within bounds before spawning
-
-                    if let Some(delete_pos) = map.ray(&mut commands, (center_x, center_y), new_pos2, ListType::Blacklist(vec!(ParticleElement::Stone, ParticleElement::Water))){
-                        let ratio_of_surrounding_toxic_gas = get_surrounding_toxic_gas_ratio(&map, center_x, center_y);
-                        if (map.get_element_at(delete_pos) == ParticleElement::Stone && rng.gen_range(0..(acidity_rate * (ratio_of_surrounding_toxic_gas) as i32  + 1)) == 0){ 
-                            map.delete_at(&mut commands, delete_pos);
-                        }
-                    }
-                    if (map.get_element_at((position_of_part.0, position_of_part.1 - 1)) == ParticleElement::Stone || map.get_element_at((position_of_part.0, position_of_part.1 - 1)) == ParticleElement::Water){
-                        if (rng.gen_range(0..(acidity_rate*acidity_rate)) == 0){
-                            map.delete_at(&mut commands, (position_of_part.0, position_of_part.1 - 1));
-                        }
-                    }
-                    if grid_coords_within_map(position_of_part) {
-                        if map.insert_at::<ToxicGasParticle>(&mut commands, position_of_part, ListType::Whitelist(vec![ParticleElement::Stone, ParticleElement::Water, ParticleElement::Air])){
-                            /*if let Some(delete_position) = map.ray(&mut commands, (center_x, center_y), new_pos, ListType::Blacklist(vec!(ParticleElement::Stone))){
-                                map.delete_at(&mut commands, delete_position);
-                            }*/
-                        }
-                    }
+            if definition.explodes.is_some_and(|explosion| temperature > explosion.at) {
+                map.ignite((x, y));
+                continue;
+            }
+            if let Some(into) = registry.phase_change(particle.element, temperature) {
+                // don't freeze anything solid around the player
+                if registry.get(into).blocks_player && player_hb.is_some_and(|hb| hb.is_particle_in_hitbox((x, y))) {
+                    continue
                 }
+                map.change_element_at((x, y), into);
             }
         }
     }
 }
-fn update_slime(
+
+// the heat a reaction gives off goes into whatever is around it
+fn apply_reaction_heat(
     mut map: ResMut<ParticleMap>,
-    time: Res<Time>, 
-    mut commands: Commands,
-    mut particles: Query<&mut ParticlePosVel, With<ParticleTagSlime>>,
-    grav_res: ResMut<GravityResource>,
-    player_hb_query: Query<&Hitbox, With<Player>>,
+    mut heat_events: EventReader<ReactionHeat>,
 ) {
-    let deltat = time.delta_seconds();
-
-    let player_hb: &Hitbox = player_hb_query.single();
-    let mut rng = rand::thread_rng();
-    for mut position in &mut particles {
-        if position.velocity.x != 0. && position.velocity.y != 0. {
-            let new_pos = (
-                (position.grid_x as f32 + position.velocity.x) as i32, 
-                (position.grid_y as f32 + position.velocity.y) as i32
-            );
-            position.velocity.y = Gravity::update_gravity(&position.velocity.y, &deltat, &grav_res);
-
-            let p = map.ray(&mut commands, (position.grid_x, position.grid_y), new_pos, ListType::OnlyAir);
-            if let Some(position_of_part) = p {
-                if position_of_part != new_pos {
-                    position.velocity = Vec2::splat(0.);
-                }
-                map.delete_at(&mut commands, (position.grid_x, position.grid_y));
-                map.insert_at::<SlimeParticle>(&mut commands, position_of_part, ListType::OnlyAir);
-                map.give_velocity(
-                    &mut commands, 
-                    position_of_part, 
-                    Vec2::new(position.velocity.x, position.velocity.y),
-                );
+    for event in heat_events.read() {
+        let (x, y) = event.position;
+        let around: Vec<(i32, i32)> = (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+            .filter(|pos| map.get_particle_at(*pos).is_some())
+            .collect();
+        for pos in around.iter() {
+            if let Some(temperature) = map.get_temperature_at(*pos) {
+                map.set_temperature_at(*pos, temperature + event.heat / around.len() as f32);
             }
-        } else {
-            let viscosity = rng.gen::<f64>() < SLIME_VISCOSITY as f64;
-            let (x, y) = (position.grid_x, position.grid_y);
+        }
+    }
+}
 
-            if viscosity && map.insert_at::<SlimeParticle>(&mut commands, (x, y - 1), ListType::OnlyAir) {
-                map.delete_at(&mut commands, (x, y));
-            } 
-            
-            else if viscosity && map.insert_at::<SlimeParticle>(&mut commands, (x - 1, y - 1), ListType::OnlyAir) {
-                map.delete_at(&mut commands, (x, y));
-            } 
-            
-            else if viscosity && map.insert_at::<SlimeParticle>(&mut commands, (x + 1, y - 1), ListType::OnlyAir) {
-                map.delete_at(&mut commands, (x, y));
-            } 
-            
-            else if viscosity && map.insert_at::<SlimeParticle>(&mut commands, (x - 1, y), ListType::OnlyAir) {
-                map.delete_at(&mut commands, (x, y));
+
+/* Blasts (see ParticleMap::explode) hurt the player and enemies they reach, more the closer they are to
+   the middle. Every cell of a gas cloud blows up on its own, so a big cloud hurts a lot more than a small one.
+*/
+const BLAST_DAMAGE: f32 = 2.; // damage per point of power in the middle of a blast
+
+fn damage_from_blasts(
+    mut map: ResMut<ParticleMap>,
+    mut death_event: EventWriter<Death>,
+    mut players: Query<(&Hitbox, &mut Health), With<Player>>,
+    mut enemies: Query<(&Hitbox, &mut EnemyHealth), With<Enemy>>,
+) {
+    for blast in map.take_blasts() {
+        let center = (Vec2::new(blast.center.0 as f32, blast.center.1 as f32) + 0.5) * PARTICLE_SIZE;
+        let reach = (blast.radius + 1) as f32 * PARTICLE_SIZE;
+        let damage = |hitbox: &Hitbox| blast.power * BLAST_DAMAGE * (1. - hitbox.offset.distance(center) / reach).max(0.);
+        for (hitbox, mut health) in players.iter_mut() {
+            let damage = damage(hitbox);
+            if damage > 0. {
+                health.take_damage(damage);
+                if health.current == 0. {
+                    death_event.send(Death);
+                }
             }
-            
-            else if viscosity && map.insert_at::<SlimeParticle>(&mut commands, (x + 1, y), ListType::OnlyAir) {
-                map.delete_at(&mut commands, (x, y));
+        }
+        for (hitbox, mut health) in enemies.iter_mut() {
+            health.take_damage(damage(hitbox));
+        }
+    }
+}
+
+
+// Electricity, see ChargeField. It spreads on the tick so it takes a few ticks to run through a lake
+const CHARGE_DAMAGE: f32 = 0.5; // per tick while touching a charged cell
+
+fn spread_charge(map: Res<ParticleMap>, mut charge: ResMut<ChargeField>) {
+    charge.update(&map);
+}
+
+fn damage_from_charge(
+    charge: Res<ChargeField>,
+    mut death_event: EventWriter<Death>,
+    mut players: Query<(&Hitbox, &mut Health), With<Player>>,
+    mut enemies: Query<(&Hitbox, &mut EnemyHealth), With<Enemy>>,
+) {
+    for (hitbox, mut health) in players.iter_mut() {
+        if hitbox.is_touching_charge(&charge) {
+            health.take_damage(CHARGE_DAMAGE);
+            if health.current == 0. {
+                death_event.send(Death);
             }
         }
     }
+    for (hitbox, mut health) in enemies.iter_mut() {
+        if hitbox.is_touching_charge(&charge) {
+            health.take_damage(CHARGE_DAMAGE);
+        }
+    }
+}
+
+fn draw_charge(charge: Res<ChargeField>, mut gizmos: Gizmos) {
+    for (x, y) in charge.charged_cells() {
+        let center = Vec2::new(*x as f32 + 0.5, *y as f32 + 0.5) * PARTICLE_SIZE;
+        gizmos.rect_2d(center, 0., Vec2::splat(PARTICLE_SIZE * 0.6), Color::srgb(1., 0.95, 0.3));
+    }
 }
 
 
@@ -510,7 +804,6 @@ fn update_slime(
 // Player interaction functions -------------------------------------------------------------------------------
 pub fn build_or_destroy(
     mut map: ResMut<ParticleMap>,
-    mut commands: Commands,
     window_query: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform), With<Camera>>,
     buttons: Res<ButtonInput<MouseButton>>,
@@ -532,10 +825,10 @@ pub fn build_or_destroy(
                 while x < size * PARTICLE_SIZE + 0.1{
                     let position = (((world_position.x+x) / PARTICLE_SIZE) as i32, ((world_position.y+y) / PARTICLE_SIZE) as i32);
                     if l {
-                        map.delete_at(&mut commands, (((world_position.x+x) / PARTICLE_SIZE) as i32, ((world_position.y+y) / PARTICLE_SIZE) as i32));
+                        map.delete_at((((world_position.x+x) / PARTICLE_SIZE) as i32, ((world_position.y+y) / PARTICLE_SIZE) as i32));
                     }
                     if r{
-                        map.insert_at::<GasParticle>(&mut commands, (position.0, position.1), ListType::Blacklist(vec![ParticleElement::Air, ParticleElement::Stone]));
+                        map.insert_at(ParticleElement::Gas, (position.0, position.1), ListType::Blacklist(vec![ParticleElement::Air, ParticleElement::Stone]));
                     }
                     x += PARTICLE_SIZE;
                 }
@@ -547,7 +840,6 @@ pub fn build_or_destroy(
 
 pub fn paint_with_ray(
     mut map: ResMut<ParticleMap>,
-    mut commands: Commands,
     window_query: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform), With<Camera>>,
     buttons: Res<ButtonInput<MouseButton>>,
@@ -564,20 +856,312 @@ pub fn paint_with_ray(
                 .map(|ray| ray.origin.truncate())
         {
             
-            let p = map.ray(&mut commands, 
+            let p = map.ray(
                 convert_to_grid_position(pt.translation.x , pt.translation.y), 
                 convert_to_grid_position(world_position.x, world_position.y), 
                 ListType::Whitelist(vec!(ParticleElement::Dirt)));
                 // a little weird here ^ might need to experiment w white/black lists to see exactly how they work
             if let Some(position_of_part) = p {
-                map.insert_at::<BedRockParticle>(&mut commands, position_of_part, ListType::All);
+                map.insert_at(ParticleElement::BedRock, position_of_part, ListType::All);
+            }
+        }
+    }
+}
+
+
+
+
+
+
+// Elements and reactions ------------------------------------------------------------------------------------
+fn load_particle_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.insert_resource(ElementRegistryHandle(asset_server.load(ELEMENTS_ASSET)));
+    commands.insert_resource(ReactionListHandle(asset_server.load(REACTIONS_ASSET)));
+}
+
+// the map starts out with the compiled in copy of the element list, this swaps in the one from the assets folder
+fn apply_element_registry(
+    mut map: ResMut<ParticleMap>,
+    mut reactions: ResMut<Reactions>,
+    mut events: EventReader<AssetEvent<ElementRegistry>>,
+    handle: Option<Res<ElementRegistryHandle>>,
+    registries: Res<Assets<ElementRegistry>>,
+) {
+    let Some(handle) = handle else {
+        return
+    };
+    for event in events.read() {
+        match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } if *id == handle.0.id() => {
+                if let Some(registry) = registries.get(*id) {
+                    map.set_registry(registry.clone());
+                    // the names in the reaction list might point at different elements now
+                    reactions.resolve(registry);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// same as apply_element_registry, for assets/default.reactions.ron
+fn apply_reaction_list(
+    map: Res<ParticleMap>,
+    mut reactions: ResMut<Reactions>,
+    mut events: EventReader<AssetEvent<ReactionList>>,
+    handle: Option<Res<ReactionListHandle>>,
+    lists: Res<Assets<ReactionList>>,
+) {
+    let Some(handle) = handle else {
+        return
+    };
+    for event in events.read() {
+        match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } if *id == handle.0.id() => {
+                if let Some(list) = lists.get(*id) {
+                    reactions.set_list(list.clone(), map.registry());
+                }
             }
+            _ => {}
         }
     }
 }
 
 
 
+// Rendering -------------------------------------------------------------------------------------------------
+fn chunk_image(data: Vec<u8>) -> Image {
+    let mut image = Image::new(
+        Extent3d { width: CHUNK_SIZE as u32, height: CHUNK_SIZE as u32, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+// world position of the middle of a chunk, where its sprites go
+fn chunk_center(chunk: (i32, i32)) -> Vec2 {
+    Vec2::new(
+        (chunk.0 * CHUNK_SIZE) as f32 * PARTICLE_SIZE + CHUNK_SIZE as f32 * PARTICLE_SIZE / 2.,
+        (chunk.1 * CHUNK_SIZE) as f32 * PARTICLE_SIZE + CHUNK_SIZE as f32 * PARTICLE_SIZE / 2.,
+    )
+}
+
+/* Draws the ParticleMap. Each chunk is a few CHUNK_SIZE x CHUNK_SIZE textures (one per z_layer in the ElementRegistry),
+   and only the chunks the map marked dirty since last frame get their pixels rewritten.
+   Without Assets<Image> (headless) the changes are just thrown away so the map can run on its own.
+*/
+fn render_particles(
+    mut commands: Commands,
+    mut map: ResMut<ParticleMap>,
+    images: Option<ResMut<Assets<Image>>>,
+    mut chunk_images: ResMut<ParticleChunkImages>,
+) {
+    if map.take_cleared() {
+        // clear_level already despawned the sprites
+        chunk_images.chunks.clear();
+    }
+    for chunk in map.take_removed_chunks() {
+        if let Some(layers) = chunk_images.chunks.remove(&chunk) {
+            for (entity, _) in layers {
+                if let Some(mut entity) = commands.get_entity(entity) {
+                    entity.despawn();
+                }
+            }
+        }
+    }
+    // a new element list can add or remove layers, so every chunk gets new sprites
+    let layers = map.registry().layers();
+    if layers != chunk_images.layers {
+        for (_, chunk_layers) in chunk_images.chunks.drain() {
+            for (entity, _) in chunk_layers {
+                if let Some(mut entity) = commands.get_entity(entity) {
+                    entity.despawn();
+                }
+            }
+        }
+        chunk_images.layers = layers;
+        map.mark_all_dirty();
+    }
+    let dirty = map.take_dirty_chunks();
+    let Some(mut images) = images else {
+        return
+    };
+
+    for chunk in dirty {
+        let Some(cells) = map.chunk_cells(chunk) else {
+            continue
+        };
+        let mut pixels = vec![vec![0u8; cells.len() * 4]; chunk_images.layers.len()];
+        for (index, cell) in cells.iter().enumerate() {
+            if let Some(particle) = cell {
+                // textures go top to bottom, the map goes bottom to top
+                let (lx, ly) = (index as i32 % CHUNK_SIZE, index as i32 / CHUNK_SIZE);
+                let pixel = ((CHUNK_SIZE - 1 - ly) * CHUNK_SIZE + lx) as usize * 4;
+                let z = map.registry().get(particle.element).z_layer;
+                let layer = chunk_images.layers.iter().position(|l| *l == z).unwrap_or(0);
+                pixels[layer][pixel..pixel + 4].copy_from_slice(&particle.color);
+            }
+        }
+
+        if let Some(layers) = chunk_images.chunks.get(&chunk) {
+            for ((_, handle), data) in layers.iter().zip(pixels) {
+                if let Some(image) = images.get_mut(handle) {
+                    image.data = data;
+                }
+            }
+            continue
+        }
+
+        let mut layers = Vec::new();
+        for (layer, data) in pixels.into_iter().enumerate() {
+            let handle = images.add(chunk_image(data));
+            let entity = commands.spawn((
+                SpriteBundle {
+                    texture: handle.clone(),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(CHUNK_SIZE as f32 * PARTICLE_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(chunk_center(chunk).extend(chunk_images.layers[layer])),
+                    ..default()
+                },
+                ParticleChunkSprite { chunk, layer },
+            )).id();
+            layers.push((entity, handle));
+        }
+        chunk_images.chunks.insert(chunk, layers);
+    }
+}
+
+
+
+
+
+/* Debug heat map. While it's on every loaded chunk gets a texture over everything else, blue where
+   it's cold, red and then yellow where it's hot. It's redrawn every frame, so it's slow on big maps.
+*/
+const HEAT_OVERLAY_Z: f32 = 950.;
+const HEAT_COLORS: [(f32, [u8; 3]); 5] = [
+    (-40., [40, 60, 255]),
+    (0., [120, 200, 255]),
+    (20., [40, 200, 60]),
+    (300., [230, 40, 20]),
+    (1200., [255, 240, 80]),
+];
+
+fn heat_color(temperature: f32) -> [u8; 4] {
+    let mut rgb = HEAT_COLORS[HEAT_COLORS.len() - 1].1;
+    for pair in HEAT_COLORS.windows(2) {
+        let ((from_t, from), (to_t, to)) = (pair[0], pair[1]);
+        if temperature <= to_t {
+            let f = ((temperature - from_t) / (to_t - from_t)).clamp(0., 1.);
+            rgb = [0, 1, 2].map(|c| (from[c] as f32 + (to[c] as f32 - from[c] as f32) * f) as u8);
+            break;
+        }
+    }
+    [rgb[0], rgb[1], rgb[2], 180]
+}
+
+// wakes up whatever was touched last step, see ParticleMap::begin_step
+fn begin_step(mut map: ResMut<ParticleMap>) {
+    map.begin_step();
+}
+
+fn toggle_heat_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<HeatOverlay>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+fn toggle_active_region_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<ActiveRegionOverlay>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+fn draw_active_regions(
+    map: Res<ParticleMap>,
+    overlay: Res<ActiveRegionOverlay>,
+    mut gizmos: Gizmos,
+) {
+    if !overlay.enabled {
+        return
+    }
+    // cell (x, y) is drawn from x*PARTICLE_SIZE to (x+1)*PARTICLE_SIZE, same as in chunk_center
+    for region in map.awake_regions() {
+        let min = Vec2::new(region.min.0 as f32, region.min.1 as f32);
+        let max = Vec2::new(region.max.0 as f32 + 1., region.max.1 as f32 + 1.);
+        gizmos.rect_2d((min + max) / 2. * PARTICLE_SIZE, 0., (max - min) * PARTICLE_SIZE, Color::srgb(0.2, 1., 0.3));
+    }
+}
+
+fn render_heat_overlay(
+    mut commands: Commands,
+    map: Res<ParticleMap>,
+    images: Option<ResMut<Assets<Image>>>,
+    mut overlay: ResMut<HeatOverlay>,
+) {
+    let loaded: HashSet<(i32, i32)> = if overlay.enabled { map.loaded_chunks().into_iter().collect() } else { HashSet::new() };
+    let gone: Vec<(i32, i32)> = overlay.chunks.keys().filter(|chunk| !loaded.contains(*chunk)).copied().collect();
+    for chunk in gone {
+        if let Some((entity, _)) = overlay.chunks.remove(&chunk) {
+            if let Some(mut entity) = commands.get_entity(entity) {
+                entity.despawn();
+            }
+        }
+    }
+    let Some(mut images) = images else {
+        return
+    };
+
+    for chunk in loaded {
+        let Some(cells) = map.chunk_cells(chunk) else {
+            continue
+        };
+        let mut data = vec![0u8; cells.len() * 4];
+        for (index, cell) in cells.iter().enumerate() {
+            if let Some(particle) = cell {
+                let (lx, ly) = (index as i32 % CHUNK_SIZE, index as i32 / CHUNK_SIZE);
+                let pixel = ((CHUNK_SIZE - 1 - ly) * CHUNK_SIZE + lx) as usize * 4;
+                data[pixel..pixel + 4].copy_from_slice(&heat_color(particle.temperature));
+            }
+        }
+
+        if let Some((_, handle)) = overlay.chunks.get(&chunk) {
+            if let Some(image) = images.get_mut(handle) {
+                image.data = data;
+            }
+            continue
+        }
+        let handle = images.add(chunk_image(data));
+        let entity = commands.spawn((
+            SpriteBundle {
+                texture: handle.clone(),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(CHUNK_SIZE as f32 * PARTICLE_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(chunk_center(chunk).extend(HEAT_OVERLAY_Z)),
+                ..default()
+            },
+            HeatOverlaySprite,
+        )).id();
+        overlay.chunks.insert(chunk, (entity, handle));
+    }
+}
 
 
 
@@ -585,25 +1169,173 @@ pub struct ParticlePlugin;
 impl Plugin for ParticlePlugin {
     fn build(&self, app: &mut App) {
         // Startup placements
-        app.insert_resource(ParticleMap::new());
+        let map = ParticleMap::new();
+        app.insert_resource(Reactions::new(ReactionList::builtin(), map.registry()));
+        app.insert_resource(map);
+        app.init_resource::<ParticleChunkImages>();
+        app.init_resource::<AmbientTemperature>();
+        app.init_resource::<HeatOverlay>();
+        app.init_resource::<ActiveRegionOverlay>();
+        app.add_event::<ReactionHeat>();
 
-        // Updates i.e. all automata goes here
+        // Updates i.e. all automata goes here. They run on the fixed tick, see common/timestep.rs
+        app.init_resource::<TimestepSettings>();
+        // calm unless the WindPlugin is added too
+        app.init_resource::<Wind>();
         //app.add_systems(Update, draw_rain);
-        app.add_systems(Update, update_water.after(crate::entities::player::blaster::systems::shoot_blaster)
-                        .run_if(in_state(AppState::InGame)));
-        app.add_systems(Update, update_gas
-                        .run_if(in_state(AppState::InGame)));
-        app.add_systems(Update, update_lava.after(update_water)
-                        .run_if(in_state(AppState::InGame)));
-        app.add_systems(Update, update_toxic_gas.after(update_water)
-                        .run_if(in_state(AppState::InGame)));
-        app.add_systems(Update, update_healing_spring.after(update_lava)
-                        .run_if(in_state(AppState::InGame)));
-        app.add_systems(Update, update_quicksand
-                      .run_if(in_state(AppState::InGame)));
-        app.add_systems(Update, update_slime
-                        .run_if(in_state(AppState::InGame)));
-        //app.add_systems(Update, paint_with_ray.after(update_water));
-        //app.add_systems(Update, build_or_destroy.after(update_water));
+        app.init_resource::<ParticleUpdateMode>();
+        app.add_systems(SimulationStep, begin_step);
+        app.add_systems(SimulationStep, (
+            update_chunks.run_if(not(resource_equals(ParticleUpdateMode::WholeMap))),
+            (update_reactions, update_particles).chain().run_if(resource_equals(ParticleUpdateMode::WholeMap)),
+        ).after(begin_step).before(apply_reaction_heat));
+        app.add_systems(SimulationStep, level_liquids.after(update_chunks).after(update_particles).before(apply_reaction_heat));
+        app.add_systems(SimulationStep, update_temperature.after(apply_reaction_heat));
+        app.add_event::<Death>();
+        app.add_systems(FixedUpdate, damage_from_blasts.after(run_simulation_steps).run_if(in_state(AppState::InGame)));
+        app.init_resource::<LiquidBodies>();
+        app.init_resource::<ChargeField>();
+        app.add_systems(FixedUpdate, (spread_charge, damage_from_charge).chain().after(run_simulation_steps).run_if(in_state(AppState::InGame)));
+        // Drawing, after everything in Update has changed the map
+        app.add_systems(PostUpdate, (render_particles, render_heat_overlay));
+        //app.add_systems(Update, paint_with_ray.after(update_particles));
+        //app.add_systems(Update, build_or_destroy.after(update_particles));
+    }
+
+    // the asset server only exists once DefaultPlugins is added, which main does after this plugin.
+    // without one (headless) the map just keeps the compiled in element and reaction lists
+    fn finish(&self, app: &mut App) {
+        if app.world().contains_resource::<AssetServer>() {
+            app.init_asset::<ElementRegistry>()
+                .init_asset_loader::<ElementRegistryLoader>()
+                .init_asset::<ReactionList>()
+                .init_asset_loader::<ReactionListLoader>()
+                .add_systems(Startup, load_particle_assets)
+                .add_systems(Update, (apply_element_registry, apply_reaction_list).chain());
+        }
+        if app.world().contains_resource::<ButtonInput<KeyCode>>() {
+            app.add_systems(Update, (toggle_heat_overlay, toggle_active_region_overlay));
+        }
+        if app.world().contains_resource::<GizmoConfigStore>() {
+            app.add_systems(Update, (draw_active_regions, draw_charge));
+        }
     }
 } 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::state::GamePhase;
+    use crate::planets::definition::Planets;
+    use crate::worldgen::WorldGen;
+
+    // just the map and the renderer, no window or gpu
+    fn render_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()));
+        app.init_asset::<Image>();
+        app.add_plugins(ParticlePlugin);
+        app.finish();
+        app
+    }
+
+    // the pixel a cell of chunk (0, 0) ends up on, in the texture of its z_layer
+    fn pixel(app: &App, pos: (i32, i32)) -> [u8; 4] {
+        let map = app.world().resource::<ParticleMap>();
+        let chunk_images = app.world().resource::<ParticleChunkImages>();
+        let images = app.world().resource::<Assets<Image>>();
+        let element = map.get_particle_at(pos).map_or(ParticleElement::Sand, |particle| particle.element);
+        let z = map.registry().get(element).z_layer;
+        let layer = chunk_images.layers.iter().position(|l| *l == z).unwrap();
+        let (_, handle) = &chunk_images.chunks[&(0, 0)][layer];
+        let index = ((CHUNK_SIZE - 1 - pos.1) * CHUNK_SIZE + pos.0) as usize * 4;
+        images.get(handle).unwrap().data[index..index + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn dirty_chunks_get_the_particle_colors() {
+        let mut app = render_app();
+        let sand: Vec<(i32, i32)> = (0..CHUNK_SIZE).map(|x| (x, 3)).collect();
+        {
+            let mut map = app.world_mut().resource_mut::<ParticleMap>();
+            for &pos in &sand {
+                map.insert_at(ParticleElement::Sand, pos, ListType::All);
+            }
+            map.insert_at(ParticleElement::Water, (5, 10), ListType::All);
+        }
+        app.update();
+
+        let colors: Vec<[u8; 4]> = {
+            let map = app.world().resource::<ParticleMap>();
+            sand.iter().map(|pos| map.get_particle_at(*pos).unwrap().color).collect()
+        };
+        // every grain keeps its own jittered color instead of one color per element
+        assert!(colors.iter().any(|color| *color != colors[0]), "sand has no color jitter");
+        for (pos, color) in sand.iter().zip(&colors) {
+            assert_eq!(pixel(&app, *pos), *color, "wrong pixel at {pos:?}");
+        }
+        let water = app.world().resource::<ParticleMap>().get_particle_at((5, 10)).unwrap().color;
+        assert_eq!(pixel(&app, (5, 10)), water);
+        assert_eq!(pixel(&app, (20, 20)), [0; 4]);
+        let sprites = app.world_mut().query::<&ParticleChunkSprite>().iter(app.world()).count();
+        assert_eq!(sprites, app.world().resource::<ParticleChunkImages>().layers.len());
+
+        // changing a cell redraws the chunk into the same images
+        let handles = app.world().resource::<ParticleChunkImages>().chunks[&(0, 0)].clone();
+        app.world_mut().resource_mut::<ParticleMap>().delete_at(sand[7]);
+        app.world_mut().resource_mut::<ParticleMap>().insert_at(ParticleElement::Sand, (30, 40), ListType::All);
+        app.update();
+        assert_eq!(app.world().resource::<ParticleChunkImages>().chunks[&(0, 0)], handles);
+        assert_eq!(pixel(&app, sand[7]), [0; 4]);
+        let new = app.world().resource::<ParticleMap>().get_particle_at((30, 40)).unwrap().color;
+        assert_eq!(pixel(&app, (30, 40)), new);
+        assert_eq!(pixel(&app, sand[8]), colors[8]);
+    }
+
+    // planet 1 with water and sand poured over it, like benches/particle_update.rs
+    fn planet_1(mode: ParticleUpdateMode, steps: usize) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, ParticlePlugin));
+        app.insert_resource(mode);
+        app.insert_resource(SimulationRng::new(SimulationSeed(1)));
+        app.insert_resource(GravityResource::new(3600., 750.));
+        let mut map = app.world_mut().resource_mut::<ParticleMap>();
+        map.reseed(1);
+        let worldgen = WorldGen::new(Planets::builtin().get(GamePhase(0)).terrain.clone(), &SimulationSeed(1), map.registry());
+        worldgen.generate_level(&mut map, &SimulationSeed(1));
+        for x in MIN_X..MAX_X {
+            for y in MAX_Y - 40..MAX_Y {
+                let element = if (x / 16) % 2 == 0 { ParticleElement::Water } else { ParticleElement::Sand };
+                map.insert_at(element, (x, y), ListType::OnlyAir);
+            }
+        }
+        for _ in 0..steps {
+            app.world_mut().run_schedule(SimulationStep);
+        }
+        app
+    }
+
+    // position, element, colour, velocity, temperature and sediment, floats as bits so NaNs would still compare
+    type Cell = ((i32, i32), u16, [u8; 4], [u32; 2], u32, u16);
+
+    fn cells(app: &App) -> Vec<Cell> {
+        let map = app.world().resource::<ParticleMap>();
+        let mut cells: Vec<_> = map.positions_where(|_| true).into_iter().map(|pos| {
+            let particle = map.get_particle_at(pos).unwrap();
+            let velocity = [particle.velocity.x.to_bits(), particle.velocity.y.to_bits()];
+            (pos, particle.element.0, particle.color, velocity, particle.temperature.to_bits(), particle.sediment.0)
+        }).collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn serial_and_parallel_come_out_the_same() {
+        const STEPS: usize = 60;
+        let before = cells(&planet_1(ParticleUpdateMode::Parallel, 0));
+        let parallel = cells(&planet_1(ParticleUpdateMode::Parallel, STEPS));
+        assert_ne!(before, parallel, "nothing moved, the test isn't testing anything");
+        assert!(parallel == cells(&planet_1(ParticleUpdateMode::Parallel, STEPS)), "two parallel runs differ");
+        assert!(parallel == cells(&planet_1(ParticleUpdateMode::Serial, STEPS)), "serial and parallel differ");
+    }
+}
@@ -1,12 +1,17 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, PartialEq)]
+// Serialize/Deserialize for the save file
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum BlasterType {
     Water,
     Deleter,
     Gas,
     Lava,
     Healing_Spring,
+    Explosive,
+    Shock,
+    Copper,
 }
 
 #[derive(Component)]
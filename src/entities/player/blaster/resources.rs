@@ -8,6 +8,8 @@ pub const BLASTER_POWER: f32 = 10.;
 
 // Higher recharge, slower rate of fire
 pub const RECHARGE_RATE: f32 = 0.05;
+// the shock blaster fires a lot slower, every shot runs through the whole lake
+pub const SHOCK_RECHARGE_RATE: f32 = 0.5;
 pub const BLASTER_DISPLACEMENT: f32 = 0.; //Distance from blaster to particle being spawned (made so that we dont detect it for splashing)
 
 #[derive(Component, Resource)]
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 use crate::{
     //common::hitbox::Hitbox, 
+    common::timestep::Interpolated,
     //particle::resources::*,
     entities::enemy::components::Enemy, 
     entities::player::components::Player,
@@ -47,6 +48,7 @@ pub fn initialize(
         },
         BlasterVector::new(),
         BlasterLastFiredTime::new(),
+        Interpolated::new(),
         Blaster,)
     );
 }
@@ -80,21 +82,26 @@ pub fn update_blaster_aim( //this gets window cursor position, not world positio
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn shoot_blaster(
     mut map: ResMut<ParticleMap>,
     time: Res<Time>,
-    mut commands: Commands,
     window_query: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform), With<Camera>>,
     buttons: Res<ButtonInput<MouseButton>>,
     mut q_blaster: Query<(&Transform, &BlasterVector, &mut BlasterLastFiredTime), (With<Blaster>, Without<Enemy>, Without<Player>)>,
     blaster_selection: Res<BlasterSelection>,
+    mut charge: ResMut<ChargeField>,
 ) {
     let window = window_query.single();
     let (camera, camera_transform) = camera_query.single();
     let (blaster_transform, blaster_vector, mut blaster_last_fired_time) = q_blaster.single_mut();
     let time_since_last_fired = (time.elapsed_seconds_f64() - blaster_last_fired_time.last_fired) as f32;
     
+    let cursor = window.cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+        .map(|ray| ray.origin.truncate());
+
     if buttons.pressed(MouseButton::Left){
         match blaster_selection.selected {
             BlasterType::Water => {
@@ -113,8 +120,8 @@ pub fn shoot_blaster(
                             let mut direction = (world_position.origin.truncate() - blaster_transform.translation.truncate()).normalize() * BLASTER_POWER;
                             
                             let position = (convert_to_grid_position(blaster_transform.translation.x + blaster_vector.vector.normalize().x*BLASTER_DISPLACEMENT, blaster_transform.translation.y + blaster_vector.vector.normalize().y*BLASTER_DISPLACEMENT)); //shoot slightly in the direction of the cursor
-                            map.insert_at::<WaterParticle>(&mut commands, position, ListType::OnlyAir);
-                            map.give_velocity(&mut commands, position, direction);  
+                            map.insert_at(ParticleElement::Water, position, ListType::OnlyAir);
+                            map.give_velocity(position, direction);  
                         }
                     }
                 }
@@ -133,7 +140,7 @@ pub fn shoot_blaster(
                         let mut x: f32 = -size * PARTICLE_SIZE;
                         while x < size * PARTICLE_SIZE + 0.1{
                             let position = (((world_position.x+x) / PARTICLE_SIZE) as i32, ((world_position.y+y) / PARTICLE_SIZE) as i32);
-                            map.delete_at(&mut commands, (((world_position.x+x) / PARTICLE_SIZE) as i32, ((world_position.y+y) / PARTICLE_SIZE) as i32));
+                            map.delete_at((((world_position.x+x) / PARTICLE_SIZE) as i32, ((world_position.y+y) / PARTICLE_SIZE) as i32));
                             x += PARTICLE_SIZE;
                         }
                         y += PARTICLE_SIZE;
@@ -142,24 +149,32 @@ pub fn shoot_blaster(
                 
             }
             BlasterType::Gas => {
-                if let Some(world_position) = 
-                        window.cursor_position()
-                            .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
-                            .map(|ray| ray.origin.truncate())
-                {
-                    let size = 1.;
-                    let mut y: f32 = -size * PARTICLE_SIZE;
-                    while y < size * PARTICLE_SIZE + 0.1{
-                        let mut x: f32 = -size * PARTICLE_SIZE;
-                        while x < size * PARTICLE_SIZE + 0.1{
-                            let position = (((world_position.x+x) / PARTICLE_SIZE) as i32, ((world_position.y+y) / PARTICLE_SIZE) as i32);
-                            map.insert_at::<ToxicGasParticle>(&mut commands, (position.0, position.1), ListType::OnlyAir);
-                            x += PARTICLE_SIZE;
+                spray(&mut map, cursor, ParticleElement::ToxicGas, 0., &time, &mut blaster_last_fired_time);
+            }
+            BlasterType::Explosive => {
+                // a cloud of volatile gas, it goes off once something hot (i.e. the lava blaster) gets to it
+                spray(&mut map, cursor, ParticleElement::VolatileGas, 0., &time, &mut blaster_last_fired_time);
+            }
+            BlasterType::Shock => {
+                // charges the water/metal under the cursor, does nothing if there's nothing there to conduct it
+                if let Some(world_position) = cursor {
+                    if time_since_last_fired > SHOCK_RECHARGE_RATE {
+                        let (x, y) = convert_to_grid_position(world_position.x, world_position.y);
+                        let shocked = (-1..=1).flat_map(|dx| (-1..=1).map(move |dy| (x + dx, y + dy)))
+                            .any(|position| charge.shock(&map, position));
+                        if shocked {
+                            blaster_last_fired_time.last_fired = time.elapsed_seconds_f64();
                         }
-                        y += PARTICLE_SIZE;
                     }
                 }
             }
+            BlasterType::Copper => {
+                // builds a little block of copper to run the shock blaster through. it's only in the
+                // element file, so it's looked up by name (nothing happens if the file doesn't have it)
+                if let Some(copper) = map.registry().by_name("Copper") {
+                    spray(&mut map, cursor, copper, 0., &time, &mut blaster_last_fired_time);
+                }
+            }
             BlasterType::Lava => {
                 if let Some(world_position) = 
                 window.cursor_position()
@@ -176,8 +191,8 @@ pub fn shoot_blaster(
                                 let mut direction = (world_position.origin.truncate() - blaster_transform.translation.truncate()).normalize() * BLASTER_POWER;
                                 
                                 let position = (convert_to_grid_position(blaster_transform.translation.x + blaster_vector.vector.normalize().x*BLASTER_DISPLACEMENT, blaster_transform.translation.y + blaster_vector.vector.normalize().y*BLASTER_DISPLACEMENT)); //shoot slightly in the direction of the cursor
-                                map.insert_at::<LavaParticle>(&mut commands, position, ListType::OnlyAir);
-                                map.give_velocity(&mut commands, position, direction);  
+                                map.insert_at(ParticleElement::Lava, position, ListType::OnlyAir);
+                                map.give_velocity(position, direction);  
                             }
                         }
                     }
@@ -199,8 +214,8 @@ pub fn shoot_blaster(
                                 let mut direction = (world_position.origin.truncate() - blaster_transform.translation.truncate()).normalize() * BLASTER_POWER;
                                 
                                 let position = (convert_to_grid_position(blaster_transform.translation.x + blaster_vector.vector.normalize().x*BLASTER_DISPLACEMENT, blaster_transform.translation.y + blaster_vector.vector.normalize().y*BLASTER_DISPLACEMENT)); //shoot slightly in the direction of the cursor
-                                map.insert_at::<Healing_SpringParticle>(&mut commands, position, ListType::OnlyAir);
-                                map.give_velocity(&mut commands, position, direction);  
+                                map.insert_at(ParticleElement::Healing_Spring, position, ListType::OnlyAir);
+                                map.give_velocity(position, direction);  
                             }
                         }
                     }
@@ -211,6 +226,36 @@ pub fn shoot_blaster(
     }
 }
 
+// fills the cells around the cursor with `element`, no more than once every `rate` seconds (0 is every frame)
+fn spray(
+    map: &mut ParticleMap,
+    cursor: Option<Vec2>,
+    element: ParticleElement,
+    rate: f32,
+    time: &Time,
+    last_fired: &mut BlasterLastFiredTime,
+) {
+    let Some(world_position) = cursor else {
+        return
+    };
+    if ((time.elapsed_seconds_f64() - last_fired.last_fired) as f32) < rate {
+        return
+    }
+    last_fired.last_fired = time.elapsed_seconds_f64();
+
+    let size = 1.;
+    let mut y: f32 = -size * PARTICLE_SIZE;
+    while y < size * PARTICLE_SIZE + 0.1{
+        let mut x: f32 = -size * PARTICLE_SIZE;
+        while x < size * PARTICLE_SIZE + 0.1{
+            let position = (((world_position.x+x) / PARTICLE_SIZE) as i32, ((world_position.y+y) / PARTICLE_SIZE) as i32);
+            map.insert_at(element, position, ListType::OnlyAir);
+            x += PARTICLE_SIZE;
+        }
+        y += PARTICLE_SIZE;
+    }
+}
+
 fn get_game_coords( //gets window cursor pos and converts to world position
     coords: &mut Vec2,
     q_window: Query<&Window, With<bevy::window::PrimaryWindow>>,
@@ -248,6 +293,12 @@ fn change_blaster_sprite(
         BlasterType::Gas => 2,
         BlasterType::Lava => 3,
         BlasterType::Healing_Spring => 4,
+        // no sprite of its own yet, it's a gas blaster too
+        BlasterType::Explosive => 2,
+        // same for the shock blaster, it borrows the water one
+        BlasterType::Shock => 0,
+        // and the copper one builds instead of deleting, close enough
+        BlasterType::Copper => 1,
     };    
 }
 
@@ -266,6 +317,12 @@ pub fn handle_blaster_change_input(
         event_writer.send(ChangeBlasterEvent { new_blaster_type: BlasterType::Lava });
     } else if keyboard_input.just_pressed(KeyCode::Digit5) {
         event_writer.send(ChangeBlasterEvent { new_blaster_type: BlasterType::Healing_Spring });
+    } else if keyboard_input.just_pressed(KeyCode::Digit6) {
+        event_writer.send(ChangeBlasterEvent { new_blaster_type: BlasterType::Explosive });
+    } else if keyboard_input.just_pressed(KeyCode::Digit7) {
+        event_writer.send(ChangeBlasterEvent { new_blaster_type: BlasterType::Shock });
+    } else if keyboard_input.just_pressed(KeyCode::Digit8) {
+        event_writer.send(ChangeBlasterEvent { new_blaster_type: BlasterType::Copper });
     }
 }
 
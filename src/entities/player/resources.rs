@@ -11,6 +11,8 @@ pub const PLAYER_MAX_SPEED: f32 = 250.;
 pub const ACCEL_RATE_X: f32 = 5000.;
 pub const ACCEL_RATE_Y: f32 = 10800.;
 
+pub const PLAYER_WIND: f32 = 0.25; // how much of the wind's speed the player is blown along with while flying
+
 pub const ANIM_TIME: f32 = 0.2;
 
 pub const SPRITE_HEIGHT: u32 = 50;
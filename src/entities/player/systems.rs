@@ -5,11 +5,11 @@ use bevy::prelude::*;
 use super::{blaster::{self, components::*}, components::*, resources::*};
 use crate::{
     common::{
-        death::Death, gravity::{Gravity, GravityResource}, hitbox::Hitbox
+        death::Death, gravity::{Gravity, GravityResource}, hitbox::Hitbox, timestep::{run_simulation_steps, Interpolated}, wind::Wind
     },
     entities::{
         enemy::components::Enemy, 
-        particle::{components::{ParticleElement, WaterParticle, ParticleTagQuickSand,ParticleTagSlime},
+        particle::{components::ParticleElement,
         resources::*},
         spaceship::components::{FoundFlag, FoundSpaceship, Spaceship}
     },
@@ -17,7 +17,8 @@ use crate::{
     LEVEL_W,
 };
 use crate::state::AppState;
-use crate::state::GamePhase;
+use crate::state::EnterPlanet;
+use crate::planets::definition::CurrentPlanet;
 
 
 
@@ -25,6 +26,7 @@ pub fn initialize(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    planet: CurrentPlanet,
 ){
     let player_sheet_handle = asset_server.load("walking.png");
     //               used to be tilesize. removed TILE_SIZE and now at 100, but change as needed  \/
@@ -32,56 +34,7 @@ pub fn initialize(
     let player_layout_len = player_layout.textures.len();
     let player_layout_handle = texture_atlases.add(player_layout);
 
-    let player_scale = 1.0;
-    commands.spawn((
-        SpriteBundle {
-            texture: player_sheet_handle,
-            transform: Transform {
-                translation: Vec3::new(0., 100.0, 900.),
-                scale: Vec3::splat(player_scale),
-                ..default()
-            },
-            sprite: Sprite {
-                // Flip the logo to the left
-                flip_x: false,
-                ..default()
-            },
-            ..default()
-        },
-        TextureAtlas {
-            layout: player_layout_handle,
-            index: 0,
-        },
-        AnimationTimer(Timer::from_seconds(ANIM_TIME, TimerMode::Repeating)),
-        AnimationFrameCount(player_layout_len),
-        Velocity::new(),
-        Health::new(100.0),
-        Gravity::new(),
-        Hitbox::new(SPRITE_WIDTH as f32, SPRITE_HEIGHT as f32, Vec2::new(0., 110.)),
-        Player,
-    ));
-
-    commands.insert_resource(PlayerRatioWaterParticles{
-        number: 0.0,
-    });
-
-    commands.insert_resource(PlayerSoundTracker{
-        last_played: Duration::new(0, 0),
-    });
-}
-
-pub fn initialize_special(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-){
-    let player_sheet_handle = asset_server.load("walking.png");
-    //               used to be tilesize. removed TILE_SIZE and now at 100, but change as needed  \/
-    let player_layout = TextureAtlasLayout::from_grid(UVec2::splat(100), 4, 1, None, None);
-    let player_layout_len = player_layout.textures.len();
-    let player_layout_handle = texture_atlases.add(player_layout);
-
-    let player_scale = 0.4;
+    let player_scale = planet.get().player.scale;
     commands.spawn((
         SpriteBundle {
             texture: player_sheet_handle,
@@ -107,6 +60,7 @@ pub fn initialize_special(
         Health::new(100.0),
         Gravity::new(),
         Hitbox::new(SPRITE_WIDTH as f32, SPRITE_HEIGHT as f32, Vec2::new(0., 110.)),
+        Interpolated::new(),
         Player,
     ));
 
@@ -128,23 +82,24 @@ pub fn move_player(
     map: ResMut<ParticleMap>,
     mut spaceship: Query<(&Hitbox, &mut FoundFlag), (With<Spaceship>, Without<Player>)>,
     mut ship_event: EventWriter<FoundSpaceship>,
-    state: Res<State<GamePhase>>
+    planet: CurrentPlanet,
 ) {
     let (mut pt, mut pv, mut ps, mut hb, mut player_health) = player.single_mut();
     let mut deltav_x = 0.;
     let mut bt = blaster_transform.single_mut();
     let (mut spaceship_hb, mut found_flag) = spaceship.single_mut();
-    let game_state = state.get();
+    // chunked planets go on forever to the sides
+    let endless = planet.get().terrain.chunked;
 
     if input.pressed(KeyCode::KeyA) {
-        if game_state.eq(&GamePhase::Planet4) || pt.translation.x >= -(LEVEL_W / 2.) + (SPRITE_WIDTH as f32) / 2.{
+        if endless || pt.translation.x >= -(LEVEL_W / 2.) + (SPRITE_WIDTH as f32) / 2.{
             deltav_x -= 1.;
             ps.flip_x = true;
         }
     }
 
     if input.pressed(KeyCode::KeyD) {
-        if game_state.eq(&GamePhase::Planet4) || pt.translation.x <= LEVEL_W - (LEVEL_W / 2. + (SPRITE_WIDTH as f32) / 2.){
+        if endless || pt.translation.x <= LEVEL_W - (LEVEL_W / 2. + (SPRITE_WIDTH as f32) / 2.){
             deltav_x += 1.;
             ps.flip_x = false;
         }
@@ -210,8 +165,8 @@ pub fn move_player(
     
     if //new_pos.x >= -(LEVEL_W / 2.) + (SPRITE_WIDTH as f32) / 2.
         //&& new_pos.x <= LEVEL_W - (LEVEL_W / 2. + (SPRITE_WIDTH as f32) / 2.)
-        //&& !new_hb.all_player_collisions(&hitboxes)
-        !new_hb.all_player_collisions(&hitboxes)
+        //&& !new_hb.all_player_collisions(&hitboxes, &map)
+        !new_hb.all_player_collisions(&hitboxes, &map)
     {
         pt.translation = new_pos;
         *hb = new_hb;
@@ -231,6 +186,7 @@ pub fn flight(
     mut player_ratio_water_particles: ResMut<PlayerRatioWaterParticles>,
     mut commands: Commands,
     grav_res: ResMut<GravityResource>,
+    wind: Res<Wind>,
     mut death_event: EventWriter<Death>,
     asset_server: Res<AssetServer>,
     mut sound_tracker: ResMut<PlayerSoundTracker>,
@@ -266,6 +222,12 @@ pub fn flight(
         take_damage(&mut health , 0.5, &mut death_event, &asset_server, &mut commands, &mut sound_tracker, &time);
     }
 
+    // Account for player in fire
+    let ratio_of_fire_particles = hb.ratio_of_fire_grid_tiles(&map);
+    if ratio_of_fire_particles > 0.0 {
+        take_damage(&mut health , 0.3, &mut death_event, &asset_server, &mut commands, &mut sound_tracker, &time);
+    }
+
     // Account for player in healing spring
     let ratio_of_healing_spring_particles = hb.ratio_of_healing_spring_grid_tiles(&map);
     if ratio_of_healing_spring_particles > 0.0 {
@@ -283,14 +245,19 @@ pub fn flight(
         pv.velocity.y = pv.velocity.y * (1. - 0.3 * ratio_of_slime_particles.powf(0.5));
     }
 
-    let change = pv.velocity * deltat;
+    let mut change = pv.velocity * deltat;
+    // up in the air the wind blows the player around a bit
+    if input.pressed(KeyCode::Space) {
+        let position = convert_to_grid_position(pt.translation.x, pt.translation.y);
+        change += wind.at(position) * PLAYER_WIND * deltat;
+    }
     let new_pos = pt.translation + change.extend(0.);
     let new_hb = Hitbox::new(SPRITE_WIDTH as f32 * pt.scale.x, SPRITE_HEIGHT as f32 * pt.scale.x, new_pos.xy());
     //Bound player to within level height
 
     if new_pos.y >= -(LEVEL_H / 2.) + (SPRITE_HEIGHT as f32) / 2.
         && new_pos.y <= (LEVEL_H / 2.) - (SPRITE_HEIGHT as f32) / 2.
-        && !new_hb.all_player_collisions(&hitboxes)
+        && !new_hb.all_player_collisions(&hitboxes, &map)
     {
         pt.translation = new_pos;
         *hb = new_hb;
@@ -301,7 +268,7 @@ pub fn flight(
     let new_hb = Hitbox::new(SPRITE_WIDTH as f32 * pt.scale.x, SPRITE_HEIGHT as f32 * pt.scale.x, new_pos.xy());
     // Velocity is zero when player hits the ground
     if pt.translation.y <= -(LEVEL_H / 2.) + (SPRITE_HEIGHT as f32) ||
-        new_hb.all_player_collisions(&hitboxes) 
+        new_hb.all_player_collisions(&hitboxes, &map) 
     {
         pv.velocity.y = 0.;
     }
@@ -309,7 +276,7 @@ pub fn flight(
 
     //update number of water particles the player is in
 
-    player_ratio_water_particles.number = water_splash(&mut player_ratio_water_particles, &hb, map, &pv, commands);
+    player_ratio_water_particles.number = water_splash(&mut player_ratio_water_particles, &hb, map, &pv);
 
 }
 
@@ -342,7 +309,6 @@ fn water_splash(
     hb: &Hitbox, 
     mut map: ResMut<ParticleMap>,
     pv: &Velocity,
-    mut commands: Commands,
 ) -> f32 {
     let new_ratio = hb.ratio_of_water_grid_tiles(&map);
     if new_ratio / player_ratio_water_particles.number > SPLASH_THRESHOLD {
@@ -371,18 +337,16 @@ fn water_splash(
                     let left_x = center_x.saturating_sub(offset);
                     let right_x = center_x + offset;
 
-                    if left_x >= top_left_x && map.get_element_at((left_x, y)) == ParticleElement::Water{
-                        map.delete_at(&mut commands, (left_x, y));
-                        map.insert_at::<WaterParticle>(&mut commands, (left_x, y+y_splash_distance), ListType::OnlyAir);
+                    if left_x >= top_left_x && map.get_element_at((left_x, y)) == ParticleElement::Water &&
+                        map.move_cell((left_x, y), (left_x, y+y_splash_distance)) {
                         count += 1;
                         if count >= num_water_particles_to_splash {
                             break;
                         }
                     }
 
-                    if right_x <= bottom_right_x && map.get_element_at((right_x, y+y_splash_distance)) == ParticleElement::Water {
-                        map.delete_at(&mut commands, (right_x,y));
-                        map.insert_at::<WaterParticle>(&mut commands, (right_x, y+y_splash_distance), ListType::OnlyAir);
+                    if right_x <= bottom_right_x && map.get_element_at((right_x, y)) == ParticleElement::Water &&
+                        map.move_cell((right_x, y), (right_x, y+y_splash_distance)) {
                         count += 1;
                         if count >= num_water_particles_to_splash {
                             break;
@@ -497,12 +461,15 @@ pub fn constant_damage(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut sound_tracker: ResMut<PlayerSoundTracker>,
+    planet: CurrentPlanet,
 ) {
+    // Damage the player every second, on planets that hurt just being there
+    let damage_amount = planet.get().player.damage_per_second;
+    if damage_amount <= 0. {
+        return;
+    }
     let (mut health, _transform) = player.single_mut();
 
-    // Damage the player every second
-    let damage_amount = 2.5; // Adjust damage amount as needed
-
     take_damage(
         &mut health,
         damage_amount * time.delta_seconds(),
@@ -518,39 +485,26 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         // Startup events
-        app.add_systems(OnEnter(AppState::InGame), initialize);
-        app.add_systems(OnEnter(GamePhase::Planet2), initialize);
-        app.add_systems(OnEnter(GamePhase::Planet3), initialize);
-        app.add_systems(OnEnter(GamePhase::Planet4), initialize);
-        app.add_systems(OnEnter(GamePhase::Planet5), initialize);
-        app.add_systems(OnEnter(GamePhase::Planet6), initialize);
-        app.add_systems(OnEnter(GamePhase::Planet7), initialize);
-        app.add_systems(OnEnter(GamePhase::Planet8), initialize_special);
+        app.add_systems(EnterPlanet, initialize);
 
        // app.add_systems(PreUpdate,  initialize.run_if(state_changed::<GamePhase>));
         app.add_event::<super::blaster::components::ChangeBlasterEvent>();
-        app.add_systems(OnEnter(AppState::InGame), super::blaster::systems::initialize.after(initialize));
-        app.add_systems(OnEnter(GamePhase::Planet2), super::blaster::systems::initialize.after(initialize));
-        app.add_systems(OnEnter(GamePhase::Planet3), super::blaster::systems::initialize.after(initialize));
-        app.add_systems(OnEnter(GamePhase::Planet4), super::blaster::systems::initialize.after(initialize));
-        app.add_systems(OnEnter(GamePhase::Planet5), super::blaster::systems::initialize.after(initialize));
-        app.add_systems(OnEnter(GamePhase::Planet6), super::blaster::systems::initialize.after(initialize));
-        app.add_systems(OnEnter(GamePhase::Planet7), super::blaster::systems::initialize.after(initialize));
-        app.add_systems(OnEnter(GamePhase::Planet8), super::blaster::systems::initialize.after(initialize_special));
+        app.add_systems(EnterPlanet, super::blaster::systems::initialize.after(initialize));
 
 
 
-        app.add_systems(Update, move_player.run_if(in_state(AppState::InGame)));
+        // movement and gravity are physics, they run on the fixed tick (see common/timestep.rs)
+        app.add_systems(FixedUpdate, move_player.run_if(in_state(AppState::InGame)));
         
 
 
-        app.add_systems(Update, flight.after(super::systems::move_player).run_if(in_state(AppState::InGame)));
-        app.add_systems(Update, animate_player.after(super::systems::move_player).run_if(in_state(AppState::InGame)));
+        app.add_systems(FixedUpdate, flight.after(super::systems::move_player).before(run_simulation_steps).run_if(in_state(AppState::InGame)));
+        app.add_systems(Update, animate_player.run_if(in_state(AppState::InGame)));
         app.add_systems(Update, super::blaster::systems::update_blaster_aim.run_if(in_state(AppState::InGame)));
-        app.add_systems(Update, super::blaster::systems::shoot_blaster.after(super::systems::flight).run_if(in_state(AppState::InGame)));
+        app.add_systems(Update, super::blaster::systems::shoot_blaster.run_if(in_state(AppState::InGame)));
         app.add_systems(Update, super::blaster::systems::handle_blaster_change_input.run_if(in_state(AppState::InGame)));
         app.add_systems(Update, super::blaster::systems::change_blaster_on_event.run_if(in_state(AppState::InGame)));
-        app.add_systems(Update, constant_damage.run_if(in_state(GamePhase::Planet8)));
+        app.add_systems(Update, constant_damage.run_if(in_state(AppState::InGame)));
 
      //   app.add_system(super::blaster::systems::switch_blaster.system());
       //  app.add_system(super::blaster::systems::handle_blaster_switch.system());
@@ -0,0 +1,8 @@
+use bevy::prelude::*;
+
+use crate::WIN_W;
+
+// where the spaceship starts out on every planet (it falls from there), and the size of its hitbox.
+// world generation digs it out and keeps a way to it open, see worldgen/caves.rs
+pub const SHIP_SPAWN: Vec2 = Vec2::new(WIN_W / 3., 100.);
+pub const SHIP_SIZE: Vec2 = Vec2::new(50., 100.);
@@ -2,11 +2,12 @@ use bevy::prelude::*;
 use super::{components::*, resources::*};
 use crate::{
     common::{
-        gravity::{Gravity, GravityResource}, hitbox::Hitbox, state::{set_next_state, AppState, GamePhase}
+        gravity::{Gravity, GravityResource}, hitbox::Hitbox, state::{set_next_state, AppState, EnterPlanet, GamePhase}
     },
+    entities::{enemy::components::Enemy, particle::resources::ParticleMap},
+    planets::definition::{Planets, WinCondition},
     LEVEL_H,
     LEVEL_W,
-    WIN_W,
 };
 
 
@@ -23,7 +24,7 @@ pub fn initialize(
         SpriteBundle {
             texture: ship_sheet_handle,
             transform: Transform {
-                translation: Vec3::new(WIN_W/3., 100.0, 902.),
+                translation: SHIP_SPAWN.extend(902.),
                 ..default()
             },
             sprite: Sprite {
@@ -39,7 +40,7 @@ pub fn initialize(
         },
         Velocity::new(),
         Gravity::new(),
-        Hitbox::new(50 as f32, 100 as f32, Vec2::new(WIN_W/3., 100.)),
+        Hitbox::new(SHIP_SIZE.x, SHIP_SIZE.y, SHIP_SPAWN),
         Spaceship,
         FoundFlag::new(),
     ));
@@ -51,6 +52,7 @@ pub fn spaceship_gravity(
     mut ship: Query<(&mut Transform, &mut Velocity, &mut Gravity, &mut Hitbox), With<Spaceship>>, 
     hitboxes: Query<&Hitbox, Without<Spaceship>>,
     grav_res: ResMut<GravityResource>,
+    map: Res<ParticleMap>,
 ) {
     /*Julianne 10/8: This function is the same as player flight, but only makes the downward force on the enemy (no flight)*/
     for (mut pt, mut pv, mut pg, mut hb) in &mut ship{
@@ -68,7 +70,7 @@ pub fn spaceship_gravity(
     //Bound enemy to within level height
     if new_pos.y >= -(LEVEL_H / 2.) + (100 as f32) / 2.
         && new_pos.y <= LEVEL_H - (100 as f32) / 2.
-        && !new_hb.all_ship_collisions(&hitboxes)
+        && !new_hb.all_ship_collisions(&hitboxes, &map)
     {    
 
             pt.translation = new_pos;
@@ -77,7 +79,7 @@ pub fn spaceship_gravity(
     let new_hb = Hitbox::new(50 as f32, 100 as f32,Vec2::new(new_pos.x, new_pos.y));
     // Velocity is zero when hits the ground
     if pt.translation.y <= -(LEVEL_H / 2.) + (50 as f32) ||
-        new_hb.all_ship_collisions(&hitboxes)
+        new_hb.all_ship_collisions(&hitboxes, &map)
     {
         pv.velocity.y = 0.;
         
@@ -88,6 +90,9 @@ pub fn spaceship_gravity(
 fn found_spaceship_event_listener(
     mut ship_event: EventReader<FoundSpaceship>,
     //mut next_state: ResMut<NextState<GameState>>,
+    ships: Query<&FoundFlag, With<Spaceship>>,
+    enemies: Query<(), With<Enemy>>,
+    planets: Res<Planets>,
     state: Res<State<GamePhase>>,
     next_phase: ResMut<NextState<GamePhase>>,
     next_app_state: ResMut<NextState<AppState>>,
@@ -95,10 +100,18 @@ fn found_spaceship_event_listener(
 ) {
     if !ship_event.is_empty() {
         info!("player found ship!");
-        //next_app_state.set(AppState::WinScreen);
         ship_event.clear();
-        set_next_state(state, next_phase, next_app_state);
-        
+    }
+    if !ships.iter().any(|flag| flag.found) {
+        return
+    }
+    // the ship stays found, so it takes off as soon as the last enemy is gone
+    let won = match planets.get(*state.get()).win {
+        WinCondition::Spaceship => true,
+        WinCondition::DefeatEnemies => enemies.is_empty(),
+    };
+    if won {
+        set_next_state(state, &planets, next_phase, next_app_state);
     }
 }
 
@@ -106,14 +119,7 @@ fn found_spaceship_event_listener(
 pub struct SpaceshipPlugin;
 impl Plugin for SpaceshipPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::InGame), initialize)
-        .add_systems(OnEnter(GamePhase::Planet2), initialize)
-        .add_systems(OnEnter(GamePhase::Planet3), initialize)
-        .add_systems(OnEnter(GamePhase::Planet4), initialize)
-        .add_systems(OnEnter(GamePhase::Planet5), initialize)
-        .add_systems(OnEnter(GamePhase::Planet6), initialize)
-        .add_systems(OnEnter(GamePhase::Planet7), initialize)
-        .add_systems(OnEnter(GamePhase::Planet8), initialize)
+        app.add_systems(EnterPlanet, initialize)
 
         //app.add_systems(PreUpdate,  initialize.run_if(state_changed::<GamePhase>))
         //.add_systems(Update, spaceship_gravity.run_if(in_state(AppState::InGame)))
@@ -0,0 +1,16 @@
+#![allow(unused_variables)]
+
+
+use crate::common::state;
+pub mod common;
+pub mod entities;
+pub mod planets;
+pub mod worldgen;
+
+
+// Game constants
+pub const TITLE: &str = "Pixelstellar";
+pub const WIN_W: f32 = 1280.;
+pub const WIN_H: f32 = 720.;
+pub const LEVEL_W: f32 = WIN_W * 3.0;
+pub const LEVEL_H: f32 = WIN_H * 2.0;
@@ -0,0 +1,226 @@
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::common::{gravity::GravityResource, state::GamePhase, wind::WindSettings};
+use crate::entities::enemy::components::EnemyKind;
+use crate::worldgen::WorldGenSettings;
+
+/* Planet definitions. Everything that makes a planet different (background, gravity, terrain, weather,
+   enemies and how you win) is in its own file in assets/planets, what all the fields do is written at the
+   top of planet_1.planet.ron. The game picks up every *.planet.ron in that folder, so adding a planet is
+   adding a file.
+   Like the element list the planets that come with the game are compiled in (Planets::builtin), so
+   there's something to play before the folder has loaded, or without an asset server at all.
+*/
+pub const PLANETS_FOLDER: &str = "planets";
+const BUILTIN_PLANETS_RON: [&str; 8] = [
+    include_str!("../../assets/planets/planet_1.planet.ron"),
+    include_str!("../../assets/planets/planet_2.planet.ron"),
+    include_str!("../../assets/planets/planet_3.planet.ron"),
+    include_str!("../../assets/planets/planet_4.planet.ron"),
+    include_str!("../../assets/planets/planet_5.planet.ron"),
+    include_str!("../../assets/planets/planet_6.planet.ron"),
+    include_str!("../../assets/planets/planet_7.planet.ron"),
+    include_str!("../../assets/planets/planet_8.planet.ron"),
+];
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Precipitation {
+    pub element: String,
+    pub amount: u32,
+    pub height: (i32, i32),
+    #[serde(default)]
+    pub around_player: Option<i32>,
+    #[serde(default)]
+    pub fall: Option<f32>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PlayerSettings {
+    pub scale: f32,
+    pub damage_per_second: f32,
+}
+
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        Self {
+            scale: 1.,
+            damage_per_second: 0.,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum WinCondition {
+    #[default]
+    Spaceship,
+    DefeatEnemies,
+}
+
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct PlanetDefinition {
+    pub name: String,
+    pub order: i32,
+    #[serde(default)]
+    pub background: Option<String>,
+    #[serde(default)]
+    pub gravity: GravityResource,
+    #[serde(default = "room_temperature")]
+    pub air_temperature: f32,
+    pub terrain: WorldGenSettings,
+    #[serde(default)]
+    pub wind: WindSettings,
+    #[serde(default)]
+    pub weather: Vec<Precipitation>,
+    #[serde(default)]
+    pub enemies: Vec<EnemyKind>,
+    #[serde(default)]
+    pub player: PlayerSettings,
+    #[serde(default)]
+    pub win: WinCondition,
+}
+
+fn room_temperature() -> f32 {
+    20.
+}
+
+impl PlanetDefinition {
+    pub fn from_ron(text: &str) -> Result<Self, PlanetDefinitionError> {
+        let mut planet: PlanetDefinition = ron::from_str(text)?;
+        // the terrain's noise is seeded with the planet's name
+        planet.terrain.name = planet.name.clone();
+        for (i, kind) in planet.enemies.iter().enumerate() {
+            if planet.enemies[..i].iter().any(|other| other.name == kind.name) {
+                return Err(PlanetDefinitionError::DuplicateEnemy(planet.name.clone(), kind.name.clone()));
+            }
+        }
+        Ok(planet)
+    }
+}
+
+// every planet, in the order they're played. GamePhase is an index into it
+#[derive(Resource, Clone, Debug)]
+pub struct Planets {
+    list: Vec<PlanetDefinition>,
+}
+
+impl Planets {
+    pub fn new(mut list: Vec<PlanetDefinition>) -> Result<Self, PlanetDefinitionError> {
+        if list.is_empty() {
+            return Err(PlanetDefinitionError::NoPlanets);
+        }
+        list.sort_by(|a, b| a.order.cmp(&b.order).then_with(|| a.name.cmp(&b.name)));
+        for (i, planet) in list.iter().enumerate() {
+            if list[..i].iter().any(|other| other.name == planet.name) {
+                return Err(PlanetDefinitionError::DuplicatePlanet(planet.name.clone()));
+            }
+        }
+        Ok(Self { list })
+    }
+
+    pub fn builtin() -> Self {
+        let list = BUILTIN_PLANETS_RON.iter()
+            .map(|text| PlanetDefinition::from_ron(text).expect("a planet in assets/planets is not a valid planet"))
+            .collect();
+        Self::new(list).expect("the planets in assets/planets don't go together")
+    }
+
+    // phases come from this list (by_name, next) and apply_planets moves them along when it's reloaded.
+    // the last planet only stands in if a stale one gets through anyway, so nothing panics over it
+    pub fn get(&self, phase: GamePhase) -> &PlanetDefinition {
+        self.list.get(phase.0).unwrap_or_else(|| self.list.last().unwrap())
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<GamePhase> {
+        self.list.iter().position(|planet| planet.name == name).map(GamePhase)
+    }
+
+    // None after the last planet
+    pub fn next(&self, phase: GamePhase) -> Option<GamePhase> {
+        (phase.0 + 1 < self.list.len()).then_some(GamePhase(phase.0 + 1))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PlanetDefinition> {
+        self.list.iter()
+    }
+}
+
+// the planet being played, for systems that only run in game
+#[derive(SystemParam)]
+pub struct CurrentPlanet<'w> {
+    planets: Res<'w, Planets>,
+    phase: Res<'w, State<GamePhase>>,
+}
+
+impl CurrentPlanet<'_> {
+    pub fn get(&self) -> &PlanetDefinition {
+        self.planets.get(*self.phase.get())
+    }
+}
+
+#[derive(Debug)]
+pub enum PlanetDefinitionError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    NoPlanets,
+    DuplicatePlanet(String),
+    DuplicateEnemy(String, String),
+}
+
+impl fmt::Display for PlanetDefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanetDefinitionError::Io(e) => write!(f, "could not read planet: {e}"),
+            PlanetDefinitionError::Ron(e) => write!(f, "could not parse planet: {e}"),
+            PlanetDefinitionError::NoPlanets => write!(f, "there are no planets"),
+            PlanetDefinitionError::DuplicatePlanet(name) => write!(f, "there are two planets called {name}"),
+            PlanetDefinitionError::DuplicateEnemy(planet, name) => write!(f, "{planet} has two kinds of enemy called {name}"),
+        }
+    }
+}
+
+impl std::error::Error for PlanetDefinitionError {}
+
+impl From<std::io::Error> for PlanetDefinitionError {
+    fn from(e: std::io::Error) -> Self {
+        PlanetDefinitionError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for PlanetDefinitionError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        PlanetDefinitionError::Ron(e)
+    }
+}
+
+
+#[derive(Default)]
+pub struct PlanetDefinitionLoader;
+
+impl AssetLoader for PlanetDefinitionLoader {
+    type Asset = PlanetDefinition;
+    type Settings = ();
+    type Error = PlanetDefinitionError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<PlanetDefinition, PlanetDefinitionError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).await?;
+        PlanetDefinition::from_ron(&text)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["planet.ron"]
+    }
+}
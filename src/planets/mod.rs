@@ -1,8 +1,3 @@
-pub mod planet_1;
-pub mod planet_2;
-pub mod planet_3;
-pub mod planet_4;
-pub mod planet_5;
-pub mod planet_6;
-pub mod planet_7;
-pub mod planet_8;
\ No newline at end of file
+// Planets are data, see definition.rs and assets/planets. systems.rs sets each one up when it starts
+pub mod definition;
+pub mod systems;
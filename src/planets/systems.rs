@@ -0,0 +1,176 @@
+use bevy::{asset::LoadedFolder, prelude::*, window::PrimaryWindow};
+use rand::Rng;
+
+use super::definition::*;
+use crate::common::{
+    seed::SimulationRng,
+    state::{AppState, EnterPlanet, ExitPlanet, GamePhase},
+    timestep::TimestepSettings,
+    ui::background::initialize_background,
+    wind::Wind,
+};
+use crate::entities::{
+    particle::{components::*, resources::*},
+    player::components::Player,
+};
+use crate::LEVEL_W;
+
+// Loading ---------------------------------------------------------------------------------------------------
+#[derive(Resource)]
+pub struct PlanetFolderHandle(pub Handle<LoadedFolder>);
+
+fn load_planets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.insert_resource(PlanetFolderHandle(asset_server.load_folder(PLANETS_FOLDER)));
+}
+
+// the game starts out with the compiled in planets, this swaps in every planet file in assets/planets
+#[allow(clippy::too_many_arguments)]
+fn apply_planets(
+    mut planets: ResMut<Planets>,
+    mut folder_events: EventReader<AssetEvent<LoadedFolder>>,
+    mut planet_events: EventReader<AssetEvent<PlanetDefinition>>,
+    handle: Option<Res<PlanetFolderHandle>>,
+    folders: Res<Assets<LoadedFolder>>,
+    definitions: Res<Assets<PlanetDefinition>>,
+    phase: Option<ResMut<State<GamePhase>>>,
+    next_phase: Option<ResMut<NextState<GamePhase>>>,
+) {
+    let Some(handle) = handle else {
+        return
+    };
+    let loaded = folder_events.read().any(|event| event.is_loaded_with_dependencies(&handle.0));
+    let modified = planet_events.read().any(|event| matches!(event, AssetEvent::Modified { .. }));
+    if !loaded && !modified {
+        return
+    }
+    let Some(folder) = folders.get(&handle.0) else {
+        return
+    };
+    // anything in the folder that isn't a planet is left alone
+    let list = folder.handles.iter()
+        .filter_map(|handle| handle.id().try_typed::<PlanetDefinition>().ok())
+        .filter_map(|id| definitions.get(id).cloned())
+        .collect();
+    let loaded = match Planets::new(list) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            error!("{e}, playing the planets that came with the game instead");
+            return
+        }
+    };
+    // the list is sorted again, so the planet being played can be somewhere else in it now
+    if let Some(mut phase) = phase {
+        let playing = &planets.get(*phase.get()).name;
+        let Some(moved) = loaded.by_name(playing) else {
+            error!("{playing} is being played but its planet file is gone, the planets were not reloaded");
+            return
+        };
+        if moved != *phase.get() {
+            // straight into the state instead of NextState, it's the same planet so it isn't left and set up again
+            *phase = State::new(moved);
+        }
+    }
+    // same for a planet that's about to start (i.e. the spaceship was just reached this frame)
+    if let Some(mut next_phase) = next_phase {
+        if let NextState::Pending(pending) = *next_phase {
+            let name = planets.iter().nth(pending.0).map(|planet| planet.name.clone());
+            match name.as_deref().and_then(|name| loaded.by_name(name)) {
+                Some(moved) => next_phase.set(moved),
+                None => {
+                    error!("the next planet ({}) is gone after reloading the planets, it is not started", name.unwrap_or_default());
+                    next_phase.reset();
+                }
+            }
+        }
+    }
+    *planets = loaded;
+}
+
+
+
+// Setting up ------------------------------------------------------------------------------------------------
+fn set_up_planet(
+    mut commands: Commands,
+    planet: CurrentPlanet,
+    mut ambient: ResMut<AmbientTemperature>,
+    mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let planet = planet.get();
+    commands.insert_resource(planet.gravity.clone());
+    ambient.0 = planet.air_temperature;
+    if let Ok(mut window) = q_window.get_single_mut() {
+        window.cursor.icon = CursorIcon::Cell;
+    }
+}
+
+fn reset_air(mut ambient: ResMut<AmbientTemperature>) {
+    *ambient = AmbientTemperature::default();
+}
+
+
+
+// Weather ---------------------------------------------------------------------------------------------------
+fn fall_weather(
+    planet: CurrentPlanet,
+    mut sim_rng: ResMut<SimulationRng>,
+    mut map: ResMut<ParticleMap>,
+    wind: Res<Wind>,
+    timestep: Res<TimestepSettings>,
+    player_transform: Query<&Transform, With<Player>>,
+) {
+    for weather in planet.get().weather.iter() {
+        // a typo in the element (or an empty height) just means nothing falls
+        let Some(element) = map.registry().by_name(&weather.element) else {
+            continue
+        };
+        if weather.height.0 >= weather.height.1 {
+            continue
+        }
+        let (min_x, max_x) = match weather.around_player {
+            Some(cells) => {
+                let Ok(player) = player_transform.get_single() else {
+                    continue
+                };
+                let x = (player.translation.x / PARTICLE_SIZE).floor() as i32;
+                (x - cells, x + cells)
+            }
+            None => ((-(LEVEL_W / 2.)) as i32, (LEVEL_W / 2.) as i32),
+        };
+
+        for _ in 0..weather.amount {
+            let rng = sim_rng.stream("weather");
+            let x = rng.gen_range(min_x..=max_x);
+            let y = rng.gen_range(weather.height.0..weather.height.1);
+            if map.get_element_at((x, y)) == ParticleElement::Air {
+                map.insert_at(element, (x, y), ListType::OnlyAir);
+                if let Some(fall) = weather.fall {
+                    let blown = wind.at((x, y)).x * timestep.step_seconds() / PARTICLE_SIZE;
+                    map.give_velocity((x, y), Vec2::new(blown, fall));
+                }
+            }
+        }
+    }
+}
+
+pub struct PlanetPlugin;
+impl Plugin for PlanetPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Planets::builtin());
+        app.add_systems(EnterPlanet, (set_up_planet, initialize_background));
+        app.add_systems(ExitPlanet, reset_air);
+        app.add_systems(FixedUpdate, fall_weather.run_if(in_state(AppState::InGame)));
+    }
+
+    // same as the element list, without an asset server only the compiled in planets are played
+    fn finish(&self, app: &mut App) {
+        if app.world().contains_resource::<AssetServer>() {
+            app.init_asset::<PlanetDefinition>()
+                .init_asset_loader::<PlanetDefinitionLoader>()
+                .add_systems(Startup, load_planets)
+                .add_systems(Update, apply_planets);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::common::cellular_automata::generate_cave;
+use crate::entities::{
+    particle::resources::{MAX_X, MAX_Y, MIN_X, MIN_Y, PARTICLE_SIZE},
+    player::resources::SPRITE_HEIGHT,
+    spaceship::resources::{SHIP_SIZE, SHIP_SPAWN},
+};
+use super::settings::CellularCaveSettings;
+
+/* The cave pass for planets with cellular_caves. The automaton grows caves between min_y and max_y, the noise
+   caves are added in, and then the caves are cleaned up so the player can get everywhere:
+     - caves the surface can't be reached from that are smaller than min_pocket cells are filled in
+     - bigger ones get a tunnel dug to the closest cave (or bit of sky) that can be reached
+     - the spaceship is dug out and connected the same way, so it's never stuck in rock
+   This needs the whole level at once, so it's worked out once when the planet starts (see WorldGen::new)
+   and the cells just look it up. Outside of the level there are only the noise caves.
+*/
+const WIDTH: usize = (MAX_X - MIN_X + 1) as usize;
+const HEIGHT: usize = (MAX_Y - MIN_Y + 1) as usize;
+// tunnels are never narrower than this whatever the planet asks for, so the player (at full size) fits through
+const MIN_TUNNEL_RADIUS: i32 = SPRITE_HEIGHT.div_ceil(PARTICLE_SIZE as u32 * 2) as i32;
+
+fn index((x, y): (i32, i32)) -> Option<usize> {
+    if !(MIN_X..=MAX_X).contains(&x) || !(MIN_Y..=MAX_Y).contains(&y) {
+        return None
+    }
+    Some((y - MIN_Y) as usize * WIDTH + (x - MIN_X) as usize)
+}
+
+fn position(i: usize) -> (i32, i32) {
+    ((i % WIDTH) as i32 + MIN_X, (i / WIDTH) as i32 + MIN_Y)
+}
+
+fn neighbours(i: usize) -> impl Iterator<Item = usize> {
+    let (x, y) = position(i);
+    [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].into_iter().filter_map(index)
+}
+
+pub struct CaveMap {
+    caves: Vec<bool>,       // every cell of the level that's a cave, only ever under the surface
+    surfaces: Vec<i32>,     // for every column of the level, everything above is sky
+    reached: Vec<bool>,     // sky, and the caves that can be got to from it
+}
+
+impl CaveMap {
+    // `surface` and `noise_cave` are the rest of world generation, see WorldGen::new
+    pub fn new(
+        settings: &CellularCaveSettings,
+        surface: impl Fn(i32) -> i32,
+        noise_cave: impl Fn((i32, i32)) -> bool,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let surfaces: Vec<i32> = (MIN_X..=MAX_X).map(surface).collect();
+        let mut map = Self {
+            caves: vec![false; WIDTH * HEIGHT],
+            reached: vec![false; WIDTH * HEIGHT],
+            surfaces,
+        };
+
+        // the automaton only runs in its band, the edges of the band count as rock so the caves close up there
+        let (min_y, max_y) = (settings.min_y.max(MIN_Y), settings.max_y.min(MAX_Y));
+        if min_y <= max_y {
+            let grid = generate_cave(
+                WIDTH,
+                (max_y - min_y + 1) as usize,
+                settings.wall_probability,
+                settings.steps,
+                settings.birth_limit,
+                settings.survival_limit,
+                rng,
+            );
+            for y in min_y..=max_y {
+                for x in MIN_X..=MAX_X {
+                    if !grid.is_wall((x - MIN_X) as isize, (y - min_y) as isize) {
+                        map.dig((x, y));
+                    }
+                }
+            }
+        }
+        for i in 0..map.caves.len() {
+            if !map.is_sky(position(i)) && noise_cave(position(i)) {
+                map.caves[i] = true;
+            }
+        }
+        let ship_min = ((SHIP_SPAWN - SHIP_SIZE / 2.) / PARTICLE_SIZE).floor();
+        let ship_max = ((SHIP_SPAWN + SHIP_SIZE / 2.) / PARTICLE_SIZE).ceil();
+        let ship: Vec<usize> = (ship_min.x as i32..=ship_max.x as i32)
+            .flat_map(|x| (ship_min.y as i32..=ship_max.y as i32).map(move |y| (x, y)))
+            .filter_map(index)
+            .collect();
+        for i in ship.iter() {
+            map.dig(position(*i));
+        }
+
+        map.connect(settings, &ship);
+        map
+    }
+
+    fn is_sky(&self, (x, y): (i32, i32)) -> bool {
+        y > self.surfaces[(x - MIN_X) as usize]
+    }
+
+    fn is_open(&self, i: usize) -> bool {
+        self.caves[i] || self.is_sky(position(i))
+    }
+
+    // sky is already open, so only cells under the surface become caves. gives back the cell that was dug
+    fn dig(&mut self, pos: (i32, i32)) -> Option<usize> {
+        let i = index(pos)?;
+        if self.is_sky(pos) {
+            return None
+        }
+        self.caves[i] = true;
+        Some(i)
+    }
+
+    // marks everything open that's joined up to `from` as reached
+    fn spread(&mut self, from: impl IntoIterator<Item = usize>) {
+        let mut queue: VecDeque<usize> = from.into_iter().filter(|i| self.is_open(*i)).collect();
+        for i in queue.iter() {
+            self.reached[*i] = true;
+        }
+        while let Some(i) = queue.pop_front() {
+            for next in neighbours(i) {
+                if !self.reached[next] && self.is_open(next) {
+                    self.reached[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    // the cells of one cave, every cave cell joined up to `start`
+    fn pocket(&self, start: usize, checked: &mut [bool]) -> Vec<usize> {
+        let mut pocket = vec![start];
+        checked[start] = true;
+        let mut n = 0;
+        while n < pocket.len() {
+            for next in neighbours(pocket[n]) {
+                if self.caves[next] && !checked[next] {
+                    checked[next] = true;
+                    pocket.push(next);
+                }
+            }
+            n += 1;
+        }
+        pocket
+    }
+
+    // shortest way (through rock too) from any cell of the pocket to a cell that's reached, the pocket not included
+    fn way_out(&self, pocket: &[usize]) -> Vec<usize> {
+        let mut came_from = vec![usize::MAX; self.caves.len()];
+        let mut queue = VecDeque::new();
+        for i in pocket.iter() {
+            came_from[*i] = *i;
+            queue.push_back(*i);
+        }
+        while let Some(i) = queue.pop_front() {
+            if self.reached[i] {
+                let (mut way, mut at) = (vec![i], i);
+                while came_from[at] != at {
+                    at = came_from[at];
+                    way.push(at);
+                }
+                return way
+            }
+            for next in neighbours(i) {
+                if came_from[next] == usize::MAX {
+                    came_from[next] = i;
+                    queue.push_back(next);
+                }
+            }
+        }
+        Vec::new()
+    }
+
+    fn connect(&mut self, settings: &CellularCaveSettings, ship: &[usize]) {
+        let sky: Vec<usize> = (0..self.caves.len()).filter(|i| self.is_sky(position(*i))).collect();
+        self.spread(sky);
+
+        // the spaceship first, it has to be connected whatever size its cave is
+        let mut checked = vec![false; self.caves.len()];
+        for (n, start) in ship.iter().copied().chain(0..self.caves.len()).enumerate() {
+            if !self.caves[start] || self.reached[start] || checked[start] {
+                continue
+            }
+            let pocket = self.pocket(start, &mut checked);
+            if n >= ship.len() && pocket.len() < settings.min_pocket {
+                for i in pocket {
+                    self.caves[i] = false;
+                }
+                continue
+            }
+
+            let way = self.way_out(&pocket);
+            let r = settings.tunnel_radius.max(MIN_TUNNEL_RADIUS);
+            let mut dug = Vec::new();
+            for i in way.iter() {
+                let (x, y) = position(*i);
+                for dx in -r..=r {
+                    for dy in -r..=r {
+                        if dx * dx + dy * dy <= r * r {
+                            dug.extend(self.dig((x + dx, y + dy)));
+                        }
+                    }
+                }
+            }
+            // the whole tunnel is reached, or its edges would be taken for caves of their own further on
+            self.spread(pocket.into_iter().chain(way).chain(dug));
+        }
+    }
+
+    pub fn is_cave(&self, pos: (i32, i32)) -> Option<bool> {
+        index(pos).map(|i| self.caves[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    // hilly surface well above the spaceship, so it starts out buried
+    fn cave_map(seed: u64, settings: &CellularCaveSettings) -> CaveMap {
+        CaveMap::new(settings, |x| 80 + (x / 7) % 10, |_| false, &mut StdRng::seed_from_u64(seed))
+    }
+
+    #[test]
+    fn every_cave_and_the_spaceship_are_reached() {
+        let settings = CellularCaveSettings { min_y: -75, max_y: 90, tunnel_radius: 3, ..Default::default() };
+        for seed in 0..4 {
+            let map = cave_map(seed, &settings);
+            for i in 0..map.caves.len() {
+                assert!(!map.caves[i] || map.reached[i], "seed {seed}: cave at {:?} can't be got to", position(i));
+            }
+            let ship = ((SHIP_SPAWN / PARTICLE_SIZE).x as i32, (SHIP_SPAWN / PARTICLE_SIZE).y as i32);
+            assert!(map.is_cave(ship).unwrap(), "seed {seed}: the spaceship wasn't dug out");
+            assert!(map.reached[index(ship).unwrap()], "seed {seed}: the spaceship can't be got to");
+        }
+    }
+}
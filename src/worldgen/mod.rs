@@ -0,0 +1,289 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::common::{
+    hitbox::Hitbox,
+    perlin_noise::generate_permutation_array,
+    seed::SimulationSeed,
+    state::{AppState, EnterPlanet, ExitPlanet},
+};
+use crate::entities::{
+    particle::{components::*, elements::ElementRegistry, resources::*},
+    player::components::Player,
+};
+use crate::planets::definition::{CurrentPlanet, Planets};
+
+pub mod caves;
+pub mod passes;
+pub mod preview;
+pub mod settings;
+
+use caves::CaveMap;
+use passes::Ore;
+use preview::*;
+pub use settings::*;
+
+/* World generation, the same for every planet. A planet's terrain is just a WorldGenSettings (see settings.rs,
+   the `terrain` in the planet's file in assets/planets), the terrain is made by running it through the passes in passes.rs:
+
+     heightmap -> strata layers -> caves -> ore veins -> liquid pools -> surface decoration
+
+   The cellular caves (caves.rs) are the one exception, they're worked out for the whole level up front.
+
+   Each cell is worked out from its position alone, so the endless ice planet can make chunks as the
+   player gets to them and they come out the same as if the world was made all at once.
+
+   The planet files are assets, so with `cargo run --features hot_reload` they're watched while the game runs.
+   When the terrain of the planet being played changes, regenerate_world makes it again in place.
+*/
+#[derive(Resource)]
+pub struct WorldGen {
+    pub settings: WorldGenSettings,
+    perm: [usize; 512],
+    cave_map: Option<CaveMap>,
+    layers: Vec<ParticleElement>,
+    ores: Vec<Ore>,
+    pools: Vec<(i32, ParticleElement)>,
+    decorations: Vec<(ParticleElement, ParticleElement, i32)>,
+}
+
+// what a column of the map looks like, worked out once for every cell in it
+pub struct Column {
+    pub surface: i32,
+    layers: Vec<(ParticleElement, f32)>,
+}
+
+// elements are named in the settings, a typo leaves a gap instead of crashing the game
+fn element(registry: &ElementRegistry, name: &str) -> ParticleElement {
+    registry.by_name(name).unwrap_or_else(|| {
+        warn!("world generation uses an unknown element {name}, it's left out");
+        ParticleElement::Air
+    })
+}
+
+impl WorldGen {
+    pub fn new(settings: WorldGenSettings, seed: &SimulationSeed, registry: &ElementRegistry) -> Self {
+        let mut rng = seed.rng(&settings.name);
+        let perm = generate_permutation_array(&mut rng);
+        let mut ore_rng = seed.rng(&format!("{}_ores", settings.name));
+
+        let mut worldgen = Self {
+            perm,
+            cave_map: None,
+            layers: settings.layers.iter().map(|layer| element(registry, &layer.element)).collect(),
+            ores: settings.ores.iter().map(|ore| Ore {
+                element: element(registry, &ore.element),
+                replaces: element(registry, &ore.replaces),
+                perm: generate_permutation_array(&mut ore_rng),
+            }).collect(),
+            pools: settings.pools.iter().map(|pool| (pool.level, element(registry, &pool.element))).collect(),
+            decorations: settings.decorations.iter()
+                .map(|decoration| (element(registry, &decoration.on), element(registry, &decoration.into), decoration.depth))
+                .collect(),
+            settings,
+        };
+        if let Some(caves) = &worldgen.settings.cellular_caves {
+            let cave_map = CaveMap::new(
+                caves,
+                |x| worldgen.column(x).surface,
+                |pos| worldgen.is_noise_cave(pos),
+                &mut rng,
+            );
+            worldgen.cave_map = Some(cave_map);
+        }
+        worldgen
+    }
+
+    fn is_noise_cave(&self, pos: (i32, i32)) -> bool {
+        self.settings.caves.as_ref().is_some_and(|caves| passes::is_noise_cave(pos, caves, &self.perm))
+    }
+
+    pub fn column(&self, x: i32) -> Column {
+        let settings = &self.settings;
+        Column {
+            surface: settings.ground + passes::height(x, &settings.height, &self.perm) as i32,
+            layers: self.layers.iter().zip(settings.layers.iter())
+                .map(|(element, layer)| (*element, passes::height(x, &layer.noise_settings, &self.perm)))
+                .collect(),
+        }
+    }
+
+    // the rock, before ores and decorations. None is open (air or a cave)
+    fn terrain(&self, column: &Column, (x, y): (i32, i32)) -> Option<ParticleElement> {
+        if y > column.surface {
+            return None
+        }
+        // the cave map has the noise caves in it too, outside of the level there are only those
+        let cave = match self.cave_map.as_ref().and_then(|caves| caves.is_cave((x, y))) {
+            Some(cave) => cave,
+            None => self.is_noise_cave((x, y)),
+        };
+        if cave {
+            return None
+        }
+        Some(passes::stratum((y - self.settings.ground) as f32, &column.layers))
+    }
+
+    // terrain, or what the pools fill the air above the surface with
+    fn ground(&self, column: &Column, pos: (i32, i32)) -> ParticleElement {
+        match self.terrain(column, pos) {
+            Some(element) => element,
+            None if pos.1 > column.surface => passes::pool(pos.1 - self.settings.ground, &self.pools),
+            None => ParticleElement::Air,
+        }
+    }
+
+    pub fn cell(&self, column: &Column, (x, y): (i32, i32)) -> ParticleElement {
+        let Some(element) = self.terrain(column, (x, y)) else {
+            return self.ground(column, (x, y))
+        };
+        let element = passes::ore((x, y), element, &self.settings.ores, &self.ores);
+        passes::decorate(element, &self.decorations, |up| self.ground(column, (x, y + up)) == ParticleElement::Air)
+    }
+
+    // highest row anything is generated in, in a column
+    fn top(&self, column: &Column) -> i32 {
+        let pools = self.pools.iter().map(|(level, _)| self.settings.ground + level);
+        pools.fold(column.surface, i32::max)
+    }
+
+    // fills the cells from `min` to `max` (both included)
+    fn generate_area(&self, map: &mut ParticleMap, min: (i32, i32), max: (i32, i32), rng: &mut impl Rng) {
+        for x in min.0..=max.0 {
+            let column = self.column(x);
+            for y in min.1..=max.1.min(self.top(&column)) {
+                let element = self.cell(&column, (x, y));
+                if element != ParticleElement::Air {
+                    map.insert_at_with_rng(element, (x, y), ListType::All, rng);
+                }
+            }
+        }
+    }
+
+    // every chunk gets its own rng from the seed, so it comes out the same whatever order they're made in
+    pub fn generate_chunk(&self, map: &mut ParticleMap, seed: &SimulationSeed, chunk: (i32, i32)) {
+        let min = (chunk.0 * CHUNK_SIZE, chunk.1 * CHUNK_SIZE);
+        let max = (min.0 + CHUNK_SIZE - 1, min.1 + CHUNK_SIZE - 1);
+        self.generate_area(map, min, max, &mut seed.chunk_rng(&self.settings.name, chunk));
+    }
+
+    // the whole level, a chunk at a time. works without an App (i.e. benches/particle_update.rs)
+    pub fn generate_level(&self, map: &mut ParticleMap, seed: &SimulationSeed) {
+        for chunk_x in MIN_X.div_euclid(CHUNK_SIZE)..=MAX_X.div_euclid(CHUNK_SIZE) {
+            for chunk_y in MIN_Y.div_euclid(CHUNK_SIZE)..=MAX_Y.div_euclid(CHUNK_SIZE) {
+                let min = ((chunk_x * CHUNK_SIZE).max(MIN_X), (chunk_y * CHUNK_SIZE).max(MIN_Y));
+                let max = ((chunk_x * CHUNK_SIZE + CHUNK_SIZE - 1).min(MAX_X), (chunk_y * CHUNK_SIZE + CHUNK_SIZE - 1).min(MAX_Y));
+                self.generate_area(map, min, max, &mut seed.chunk_rng(&self.settings.name, (chunk_x, chunk_y)));
+            }
+        }
+    }
+}
+
+// runs when a planet starts. Chunked planets only get their settings here, load_chunks does the rest
+pub fn generate_world(
+    mut commands: Commands,
+    planet: CurrentPlanet,
+    seed: Res<SimulationSeed>,
+    mut map: ResMut<ParticleMap>,
+    mut chunks: ResMut<ChunkList>,
+) {
+    let worldgen = WorldGen::new(planet.get().terrain.clone(), &seed, map.registry());
+    chunks.chunk_list.clear();
+    if !worldgen.settings.chunked {
+        worldgen.generate_level(&mut map, &seed);
+    }
+    commands.insert_resource(worldgen);
+}
+
+/* Hot reloading the terrain. Only the terrain is made again, the player, enemies and the spaceship stay where
+   they are (with the ground cleared out around the player so they aren't stuck in rock). Anything that was
+   poured, dug or shot into the level is gone.
+   The old and new surfaces can be compared with the heightmap preview (F6, see preview.rs).
+*/
+fn regenerate_world(
+    planets: Res<Planets>,
+    seed: Res<SimulationSeed>,
+    mut worldgen: ResMut<WorldGen>,
+    mut map: ResMut<ParticleMap>,
+    mut chunks: ResMut<ChunkList>,
+    mut preview: ResMut<HeightmapPreview>,
+    player_hitbox: Query<&Hitbox, With<Player>>,
+) {
+    // by name, the planet can be somewhere else in the list after a reload
+    let Some(phase) = planets.by_name(&worldgen.settings.name) else {
+        error!("there is no planet {} anymore, its terrain was not made again", worldgen.settings.name);
+        return
+    };
+    let settings = &planets.get(phase).terrain;
+    if *settings == worldgen.settings {
+        return
+    }
+    let regenerated = WorldGen::new(settings.clone(), &seed, map.registry());
+    preview.compare(&worldgen, &regenerated);
+
+    for chunk in map.loaded_chunks() {
+        map.despawn_chunk(chunk);
+    }
+    chunks.chunk_list.clear();
+    let player = player_hitbox.get_single().ok();
+    if regenerated.settings.chunked {
+        // only the chunks around the player, load_chunks does the rest as usual
+        let position = player.map_or((0, 0), |hitbox| convert_to_grid_position(hitbox.offset.x, hitbox.offset.y));
+        for chunk in chunks.load(position) {
+            regenerated.generate_chunk(&mut map, &seed, chunk);
+        }
+    } else {
+        regenerated.generate_level(&mut map, &seed);
+    }
+
+    if let Some(hitbox) = player {
+        let bl = (hitbox.offset - Vec2::new(hitbox.width, hitbox.height) / 2.) / PARTICLE_SIZE;
+        let tr = (hitbox.offset + Vec2::new(hitbox.width, hitbox.height) / 2.) / PARTICLE_SIZE;
+        for x in (bl.x.floor() as i32 - 1)..=(tr.x.ceil() as i32) {
+            for y in (bl.y.floor() as i32 - 1)..=(tr.y.ceil() as i32) {
+                map.delete_at((x, y));
+            }
+        }
+    }
+    info!("terrain of {} changed, made it again", regenerated.settings.name);
+    *worldgen = regenerated;
+}
+
+fn load_chunks(
+    seed: Res<SimulationSeed>,
+    worldgen: Res<WorldGen>,
+    mut chunks: ResMut<ChunkList>,
+    mut particles: ResMut<ParticleMap>,
+    player_transform: Query<&Transform, With<Player>>,
+) {
+    let Ok(player) = player_transform.get_single() else {
+        return
+    };
+    if !worldgen.settings.chunked {
+        return
+    }
+    let pt = player.translation;
+    let position = ((pt.x / PARTICLE_SIZE).floor() as i32, (pt.y / PARTICLE_SIZE).floor() as i32);
+    for chunk in chunks.load(position) {
+        worldgen.generate_chunk(&mut particles, &seed, chunk);
+    }
+    for chunk in chunks.unload(position) {
+        particles.despawn_chunk(chunk);
+    }
+}
+
+pub struct WorldGenPlugin;
+impl Plugin for WorldGenPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ChunkList::new());
+        app.init_resource::<HeightmapPreview>();
+        app.add_systems(EnterPlanet, generate_world);
+        app.add_systems(ExitPlanet, reset_heightmap_preview);
+        // planets only change when a planet file is reloaded (see planets/systems.rs)
+        app.add_systems(Update, regenerate_world.before(load_chunks)
+            .run_if(in_state(AppState::InGame)).run_if(resource_exists::<WorldGen>).run_if(resource_changed::<Planets>));
+        app.add_systems(Update, load_chunks.run_if(in_state(AppState::InGame)).run_if(resource_exists::<WorldGen>));
+        app.add_systems(Update, (toggle_heightmap_preview, render_heightmap_preview).chain()
+            .run_if(in_state(AppState::InGame)).run_if(resource_exists::<WorldGen>));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::common::perlin_noise::*;
+use crate::entities::particle::components::*;
+use super::settings::*;
+
+/* The passes, in the order WorldGen::cell runs them. Every one of them only looks at the position it's given
+   (and the column it's in), never at what's already in the map, so any part of the world can be generated
+   on its own and comes out the same as when the whole level is made at once.
+*/
+
+// Heightmap: how far above the ground the surface is in column x
+pub fn height(x: i32, noise: &NoiseSettings, perm: &[usize; 512]) -> f32 {
+    get_1d_octaves(
+        x as f32,
+        noise.start_frequency,
+        noise.octaves,
+        noise.persistence,
+        noise.frequency_modifier,
+        noise.noise_range_min,
+        noise.noise_range_max,
+        perm,
+    )
+    .floor()
+}
+
+// Strata: the first layer (top to bottom) the cell is above the height of, otherwise the last one
+pub fn stratum(height: f32, layers: &[(ParticleElement, f32)]) -> ParticleElement {
+    for (element, layer_height) in layers.iter() {
+        if height >= *layer_height {
+            return *element;
+        }
+    }
+    layers.last().map_or(ParticleElement::Air, |(element, _)| *element)
+}
+
+// Caves, cut out of 2D noise
+pub fn is_noise_cave((x, y): (i32, i32), caves: &CaveSettings, perm: &[usize; 512]) -> bool {
+    if y < caves.min_y || y > caves.max_y {
+        return false
+    }
+    let noise_cave = get_2d_octaves(
+        x as f32,
+        y as f32,
+        caves.start_frequency,
+        caves.octaves,
+        caves.persistence,
+        caves.frequency_modifier,
+        0.,
+        1.,
+        perm,
+    );
+    noise_cave >= caves.noise_threshold_min && noise_cave <= caves.noise_threshold_max
+}
+
+// Ore veins: each ore has its own permutation, so veins don't just line up with the caves
+pub struct Ore {
+    pub element: ParticleElement,
+    pub replaces: ParticleElement,
+    pub perm: [usize; 512],
+}
+
+pub fn ore((x, y): (i32, i32), element: ParticleElement, settings: &[OreSettings], ores: &[Ore]) -> ParticleElement {
+    for (settings, ore) in settings.iter().zip(ores.iter()) {
+        if element != ore.replaces || y < settings.min_y || y > settings.max_y {
+            continue;
+        }
+        let noise = get_2d_octaves(x as f32, y as f32, settings.start_frequency, settings.octaves, 0.5, 1.2, 0., 1., &ore.perm);
+        if noise >= settings.threshold {
+            return ore.element;
+        }
+    }
+    element
+}
+
+// Liquid pools: the first pool whose level the cell is under. only for open air above the surface
+pub fn pool(height: i32, pools: &[(i32, ParticleElement)]) -> ParticleElement {
+    pools.iter()
+        .find(|(level, _)| height <= *level)
+        .map_or(ParticleElement::Air, |(_, element)| *element)
+}
+
+// Surface decoration. `open_above(d)` is whether the cell d cells up is open air
+pub fn decorate(mut element: ParticleElement, decorations: &[(ParticleElement, ParticleElement, i32)], open_above: impl Fn(i32) -> bool) -> ParticleElement {
+    for (on, into, depth) in decorations.iter() {
+        if element == *on && (1..=*depth).any(&open_above) {
+            element = *into;
+        }
+    }
+    element
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::seed::SimulationSeed;
+    use crate::entities::particle::resources::{ParticleMap, MAX_X, MAX_Y, MIN_X, MIN_Y};
+    use super::super::WorldGen;
+    use super::*;
+
+    // stone all the way down, so everything under the surface can be ore
+    fn settings() -> WorldGenSettings {
+        WorldGenSettings {
+            name: "passes_test".into(),
+            layers: vec![LayerSettings { element: "Stone".into(), noise_settings: NoiseSettings::default() }],
+            ..Default::default()
+        }
+    }
+
+    fn generate(settings: WorldGenSettings) -> (WorldGen, ParticleMap) {
+        let mut map = ParticleMap::new();
+        let worldgen = WorldGen::new(settings, &SimulationSeed(3), map.registry());
+        worldgen.generate_level(&mut map, &SimulationSeed(3));
+        (worldgen, map)
+    }
+
+    #[test]
+    fn ore_veins_stay_in_their_rock_and_rows() {
+        let mut settings = settings();
+        settings.ores.push(OreSettings {
+            element: "Copper".into(),
+            replaces: "Stone".into(),
+            start_frequency: 0.06,
+            octaves: 2,
+            min_y: -100,
+            max_y: 0,
+            threshold: 0.6,
+        });
+        let (worldgen, map) = generate(settings);
+        let copper = map.registry().by_name("Copper").unwrap();
+        let mut veins = 0;
+        for x in MIN_X..=MAX_X {
+            let surface = worldgen.column(x).surface;
+            for y in MIN_Y..=MAX_Y {
+                if map.get_element_at((x, y)) == copper {
+                    assert!((-100..=0).contains(&y) && y <= surface, "copper at {:?} outside of its stone", (x, y));
+                    veins += 1;
+                }
+            }
+        }
+        assert!(veins > 0, "no copper was made");
+    }
+
+    #[test]
+    fn pools_fill_the_air_up_to_their_level() {
+        let (plain, _) = generate(settings());
+        let ground = plain.settings.ground;
+        let lowest = (MIN_X..=MAX_X).map(|x| plain.column(x).surface).min().unwrap();
+        let level = lowest - ground + 20;
+
+        let mut settings = settings();
+        settings.pools.push(PoolSettings { element: "Water".into(), level });
+        let (worldgen, map) = generate(settings);
+        let mut water = 0;
+        for x in MIN_X..=MAX_X {
+            let surface = worldgen.column(x).surface;
+            for y in surface + 1..=MAX_Y {
+                let expected = if y - ground <= level { ParticleElement::Water } else { ParticleElement::Air };
+                assert_eq!(map.get_element_at((x, y)), expected, "at {:?}", (x, y));
+                if expected == ParticleElement::Water {
+                    water += 1;
+                }
+            }
+        }
+        assert!(water > 0, "the pool is empty");
+    }
+}
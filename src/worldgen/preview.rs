@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use bevy::render::{
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+    texture::ImageSampler,
+};
+
+use super::WorldGen;
+use crate::entities::particle::resources::{MAX_X, MAX_Y, MIN_X, MIN_Y};
+
+/* Heightmap preview, F6 turns it on. Two heightmaps of the whole level next to each other in the top right,
+   the surface before the terrain was last reloaded on the left and after on the right (with the old surface
+   drawn over it in red), so a change to the noise settings shows up even if it's off screen.
+   Until the terrain is reloaded both sides are the same.
+*/
+const PREVIEW_W: u32 = 320; // pixels across one heightmap, each pixel is a few columns of the level
+const PREVIEW_H: u32 = 120;
+const PREVIEW_GAP: u32 = 8;
+const PREVIEW_SCALE: f32 = 1.5;
+
+const SKY: [u8; 4] = [15, 15, 30, 200];
+const GROUND_BEFORE: [u8; 4] = [150, 120, 90, 255];
+const GROUND_AFTER: [u8; 4] = [90, 170, 80, 255];
+const OLD_SURFACE: [u8; 4] = [230, 60, 40, 255];
+
+#[derive(Component)]
+pub struct HeightmapPreviewImage;
+
+#[derive(Resource, Default)]
+pub struct HeightmapPreview {
+    pub enabled: bool,
+    before: Vec<i32>,
+    after: Vec<i32>,
+    redraw: bool,
+    image: Option<(Entity, Handle<Image>)>,
+}
+
+// surface height for every pixel across the preview
+fn surfaces(worldgen: &WorldGen) -> Vec<i32> {
+    let columns = (MAX_X - MIN_X) as f32 / PREVIEW_W as f32;
+    (0..PREVIEW_W)
+        .map(|i| worldgen.column(MIN_X + (i as f32 * columns) as i32).surface)
+        .collect()
+}
+
+// row of the image a height is drawn at, 0 is the top of the level
+fn row(height: i32) -> u32 {
+    let f = (MAX_Y - height.clamp(MIN_Y, MAX_Y)) as f32 / (MAX_Y - MIN_Y) as f32;
+    ((f * (PREVIEW_H - 1) as f32) as u32).min(PREVIEW_H - 1)
+}
+
+impl HeightmapPreview {
+    pub fn compare(&mut self, before: &WorldGen, after: &WorldGen) {
+        self.before = surfaces(before);
+        self.after = surfaces(after);
+        self.redraw = true;
+    }
+
+    fn pixels(&self) -> Vec<u8> {
+        let width = PREVIEW_W * 2 + PREVIEW_GAP;
+        let mut data = vec![0u8; (width * PREVIEW_H * 4) as usize];
+        let mut put = |x: u32, y: u32, color: [u8; 4]| {
+            let i = ((y * width + x) * 4) as usize;
+            data[i..i + 4].copy_from_slice(&color);
+        };
+        for x in 0..PREVIEW_W {
+            let (before, after) = (row(self.before[x as usize]), row(self.after[x as usize]));
+            for y in 0..PREVIEW_H {
+                put(x, y, if y >= before { GROUND_BEFORE } else { SKY });
+                put(PREVIEW_W + PREVIEW_GAP + x, y, if y >= after { GROUND_AFTER } else { SKY });
+            }
+            put(PREVIEW_W + PREVIEW_GAP + x, before, OLD_SURFACE);
+        }
+        data
+    }
+}
+
+fn preview_image(data: Vec<u8>) -> Image {
+    let mut image = Image::new(
+        Extent3d { width: PREVIEW_W * 2 + PREVIEW_GAP, height: PREVIEW_H, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+pub fn toggle_heightmap_preview(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut preview: ResMut<HeightmapPreview>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F6) {
+        preview.enabled = !preview.enabled;
+    }
+}
+
+// the next planet starts out with both sides the same again
+pub fn reset_heightmap_preview(
+    mut commands: Commands,
+    mut preview: ResMut<HeightmapPreview>,
+) {
+    if let Some((entity, _)) = preview.image.take() {
+        commands.entity(entity).despawn();
+    }
+    preview.before.clear();
+}
+
+pub fn render_heightmap_preview(
+    mut commands: Commands,
+    images: Option<ResMut<Assets<Image>>>,
+    worldgen: Res<WorldGen>,
+    mut preview: ResMut<HeightmapPreview>,
+) {
+    if !preview.enabled {
+        if let Some((entity, _)) = preview.image.take() {
+            commands.entity(entity).despawn();
+        }
+        return
+    }
+    let Some(mut images) = images else {
+        return
+    };
+    if preview.before.is_empty() {
+        preview.compare(&worldgen, &worldgen);
+    }
+    if !preview.redraw && preview.image.is_some() {
+        return
+    }
+    preview.redraw = false;
+
+    let data = preview.pixels();
+    if let Some((_, handle)) = &preview.image {
+        if let Some(image) = images.get_mut(handle) {
+            image.data = data;
+        }
+        return
+    }
+    let handle = images.add(preview_image(data));
+    let entity = commands.spawn((
+        ImageBundle {
+            image: UiImage::new(handle.clone()),
+            style: Style {
+                width: Val::Px((PREVIEW_W * 2 + PREVIEW_GAP) as f32 * PREVIEW_SCALE),
+                height: Val::Px(PREVIEW_H as f32 * PREVIEW_SCALE),
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                right: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        },
+        HeightmapPreviewImage,
+    )).id();
+    preview.image = Some((entity, handle));
+}
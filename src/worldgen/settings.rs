@@ -0,0 +1,210 @@
+use serde::Deserialize;
+
+// Define structs --------------------------------------------------------------------------------
+// Everything a planet's terrain is made from. The passes that use each part are in passes.rs,
+// elements are given by their name in assets/default.elements.ron.
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct NoiseSettings {
+    /// The starting frequency of the noise function.
+    /// Determines the initial scale of the noise pattern.
+    /// Lower values result in broader, smoother features,
+    /// while higher values create finer, more frequent variations.
+    pub start_frequency: f32,
+
+    /// The number of octaves to use in the noise generation.
+    /// Each octave adds a layer of detail by superimposing noise at higher frequencies
+    /// and lower amplitudes. Increasing the number of octaves adds complexity
+    /// to the noise pattern.
+    pub octaves: usize,
+
+    /// Controls the amplitude scaling (persistence) for each successive octave.
+    /// It determines how quickly the amplitude decreases for higher octaves.
+    /// A lower persistence value results in a smoother noise (less influence from higher octaves),
+    /// while a higher value retains more detail from the higher-frequency octaves.
+    pub persistence: f32,
+
+    /// Adjusts the frequency for each successive octave.
+    /// This multiplier increases the frequency at each octave level,
+    /// allowing for finer details at higher octaves.
+    /// It controls how rapidly the frequency increases with each octave.
+    pub frequency_modifier: f32,
+
+    /// The minimum value of the mapped noise range.
+    /// After generating the raw noise values, they are mapped to a range between
+    /// `noise_range_min` and `noise_range_max`. This parameter sets the lower bound
+    /// of that range.
+    pub noise_range_min: f32,
+
+    /// The maximum value of the mapped noise range.
+    /// Sets the upper bound of the mapped noise values after scaling.
+    /// This allows you to control the maximum effect the noise can have on the terrain,
+    /// such as limiting the maximum height variation in a terrain layer.
+    pub noise_range_max: f32,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CaveSettings {
+    /// Controls the starting frequency of the 2D noise for cave generation.
+    /// Lower values will result in larger, more sparse caves; higher values will make caves more frequent and smaller.
+    pub start_frequency: f32,
+
+    /// The number of octaves used for the 2D noise.
+    /// More octaves add complexity and detail to the caves, but might also reduce large cave systems.
+    pub octaves: usize,
+
+    /// The persistence value of the cave noise function.
+    /// Determines how much each octave contributes to the final cave generation.
+    pub persistence: f32,
+
+    /// Frequency modifier that adjusts how rapidly the frequency increases per octave.
+    pub frequency_modifier: f32,
+
+    /// The minimum Y-coordinate for cave placement.
+    pub min_y: i32,
+
+    /// The maximum Y-coordinate for cave placement.
+    pub max_y: i32,
+
+    /// Minimum threshold value for cave generation.
+    /// Only cave cells that have a noise value above this will be placed.
+    pub noise_threshold_min: f32,
+
+    /// Maximum threshold value for cave generation.
+    /// Caves will not be placed if the noise value exceeds this threshold.
+    pub noise_threshold_max: f32,
+}
+
+// caves grown with common/cellular_automata.rs instead of cut out of noise, between min_y and max_y. They're
+// added to the noise caves (if there are any) and then cleaned up so every cave can be reached from the surface,
+// see caves.rs. It's all worked out for the whole level at once, so outside of the level (the chunks of an
+// endless planet) there are only the noise caves
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CellularCaveSettings {
+    pub wall_probability: f32,  // chance a cell starts out as rock
+    pub steps: usize,           // how many times the automaton runs, more steps smooths the caves out
+    pub birth_limit: usize,     // an open cell with at least this many rock neighbours fills in
+    pub survival_limit: usize,  // a rock cell with fewer rock neighbours than this opens up
+    pub min_y: i32,             // the automaton only runs between these two
+    pub max_y: i32,
+    pub min_pocket: usize,      // caves that can't be reached and are smaller than this (in cells) are filled in
+    pub tunnel_radius: i32,     // bigger caves get a tunnel this wide (from the middle) dug to one that can be
+                                // reached, never less than the player fits through (see caves.rs)
+}
+
+// cells between this layer's height and the next one up are made of `element`.
+// a cell goes to the first layer (top to bottom) it's above the height of, the last layer fills in the rest
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct LayerSettings {
+    pub element: String,
+    pub noise_settings: NoiseSettings,
+}
+
+// veins of `element` running through `replaces`, wherever the ore's own 2D noise is above `threshold`
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct OreSettings {
+    pub element: String,
+    pub replaces: String,
+    pub start_frequency: f32,
+    pub octaves: usize,
+    pub min_y: i32,
+    pub max_y: i32,
+    pub threshold: f32,  // range 0-1, the closer to 1 the rarer
+}
+
+// the open air above the surface is filled with `element` up to `level` cells above the ground,
+// so the low parts of the map turn into lakes
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct PoolSettings {
+    pub element: String,
+    pub level: i32,
+}
+
+// `on` cells with air at most `depth` cells above them turn into `into`, i.e. grass on top of dirt.
+// they're done in order, so a later one can put something under an earlier one
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct DecorationSettings {
+    pub on: String,
+    pub into: String,
+    pub depth: i32,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct WorldGenSettings {
+    #[serde(skip)]
+    pub name: String,         // stream the planet's noise comes from (see common/seed.rs), the planet's name
+    pub ground: i32,          // y the heightmap and the layer heights are measured up from
+    pub height: NoiseSettings,  // how far above the ground the surface is
+    pub layers: Vec<LayerSettings>,  // in order from top to bottom
+    pub caves: Option<CaveSettings>,  // "caves: None" to disable caves
+    pub cellular_caves: Option<CellularCaveSettings>,
+    pub ores: Vec<OreSettings>,
+    pub pools: Vec<PoolSettings>,
+    pub decorations: Vec<DecorationSettings>,
+    pub chunked: bool,        // generate the chunks around the player as they move instead of the level all at once
+}
+
+// Parameter adjustment --------------------------------------------------------------------------------
+impl Default for NoiseSettings {
+    fn default() -> Self {
+        Self {
+            start_frequency: 0.05,
+            octaves: 3,
+            persistence: 0.5,
+            frequency_modifier: 1.2,
+            noise_range_min: 0.,
+            noise_range_max: 180.,
+        }
+    }
+}
+
+impl Default for CaveSettings {
+    fn default() -> Self {
+        Self {
+            start_frequency: 0.03,
+            octaves: 3,
+            persistence: 0.5,
+            frequency_modifier: 1.2,
+            min_y: -50,
+            max_y: 90,
+            noise_threshold_min: 0.45,
+            noise_threshold_max: 0.55,
+        }
+    }
+}
+
+impl Default for CellularCaveSettings {
+    fn default() -> Self {
+        Self {
+            wall_probability: 0.5,
+            steps: 5,
+            birth_limit: 5,
+            survival_limit: 4,
+            min_y: -50,
+            max_y: 90,
+            min_pocket: 150,
+            tunnel_radius: 7,  // the player is 8x13 cells
+        }
+    }
+}
+
+impl Default for WorldGenSettings {
+    fn default() -> Self {
+        Self {
+            name: "worldgen".into(),
+            ground: -90,
+            height: NoiseSettings::default(),
+            layers: Vec::new(),
+            caves: None,
+            cellular_caves: None,
+            ores: Vec::new(),
+            pools: Vec::new(),
+            decorations: Vec::new(),
+            chunked: false,
+        }
+    }
+}